@@ -0,0 +1,94 @@
+//! Character aliases
+//!
+//! `@alias LF=リーフォン` in the header lets step lines use `LF` instead of the
+//! full operator name. Aliases are expanded while parsing and written back
+//! when the combo is saved as text.
+
+use super::METADATA_MARKER;
+use serde::{Deserialize, Serialize};
+
+/// Directive name after the `@` marker
+const ALIAS_DIRECTIVE: &str = "alias";
+
+/// Short code for an operator name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CharacterAlias {
+    pub alias: String,
+    pub name: String,
+}
+
+/// Body of an `@alias ...` line, if `line` is one
+pub(super) fn alias_line(line: &str) -> Option<&str> {
+    let rest = line
+        .trim()
+        .strip_prefix(METADATA_MARKER)?
+        .strip_prefix(ALIAS_DIRECTIVE)?;
+    rest.starts_with(char::is_whitespace).then(|| rest.trim())
+}
+
+/// Parse `LF=リーフォン`; `None` when either side is empty or holds a separator
+pub(super) fn parse_alias(body: &str) -> Option<CharacterAlias> {
+    let (alias, name) = body.split_once('=')?;
+    let (alias, name) = (alias.trim(), name.trim());
+    let valid = |s: &str| !s.is_empty() && !s.contains([',', '|']);
+    (valid(alias) && valid(name)).then(|| CharacterAlias {
+        alias: alias.to_string(),
+        name: name.to_string(),
+    })
+}
+
+/// `@alias LF=リーフォン` line for `alias`
+pub(super) fn format_alias(alias: &CharacterAlias) -> String {
+    format!(
+        "{}{} {}={}",
+        METADATA_MARKER, ALIAS_DIRECTIVE, alias.alias, alias.name
+    )
+}
+
+/// Full name for a character field, or the field itself when it is no alias
+pub(super) fn expand<'a>(aliases: &'a [CharacterAlias], character: &'a str) -> &'a str {
+    aliases
+        .iter()
+        .find(|a| a.alias == character)
+        .map_or(character, |a| a.name.as_str())
+}
+
+/// Alias for a full name, or the name itself when it has none
+pub(super) fn abbreviate<'a>(aliases: &'a [CharacterAlias], character: &'a str) -> &'a str {
+    aliases
+        .iter()
+        .find(|a| a.name == character)
+        .map_or(character, |a| a.alias.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combo::{parse_combo_content, serialize_combo_file, ParseError};
+
+    #[test]
+    fn test_character_aliases() {
+        assert_eq!(alias_line(" @alias LF=リーフォン "), Some("LF=リーフォン"));
+        assert_eq!(alias_line("@aliases: x"), None);
+        assert!(parse_alias("LF").is_none());
+        assert!(parse_alias("=リーフォン").is_none());
+
+        let content = "@author: saica\n@alias LF=リーフォン\n@alias CH = チェン\n#,物理,,|\n2,LF,戦技,|\nE,CH,連携,|\n3,ポグ,戦技,|";
+        let combo = parse_combo_content(content).unwrap();
+        let characters: Vec<&str> = combo.steps().map(|s| s.character.as_str()).collect();
+        assert_eq!(characters, ["リーフォン", "チェン", "ポグ"]);
+        assert_eq!(combo.aliases.len(), 2);
+
+        let output = serialize_combo_file(&combo);
+        assert!(
+            output.contains("@author: saica\n@alias LF=リーフォン\n@alias CH=チェン\n#,物理,,|\n")
+        );
+        assert!(output.contains("\n2,LF,戦技,|\nE,CH,連携,|\n3,ポグ,戦技,|\n"));
+
+        // Aliases belong to the header
+        assert!(matches!(
+            parse_combo_content("#,物理,,|\n@alias LF=リーフォン\n2,LF,戦技,|"),
+            Err(ParseError::InvalidFormat { line: 2, .. })
+        ));
+    }
+}
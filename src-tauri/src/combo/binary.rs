@@ -0,0 +1,70 @@
+//! Compact binary encoding
+//!
+//! MessagePack with a short header, used for caches that are read at
+//! startup. Text files stay the source of truth: anything that does not
+//! decode (older layout, truncated file) is reported as missing so callers
+//! fall back to the text format.
+
+use super::{ComboFile, ParseError};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// File signature
+const MAGIC: &[u8; 4] = b"ACNB";
+
+/// Layout version; bump whenever a cached type changes shape
+const BINARY_VERSION: u8 = 1;
+
+/// Encode `value` with the binary header
+pub fn to_bytes<T: Serialize>(value: &T) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.push(BINARY_VERSION);
+    // Field names are kept so skipped and defaulted fields round-trip
+    if rmp_serde::encode::write_named(&mut bytes, value).is_err() {
+        bytes.truncate(MAGIC.len());
+    }
+    bytes
+}
+
+/// Decode bytes written by [`to_bytes`]; `None` for anything else
+pub fn from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Option<T> {
+    let payload = bytes.strip_prefix(MAGIC.as_slice())?;
+    let (&version, payload) = payload.split_first()?;
+    if version != BINARY_VERSION {
+        return None;
+    }
+    rmp_serde::from_slice(payload).ok()
+}
+
+/// Binary form of a combo
+pub fn encode_combo_binary(combo: &ComboFile) -> Vec<u8> {
+    to_bytes(combo)
+}
+
+/// Read a combo written by [`encode_combo_binary`]
+pub fn decode_combo_binary(bytes: &[u8]) -> Result<ComboFile, ParseError> {
+    from_bytes(bytes).ok_or_else(|| ParseError::IoError("Invalid binary combo".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combo::{parse_combo_content, serialize_combo_file};
+
+    #[test]
+    fn test_binary_round_trip() {
+        let content = "@author: saica\n@alias LF=リーフォン\n#,物理,,|\nGROUP\nU2:800,LF,必殺技,,,-100,ボス|\nE,チェン,連携,|\nENDGROUP\n?,続ける？,はい>1/いいえ,|";
+        let combo = parse_combo_content(content).unwrap();
+
+        let bytes = encode_combo_binary(&combo);
+        let decoded = decode_combo_binary(&bytes).unwrap();
+        assert_eq!(serialize_combo_file(&decoded), serialize_combo_file(&combo));
+        assert_eq!(decoded.step(0).unwrap().group_id, Some(1));
+
+        let mut stale = bytes.clone();
+        stale[MAGIC.len()] = BINARY_VERSION + 1;
+        assert!(decode_combo_binary(&stale).is_err());
+        assert!(decode_combo_binary(&bytes[..bytes.len() / 2]).is_err());
+        assert!(from_bytes::<ComboFile>(content.as_bytes()).is_none());
+    }
+}
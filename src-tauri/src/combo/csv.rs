@@ -0,0 +1,194 @@
+//! Spreadsheet (CSV) import
+//!
+//! Reads rotations planned in a spreadsheet and exported as CSV, e.g. from
+//! Excel. Key cells use the same tokens as combo files (`U2`, `E`, `#`, ...).
+
+use super::sp::parse_sp;
+use super::{parse_fields, parse_gap, ComboFile, ComboMetadata, InputType, ParseError};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Which CSV column holds each field (0-based)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CsvColumns {
+    pub key: usize,
+    pub character: usize,
+    pub skill_type: usize,
+    /// Memo column (`None` when the sheet has none)
+    pub memo: Option<usize>,
+    /// Timing gap column in milliseconds (`None` when the sheet has none)
+    pub gap: Option<usize>,
+    /// SP column (`-100`, `+20`; `None` when the sheet has none)
+    pub sp: Option<usize>,
+    /// Skip the first row (column titles)
+    pub has_header: bool,
+}
+
+impl Default for CsvColumns {
+    fn default() -> Self {
+        Self {
+            key: 0,
+            character: 1,
+            skill_type: 2,
+            memo: Some(3),
+            gap: None,
+            sp: None,
+            has_header: true,
+        }
+    }
+}
+
+/// Guess the delimiter from the first line
+///
+/// Excel writes `;` in locales that use a decimal comma, and tab-separated
+/// text is common when copying cells.
+fn detect_delimiter(first_line: &str) -> char {
+    [',', ';', '\t']
+        .into_iter()
+        .max_by_key(|&d| first_line.matches(d).count())
+        .filter(|&d| first_line.contains(d))
+        .unwrap_or(',')
+}
+
+/// Split CSV content into records of cells, with the 1-based line each starts on
+///
+/// Quoted cells may contain the delimiter, line breaks and `""` escapes.
+fn parse_records(content: &str, delimiter: char) -> Vec<(usize, Vec<String>)> {
+    let mut records = Vec::new();
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut start_line = 1;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                cell.push('"');
+            }
+            '"' => in_quotes = !in_quotes,
+            '\n' if !in_quotes => {
+                cells.push(std::mem::take(&mut cell));
+                records.push((start_line, std::mem::take(&mut cells)));
+                line += 1;
+                start_line = line;
+            }
+            '\r' if !in_quotes => {}
+            c if c == delimiter && !in_quotes => cells.push(std::mem::take(&mut cell)),
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+                cell.push(c);
+            }
+        }
+    }
+    if !cell.is_empty() || !cells.is_empty() {
+        cells.push(cell);
+        records.push((start_line, cells));
+    }
+    records
+}
+
+/// Import a combo from CSV content
+///
+/// Rows with an empty key cell are skipped, so spacer rows in the sheet are
+/// fine. Error line numbers refer to the CSV file.
+pub fn import_csv(content: &str, columns: &CsvColumns) -> Result<ComboFile, ParseError> {
+    // Excel prefixes UTF-8 exports with a byte order mark
+    let content = content.trim_start_matches('\u{feff}');
+    let delimiter = detect_delimiter(content.lines().next().unwrap_or(""));
+
+    let mut commands = Vec::new();
+    let mut open_loops = Vec::new();
+    let skip = usize::from(columns.has_header);
+    for (line_number, cells) in parse_records(content, delimiter).into_iter().skip(skip) {
+        let cell = |index: usize| {
+            cells
+                .get(index)
+                .map(|c| c.trim().to_string())
+                .unwrap_or_default()
+        };
+        let key = cell(columns.key);
+        if key.is_empty() {
+            continue;
+        }
+
+        let source = cells.join(&delimiter.to_string());
+        let memo = columns.memo.map(cell).unwrap_or_default();
+        let mut cmd = parse_fields(
+            &key,
+            cell(columns.character),
+            cell(columns.skill_type),
+            memo,
+            line_number,
+            &source,
+        )?;
+        let gap = columns.gap.map(cell).unwrap_or_default();
+        cmd.gap_ms = parse_gap(&gap, line_number, &source)?;
+        let sp = columns.sp.map(cell).unwrap_or_default();
+        (cmd.sp_cost, cmd.sp_gain) = parse_sp(&sp, line_number, &source)?;
+        match cmd.input_type {
+            InputType::LoopStart { .. } => open_loops.push((line_number, source)),
+            InputType::LoopEnd if open_loops.pop().is_none() => {
+                return Err(ParseError::InvalidFormat {
+                    line: line_number,
+                    content: source,
+                });
+            }
+            _ => {}
+        }
+        commands.push(Arc::new(cmd));
+    }
+
+    if let Some((line, content)) = open_loops.pop() {
+        return Err(ParseError::InvalidFormat { line, content });
+    }
+    if commands.is_empty() {
+        return Err(ParseError::EmptyFile);
+    }
+
+    let mut combo = ComboFile {
+        title: String::new(),
+        metadata: ComboMetadata::default(),
+        aliases: Vec::new(),
+        commands,
+        footer: Vec::new(),
+    };
+    combo.refresh_title();
+    combo.validate_branches()?;
+    Ok(combo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combo::KeyIdentifier;
+
+    #[test]
+    fn test_import_excel_csv() {
+        let content = "\u{feff}キー;キャラ;技;メモ\r\n#;物理;;\r\nU2;リーフォン;必殺技;\"溜めて;\"\"最速\"\"\"\r\n;;;\r\nE;チェン;連携;\"2行\nメモ\"\r\n";
+        let combo = import_csv(content, &CsvColumns::default()).unwrap();
+
+        assert_eq!(combo.title, "物理");
+        assert_eq!(combo.step_count(), 2);
+        assert_eq!(combo.step(0).unwrap().memo, "溜めて;\"最速\"");
+        assert_eq!(combo.step(1).unwrap().key, KeyIdentifier::Chain);
+        assert_eq!(combo.step(1).unwrap().memo, "2行\nメモ");
+
+        let columns = CsvColumns {
+            key: 1,
+            character: 0,
+            memo: None,
+            has_header: false,
+            ..CsvColumns::default()
+        };
+        match import_csv("リーフォン,2,戦技\nチェン,X,連携", &columns) {
+            Err(ParseError::InvalidKey { line, .. }) => assert_eq!(line, 2),
+            other => panic!("unexpected result: {:?}", other.map(|c| c.title)),
+        }
+    }
+}
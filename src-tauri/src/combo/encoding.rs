@@ -0,0 +1,85 @@
+//! Text encoding detection
+//!
+//! Combo files written in older Japanese editors are often Shift-JIS or
+//! UTF-16 (Notepad's "Unicode"). Files are decoded by their byte order mark,
+//! then as UTF-8, and finally as Shift-JIS.
+
+use super::ParseError;
+use encoding_rs::{Encoding, SHIFT_JIS, UTF_16BE, UTF_16LE};
+use std::path::Path;
+
+/// Guess UTF-16 without a byte order mark from NUL bytes in ASCII text
+fn bomless_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    if bytes.len() < 2 || !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let nul_at = |parity: usize| {
+        bytes
+            .iter()
+            .skip(parity)
+            .step_by(2)
+            .filter(|&&b| b == 0)
+            .count()
+    };
+    // ASCII characters put a NUL in the high byte; a few NULs on the other
+    // side come from characters such as U+3000 (full-width space)
+    let (even, odd) = (nul_at(0), nul_at(1));
+    let enough = bytes.len() / 8;
+    if odd > enough && even * 4 < odd {
+        Some(UTF_16LE)
+    } else if even > enough && odd * 4 < even {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// Decode file content, dropping any byte order mark
+pub fn decode_text(bytes: &[u8]) -> String {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        return encoding
+            .decode_without_bom_handling(&bytes[bom_len..])
+            .0
+            .into_owned();
+    }
+    if let Some(encoding) = bomless_utf16(bytes) {
+        return encoding.decode_without_bom_handling(bytes).0.into_owned();
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => SHIFT_JIS.decode_without_bom_handling(bytes).0.into_owned(),
+    }
+}
+
+/// Read a text file in any of the supported encodings
+pub fn read_text<P: AsRef<Path>>(path: P) -> Result<String, ParseError> {
+    let bytes = std::fs::read(path).map_err(|e| ParseError::IoError(e.to_string()))?;
+    Ok(decode_text(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_text() {
+        let text = "#,物理,,|\n2,リーフォン,戦技,|";
+        assert_eq!(decode_text(text.as_bytes()), text);
+
+        let mut bom = vec![0xEF, 0xBB, 0xBF];
+        bom.extend_from_slice(text.as_bytes());
+        assert_eq!(decode_text(&bom), text);
+
+        let (sjis, _, _) = SHIFT_JIS.encode(text);
+        assert_eq!(decode_text(&sjis), text);
+
+        let utf16: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let mut with_bom = vec![0xFF, 0xFE];
+        with_bom.extend_from_slice(&utf16);
+        assert_eq!(decode_text(&with_bom), text);
+        assert_eq!(decode_text(&utf16), text);
+
+        let utf16be: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(decode_text(&utf16be), text);
+    }
+}
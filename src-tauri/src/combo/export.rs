@@ -0,0 +1,271 @@
+//! Cheat sheet export
+//!
+//! Renders a combo as a Markdown table (for Discord and similar) or as a
+//! standalone HTML page for printing.
+
+use super::{chord_label, direction_prefix, key_label, ComboCommand, ComboFile, InputType};
+use serde::{Deserialize, Serialize};
+
+/// Cheat sheet format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+}
+
+/// Table column titles
+const COLUMNS: [&str; 5] = ["#", "キー", "キャラクター", "技種", "メモ"];
+
+/// One rendered table row
+enum SheetRow {
+    /// `#` title line
+    Section(String),
+    /// Loop marker or other note spanning the table
+    Note(String),
+    Step {
+        number: usize,
+        key: String,
+        /// Hold badge text (`None` for taps)
+        hold: Option<String>,
+        character: String,
+        skill: String,
+        memo: String,
+    },
+}
+
+/// Key column text (without the hold badge)
+fn key_text(cmd: &ComboCommand) -> String {
+    let key = match &cmd.input_type {
+        InputType::Chord(keys) => chord_label(keys),
+        InputType::Branch { .. } => "?".to_string(),
+        InputType::Wait { .. } => "WAIT".to_string(),
+        _ => key_label(&cmd.key),
+    };
+    format!("{}{}", direction_prefix(cmd.direction), key)
+}
+
+/// Rows of the cheat sheet; comments are left out
+fn sheet_rows(combo: &ComboFile) -> Vec<SheetRow> {
+    let mut rows = Vec::new();
+    let mut number = 0;
+    for cmd in &combo.commands {
+        if cmd.is_title {
+            rows.push(SheetRow::Section(cmd.character.clone()));
+            continue;
+        }
+        match &cmd.input_type {
+            InputType::Comment | InputType::Include { .. } => continue,
+            InputType::LoopStart { count } => {
+                rows.push(SheetRow::Note(format!("ループ ×{} 開始", count)));
+                continue;
+            }
+            InputType::LoopEnd => {
+                rows.push(SheetRow::Note("ループ 終了".to_string()));
+                continue;
+            }
+            InputType::Info => {
+                rows.push(SheetRow::Note(cmd.memo.clone()));
+                continue;
+            }
+            _ => {}
+        }
+
+        number += 1;
+        let hold = match cmd.input_type {
+            InputType::Hold {
+                duration_ms: Some(duration_ms),
+            } => Some(format!("長押し {}ms", duration_ms)),
+            InputType::Hold { duration_ms: None } => Some("長押し".to_string()),
+            InputType::HoldStart => Some("押し始め".to_string()),
+            InputType::HoldRelease => Some("離す".to_string()),
+            InputType::ChargedHold { ref stages } => Some(format!(
+                "溜め {}ms",
+                stages
+                    .iter()
+                    .map(|ms| ms.to_string())
+                    .collect::<Vec<_>>()
+                    .join("/")
+            )),
+            InputType::Sequence { .. } => Some("順押し".to_string()),
+            InputType::DoubleTap { .. } => Some("2回押し".to_string()),
+            InputType::MultiTap { count, .. } => Some(format!("{}回押し", count)),
+            InputType::Wait { duration_ms } => Some(format!("待機 {}ms", duration_ms)),
+            _ => None,
+        };
+        let skill = match &cmd.input_type {
+            InputType::Branch { options } => options
+                .iter()
+                .map(|o| match o.target {
+                    Some(target) => format!("{} → {}", o.label, target + 1),
+                    None => o.label.clone(),
+                })
+                .collect::<Vec<_>>()
+                .join(" / "),
+            _ => cmd.skill_type.clone(),
+        };
+        rows.push(SheetRow::Step {
+            number,
+            key: key_text(cmd),
+            hold,
+            character: cmd.character.clone(),
+            skill,
+            memo: cmd.memo.clone(),
+        });
+    }
+    rows
+}
+
+/// Escape text for a Markdown table cell
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', "<br>")
+}
+
+/// Escape text for HTML
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\n', "<br>")
+}
+
+/// Render as a Markdown table
+pub fn render_markdown(combo: &ComboFile) -> String {
+    let mut output = format!("## {}\n\n", escape_markdown(&combo.title));
+    output.push_str(&format!("| {} |\n", COLUMNS.join(" | ")));
+    output.push_str(&format!("|{}\n", "---|".repeat(COLUMNS.len())));
+
+    // The first title line is already the heading
+    let mut rows = sheet_rows(combo).into_iter().peekable();
+    if matches!(rows.peek(), Some(SheetRow::Section(_))) {
+        rows.next();
+    }
+
+    for row in rows {
+        let line = match row {
+            SheetRow::Section(name) => format!("| | **{}** | | | |", escape_markdown(&name)),
+            SheetRow::Note(note) => format!("| | *{}* | | | |", note),
+            SheetRow::Step {
+                number,
+                key,
+                hold,
+                character,
+                skill,
+                memo,
+            } => {
+                let key = match hold {
+                    Some(hold) => format!("`{}` **{}**", key, hold),
+                    None => format!("`{}`", key),
+                };
+                format!(
+                    "| {} | {} | {} | {} | {} |",
+                    number,
+                    key,
+                    escape_markdown(&character),
+                    escape_markdown(&skill),
+                    escape_markdown(&memo)
+                )
+            }
+        };
+        output.push_str(&line);
+        output.push('\n');
+    }
+    output
+}
+
+/// Page style of the HTML cheat sheet
+const HTML_STYLE: &str = "body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #999; padding: 4px 10px; text-align: left; }
+th { background: #eee; }
+tr.section td { background: #dde6f5; font-weight: bold; }
+tr.note td { color: #666; font-style: italic; }
+kbd { font-family: monospace; font-weight: bold; }
+.hold { background: #c0392b; color: #fff; border-radius: 4px; padding: 0 6px; margin-left: 6px; font-size: 0.85em; }";
+
+/// Render as a standalone HTML page
+pub fn render_html(combo: &ComboFile) -> String {
+    let title = escape_html(&combo.title);
+    let mut output = format!(
+        "<!DOCTYPE html>\n<html lang=\"ja\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n<h1>{}</h1>\n<table>\n<tr>",
+        title, HTML_STYLE, title
+    );
+    for column in COLUMNS {
+        output.push_str(&format!("<th>{}</th>", column));
+    }
+    output.push_str("</tr>\n");
+
+    let span = COLUMNS.len();
+    for row in sheet_rows(combo) {
+        let line = match row {
+            SheetRow::Section(name) => format!(
+                "<tr class=\"section\"><td colspan=\"{}\">{}</td></tr>",
+                span,
+                escape_html(&name)
+            ),
+            SheetRow::Note(note) => format!(
+                "<tr class=\"note\"><td colspan=\"{}\">{}</td></tr>",
+                span,
+                escape_html(&note)
+            ),
+            SheetRow::Step {
+                number,
+                key,
+                hold,
+                character,
+                skill,
+                memo,
+            } => {
+                let hold = hold
+                    .map(|hold| format!("<span class=\"hold\">{}</span>", hold))
+                    .unwrap_or_default();
+                format!(
+                    "<tr><td>{}</td><td><kbd>{}</kbd>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    number,
+                    escape_html(&key),
+                    hold,
+                    escape_html(&character),
+                    escape_html(&skill),
+                    escape_html(&memo)
+                )
+            }
+        };
+        output.push_str(&line);
+        output.push('\n');
+    }
+
+    output.push_str("</table>\n</body>\n</html>\n");
+    output
+}
+
+/// Render in the given format
+pub fn export_combo(combo: &ComboFile, format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Markdown => render_markdown(combo),
+        ExportFormat::Html => render_html(combo),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combo::parse_combo_content;
+
+    #[test]
+    fn test_render_markdown_and_html() {
+        let content = "#,物理,,|\n// メモ\nU2:800,リーフォン,必殺技,a|b|\n#,ループ,,|\nLOOP 2\nE,チェン,連携,|\nENDLOOP";
+        let combo = parse_combo_content(content).unwrap();
+
+        let markdown = render_markdown(&combo);
+        assert!(markdown.starts_with("## 物理\n\n| # | キー |"));
+        assert!(markdown.contains("| 1 | `2` **長押し 800ms** | リーフォン | 必殺技 | a\\|b |"));
+        assert!(markdown.contains("| | **ループ** | | | |"));
+        assert!(markdown.contains("| 2 | `E` | チェン | 連携 |  |"));
+        assert!(!markdown.contains("メモ |\n| 1"));
+
+        let html = render_html(&combo);
+        assert!(html.contains("<kbd>2</kbd><span class=\"hold\">長押し 800ms</span>"));
+        assert!(html.contains("<td colspan=\"5\">ループ ×2 開始</td>"));
+    }
+}
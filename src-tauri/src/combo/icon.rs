@@ -0,0 +1,104 @@
+//! Step icons
+//!
+//! The eighth column of a row names a picture for the overlay: either an
+//! image path relative to the combo file (`icons/lifeng.png`) or an icon id
+//! (`lifeng`) looked up in the `icons` folder of the combo library.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::path::{Component, Path, PathBuf};
+
+/// Image types the overlay can show, by extension
+const IMAGE_TYPES: [(&str, &str); 5] = [
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("webp", "image/webp"),
+    ("gif", "image/gif"),
+];
+
+/// Extension of icon ids in the library `icons` folder
+const ICON_ID_EXTENSION: &str = "png";
+
+/// MIME type of an image path, `None` for unsupported types
+fn image_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    IMAGE_TYPES
+        .iter()
+        .find(|(ext, _)| *ext == extension)
+        .map(|(_, mime)| *mime)
+}
+
+/// Whether `icon` is an image path (as opposed to an icon id)
+fn is_path(icon: &str) -> bool {
+    icon.contains(['/', '\\', '.'])
+}
+
+/// Whether an icon column value is usable
+///
+/// Paths must be relative, stay below the combo file's folder and point to a
+/// supported image type; ids may use letters, digits, `_` and `-`.
+pub fn is_valid_icon(icon: &str) -> bool {
+    if !is_path(icon) {
+        return !icon.is_empty()
+            && icon
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+    }
+    let path = Path::new(icon);
+    path.components().all(|c| matches!(c, Component::Normal(_))) && image_type(path).is_some()
+}
+
+/// File of `icon` for a combo in `combo_dir`, with icon ids looked up in
+/// `icon_dir`
+pub fn resolve_icon(icon: &str, combo_dir: &Path, icon_dir: &Path) -> PathBuf {
+    if is_path(icon) {
+        combo_dir.join(icon.replace('\\', "/"))
+    } else {
+        icon_dir.join(format!("{}.{}", icon, ICON_ID_EXTENSION))
+    }
+}
+
+/// Read an image as a `data:` URL the webview can show directly
+pub fn icon_data_url(path: &Path) -> std::io::Result<String> {
+    let mime = image_type(path).ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "unsupported image type")
+    })?;
+    let bytes = std::fs::read(path)?;
+    Ok(format!("data:{};base64,{}", mime, STANDARD.encode(bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_icon_references() {
+        assert!(is_valid_icon("lifeng"));
+        assert!(is_valid_icon("リーフォン"));
+        assert!(is_valid_icon("icons/lifeng.PNG"));
+        assert!(!is_valid_icon("../secret.png"));
+        assert!(!is_valid_icon("/etc/lifeng.png"));
+        assert!(!is_valid_icon("icons/lifeng.txt"));
+        assert!(!is_valid_icon("life feng"));
+
+        let combo = Path::new("combos");
+        let icons = Path::new("combos/icons");
+        assert_eq!(
+            resolve_icon("img/lifeng.png", combo, icons),
+            Path::new("combos/img/lifeng.png")
+        );
+        assert_eq!(
+            resolve_icon("lifeng", combo, icons),
+            Path::new("combos/icons/lifeng.png")
+        );
+
+        let file = std::env::temp_dir().join("akef_icon_test.png");
+        std::fs::write(&file, [0x89, b'P', b'N', b'G']).unwrap();
+        assert_eq!(
+            icon_data_url(&file).unwrap(),
+            "data:image/png;base64,iVBORw=="
+        );
+        let _ = std::fs::remove_file(file);
+    }
+}
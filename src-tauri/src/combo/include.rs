@@ -0,0 +1,196 @@
+//! Sub-combo includes
+//!
+//! `@include opener.txt` splices the steps of another combo file, so a shared
+//! opener can live in one file. Include rows are kept as they are while
+//! editing and resolved when a combo is loaded for playback. Paths are
+//! relative to the including file.
+
+use super::{parse_combo_file, ComboCommand, ComboFile, InputType, ParseError, METADATA_MARKER};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Directive name after the `@` marker
+const INCLUDE_DIRECTIVE: &str = "include";
+
+/// Path of an `@include path` line, if `line` is one
+pub(super) fn include_line(line: &str) -> Option<&str> {
+    let rest = line
+        .trim()
+        .strip_prefix(METADATA_MARKER)?
+        .strip_prefix(INCLUDE_DIRECTIVE)?;
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let path = rest.trim();
+    (!path.is_empty()).then_some(path)
+}
+
+/// `@include path` line for `path`
+pub(super) fn format_include(path: &str) -> String {
+    format!("{}{} {}", METADATA_MARKER, INCLUDE_DIRECTIVE, path)
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Copy of `cmd` with its branch targets passed through `map`
+pub(super) fn remap_targets(
+    cmd: &Arc<ComboCommand>,
+    map: impl Fn(usize) -> usize,
+) -> Arc<ComboCommand> {
+    let InputType::Branch { options } = &cmd.input_type else {
+        return cmd.clone();
+    };
+    let mut options = options.clone();
+    for option in &mut options {
+        option.target = option.target.map(&map);
+    }
+    Arc::new(ComboCommand {
+        input_type: InputType::Branch { options },
+        ..(**cmd).clone()
+    })
+}
+
+/// Replace the include rows of `commands` (read from `file`)
+///
+/// `stack` holds the files currently being included, to detect cycles.
+fn splice(
+    commands: &[Arc<ComboCommand>],
+    file: &Path,
+    stack: &mut Vec<PathBuf>,
+) -> Result<Vec<Arc<ComboCommand>>, ParseError> {
+    let dir = file.parent().unwrap_or(Path::new(""));
+    let mut output = Vec::new();
+    // Step index in `commands` -> step index in `output`
+    let mut step_map = Vec::new();
+    // Rows of `output` that are branches of this file
+    let mut own_branches = Vec::new();
+    let mut steps = 0;
+    // Cancel windows of included files get ids above every id used so far
+    let mut group_base = commands
+        .iter()
+        .filter_map(|c| c.group_id)
+        .max()
+        .unwrap_or(0);
+
+    for cmd in commands {
+        let InputType::Include { path } = &cmd.input_type else {
+            if cmd.is_step() {
+                step_map.push(steps);
+                steps += 1;
+            }
+            if matches!(cmd.input_type, InputType::Branch { .. }) {
+                own_branches.push(output.len());
+            }
+            output.push(cmd.clone());
+            continue;
+        };
+
+        let target = dir.join(path);
+        let key = canonical(&target);
+        if stack.contains(&key) {
+            return Err(ParseError::IncludeCycle(path.clone()));
+        }
+        let included = parse_combo_file(&target).map_err(|e| ParseError::InvalidInclude {
+            path: path.clone(),
+            message: e.to_string(),
+        })?;
+
+        stack.push(key);
+        let spliced = splice(&included.commands, &target, stack)?;
+        stack.pop();
+
+        // Branches of the included file point into its own steps
+        let offset = steps;
+        let shift = group_base;
+        for cmd in spliced.iter().filter(|c| !c.is_title) {
+            if cmd.is_step() {
+                steps += 1;
+            }
+            let mut cmd = remap_targets(cmd, |t| t + offset);
+            if let Some(id) = cmd.group_id {
+                Arc::make_mut(&mut cmd).group_id = Some(id + shift);
+                group_base = group_base.max(id + shift);
+            }
+            output.push(cmd);
+        }
+    }
+
+    for row in own_branches {
+        output[row] = remap_targets(&output[row], |t| step_map.get(t).copied().unwrap_or(t));
+    }
+    Ok(output)
+}
+
+/// Splice the steps of every `@include`d file into `combo`
+///
+/// `file` is the path `combo` was read from. Title rows of included files
+/// are left out and branch targets are renumbered.
+pub fn resolve_includes(combo: &mut ComboFile, file: &Path) -> Result<(), ParseError> {
+    let has_includes = combo
+        .commands
+        .iter()
+        .any(|cmd| matches!(cmd.input_type, InputType::Include { .. }));
+    if !has_includes {
+        return Ok(());
+    }
+
+    let mut stack = vec![canonical(file)];
+    combo.commands = splice(&combo.commands, file, &mut stack)?;
+    combo.renumber_groups();
+    combo.validate_branches()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combo::{parse_combo_content, parse_combo_library_file, serialize_combo_file};
+    use std::fs;
+
+    #[test]
+    fn test_resolve_includes() {
+        let dir = std::env::temp_dir().join("akef_combonavi_include_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("shared")).unwrap();
+        fs::write(
+            dir.join("shared/opener.txt"),
+            "#,オープナー,,|\n?,必殺技は使える？,はい>2/いいえ,|\nU2,リーフォン,必殺技,|",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("main.txt"),
+            "#,物理,,|\n@include shared/opener.txt\n?,続ける？,はい>2/いいえ>3,|\nE,チェン,連携,|\n3,ポグ,戦技,|",
+        )
+        .unwrap();
+
+        // The editor keeps the directive
+        let content = fs::read_to_string(dir.join("main.txt")).unwrap();
+        let raw = parse_combo_content(&content).unwrap();
+        assert_eq!(raw.step_count(), 3);
+        assert!(serialize_combo_file(&raw).contains("\n@include shared/opener.txt\n"));
+
+        let library = parse_combo_library_file(dir.join("main.txt")).unwrap();
+        let combo = &library.combos[0].combo;
+        assert_eq!(combo.title, "物理");
+        assert_eq!(combo.step_count(), 5);
+        let targets = |index: usize| match &combo.step(index).unwrap().input_type {
+            InputType::Branch { options } => options.iter().map(|o| o.target).collect::<Vec<_>>(),
+            _ => panic!("step {} is not a branch", index),
+        };
+        assert_eq!(targets(0), [Some(1), None]);
+        assert_eq!(targets(2), [Some(3), Some(4)]);
+
+        fs::write(
+            dir.join("shared/opener.txt"),
+            "@include ../main.txt\n1,管理人,戦技,|",
+        )
+        .unwrap();
+        assert!(matches!(
+            parse_combo_library_file(dir.join("main.txt")),
+            Err(ParseError::IncludeCycle(_))
+        ));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
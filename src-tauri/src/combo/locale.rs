@@ -0,0 +1,65 @@
+//! Localized memos
+//!
+//! A memo written as `{ja=溜めてから, en=Charge first}` holds one text per
+//! language. The overlay shows the variant for the configured language, so a
+//! single shared file works for players of every language.
+
+/// Language used when the configured one has no variant
+const FALLBACK_LANGUAGE: &str = "ja";
+
+/// `(language, text)` pairs of a localized memo, or `None` for plain text
+fn variants(memo: &str) -> Option<Vec<(&str, &str)>> {
+    let body = memo.trim().strip_prefix('{')?.strip_suffix('}')?;
+    body.split(',')
+        .map(|entry| {
+            let (language, text) = entry.split_once('=')?;
+            let language = language.trim();
+            (!language.is_empty()).then(|| (language, text.trim()))
+        })
+        .collect()
+}
+
+/// Memo text for `language` (`ja`, `en`, ...)
+///
+/// Falls back to Japanese, then to the first variant. Plain memos are
+/// returned as they are.
+pub fn localize<'a>(memo: &'a str, language: &str) -> &'a str {
+    let Some(variants) = variants(memo) else {
+        return memo;
+    };
+    let find = |code: &str| {
+        variants
+            .iter()
+            .find(|(l, _)| l.eq_ignore_ascii_case(code))
+            .map(|(_, text)| *text)
+    };
+    find(language)
+        .or_else(|| find(FALLBACK_LANGUAGE))
+        .or_else(|| variants.first().map(|(_, text)| *text))
+        .unwrap_or(memo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combo::{parse_combo_content, serialize_combo_file};
+
+    #[test]
+    fn test_localize_memo() {
+        let combo = parse_combo_content("2,リーフォン,戦技,{ja=溜めてから, en=Charge first},1200|")
+            .unwrap();
+        let step = combo.step(0).unwrap();
+        assert_eq!(step.memo, "{ja=溜めてから, en=Charge first}");
+        assert_eq!(step.gap_ms, Some(1200));
+        assert!(
+            serialize_combo_file(&combo).contains("戦技,{ja=溜めてから, en=Charge first},1200|")
+        );
+
+        assert_eq!(localize(&step.memo, "en"), "Charge first");
+        assert_eq!(localize(&step.memo, "ja"), "溜めてから");
+        assert_eq!(localize(&step.memo, "zh-Hans"), "溜めてから");
+        assert_eq!(localize("{en=Dodge}", "ja"), "Dodge");
+        assert_eq!(localize("溜める", "en"), "溜める");
+        assert_eq!(localize("{not localized}", "en"), "{not localized}");
+    }
+}
@@ -0,0 +1,162 @@
+//! Merging combo files
+//!
+//! Combines the phases (`#` sections) of two combos, e.g. one player's opener
+//! with another's burst window. Branch targets follow their steps, and phases
+//! of the second file whose title is already taken get a numbered suffix.
+
+use super::include::remap_targets;
+use super::{parse_combo_content, serialize_combo_file, ComboCommand, ComboFile, ParseError};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// How the phases of the two files are ordered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStrategy {
+    /// Every phase of the base file, then every phase of the other
+    #[default]
+    Concatenate,
+    /// Alternate phases, starting with the base file
+    Interleave,
+}
+
+/// Split rows at title rows; rows before the first title join the first phase
+fn phases(combo: &ComboFile) -> Vec<Vec<Arc<ComboCommand>>> {
+    let mut phases: Vec<Vec<Arc<ComboCommand>>> = Vec::new();
+    for cmd in &combo.commands {
+        match phases.last_mut() {
+            Some(phase) if !cmd.is_title || phase.iter().all(|c| !c.is_title) => {
+                phase.push(cmd.clone())
+            }
+            _ => phases.push(vec![cmd.clone()]),
+        }
+    }
+    phases
+}
+
+/// `title`, or `title (n)` with the lowest `n` not in `taken`
+fn unique_title(title: &str, taken: &[String]) -> String {
+    if !taken.iter().any(|t| t == title) {
+        return title.to_string();
+    }
+    (2..)
+        .map(|n| format!("{} ({})", title, n))
+        .find(|candidate| !taken.contains(candidate))
+        .expect("some suffix is free")
+}
+
+/// Merge `other` into a copy of `base`
+///
+/// The result keeps the header of `base`; aliases of `other` are added unless
+/// their short code is taken. Fails when the merged rows no longer form a
+/// valid combo (e.g. a loop that spans phases was split apart).
+pub fn merge_combos(
+    base: &ComboFile,
+    other: &ComboFile,
+    strategy: MergeStrategy,
+) -> Result<ComboFile, ParseError> {
+    let sources = [phases(base), phases(other)];
+    let order: Vec<(usize, usize)> = match strategy {
+        MergeStrategy::Concatenate => (0..sources[0].len())
+            .map(|i| (0, i))
+            .chain((0..sources[1].len()).map(|i| (1, i)))
+            .collect(),
+        MergeStrategy::Interleave => (0..sources[0].len().max(sources[1].len()))
+            .flat_map(|i| [(0, i), (1, i)])
+            .filter(|&(file, i)| i < sources[file].len())
+            .collect(),
+    };
+
+    // Old step index -> merged step index, per file
+    let mut step_maps = [Vec::new(), Vec::new()];
+    let mut rows = Vec::new();
+    let mut titles: Vec<String> = base
+        .commands
+        .iter()
+        .filter(|c| c.is_title)
+        .map(|c| c.character.clone())
+        .collect();
+    let group_base = base
+        .commands
+        .iter()
+        .filter_map(|c| c.group_id)
+        .max()
+        .unwrap_or(0);
+    let mut steps = 0;
+
+    for (file, phase) in order {
+        for cmd in &sources[file][phase] {
+            let mut cmd = cmd.clone();
+            if file == 1 {
+                if cmd.is_title {
+                    let title = unique_title(&cmd.character, &titles);
+                    if title != cmd.character {
+                        let row = Arc::make_mut(&mut cmd);
+                        row.character = title.clone();
+                        row.source = None;
+                    }
+                    titles.push(title);
+                }
+                if let Some(id) = cmd.group_id {
+                    Arc::make_mut(&mut cmd).group_id = Some(id + group_base);
+                }
+            }
+            if cmd.is_step() {
+                step_maps[file].push(steps);
+                steps += 1;
+            }
+            rows.push((file, cmd));
+        }
+    }
+
+    let mut merged = base.clone();
+    merged.commands = rows
+        .into_iter()
+        .map(|(file, cmd)| {
+            let map = &step_maps[file];
+            remap_targets(&cmd, |t| map.get(t).copied().unwrap_or(t))
+        })
+        .collect();
+    for alias in &other.aliases {
+        if !merged.aliases.iter().any(|a| a.alias == alias.alias) {
+            merged.aliases.push(alias.clone());
+        }
+    }
+    merged.footer.extend(other.footer.iter().cloned());
+    merged.renumber_groups();
+
+    // Round-trip through the text format to check loops and groups
+    parse_combo_content(&serialize_combo_file(&merged))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combo::InputType;
+
+    #[test]
+    fn test_merge_combos() {
+        let base = parse_combo_content(
+            "#,オープナー,,|\n2,リーフォン,戦技,|\n#,バースト,,|\n?,続ける？,はい>1/いいえ,|",
+        )
+        .unwrap();
+        let other = parse_combo_content("#,バースト,,|\nE,チェン,連携,|\n#,締め,,|\n3,ポグ,戦技,|")
+            .unwrap();
+
+        let merged = merge_combos(&base, &other, MergeStrategy::Concatenate).unwrap();
+        let titles: Vec<_> = merged.phases().into_iter().map(|p| p.name).collect();
+        assert_eq!(titles, ["オープナー", "バースト", "バースト (2)", "締め"]);
+        assert_eq!(merged.title, "オープナー");
+
+        let merged = merge_combos(&base, &other, MergeStrategy::Interleave).unwrap();
+        let titles: Vec<_> = merged.phases().into_iter().map(|p| p.name).collect();
+        assert_eq!(titles, ["オープナー", "バースト (2)", "バースト", "締め"]);
+        let characters: Vec<_> = merged.steps().map(|s| s.character.as_str()).collect();
+        assert_eq!(characters, ["リーフォン", "チェン", "続ける？", "ポグ"]);
+        // The branch still points at the base file's first step
+        match &merged.step(2).unwrap().input_type {
+            InputType::Branch { options } => assert_eq!(options[0].target, Some(0)),
+            other => panic!("expected a branch, got {:?}", other),
+        }
+    }
+}
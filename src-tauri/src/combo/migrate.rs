@@ -0,0 +1,166 @@
+//! Combo format versions and migrations
+//!
+//! Text combo files start with an `@version: N` header line. Files without
+//! one are version 1. Older files are upgraded line by line when parsed, and
+//! [`upgrade_content`] produces the rewritten file for saving back to disk.
+
+use super::{ParseError, METADATA_MARKER};
+use serde::{Deserialize, Serialize};
+
+/// Format version written by this build
+pub const CURRENT_FORMAT_VERSION: u32 = 2;
+
+/// Header key of the version line
+const VERSION_KEY: &str = "version";
+
+/// A single rewritten line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MigrationChange {
+    /// 1-based line number
+    pub line: usize,
+    pub before: String,
+    pub after: String,
+}
+
+/// What an upgrade changed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub changes: Vec<MigrationChange>,
+}
+
+/// Upgrade step from version `from` to `from + 1`
+struct Migration {
+    from: u32,
+    /// Rewrite a trimmed line; `None` keeps it unchanged
+    apply: fn(&str) -> Option<String>,
+}
+
+/// Migrations in version order
+const MIGRATIONS: &[Migration] = &[Migration {
+    from: 1,
+    apply: migrate_hold_keys,
+}];
+
+/// Version 1 -> 2: `hold-2` key tokens become `U2`
+fn migrate_hold_keys(line: &str) -> Option<String> {
+    const LEGACY_HOLD_PREFIX: &str = "hold-";
+    let prefix = line.get(..LEGACY_HOLD_PREFIX.len())?;
+    if !prefix.eq_ignore_ascii_case(LEGACY_HOLD_PREFIX) {
+        return None;
+    }
+    Some(format!("U{}", &line[LEGACY_HOLD_PREFIX.len()..]))
+}
+
+/// Value of a `@version: N` line, if `line` is one
+pub(super) fn version_line(line: &str) -> Option<&str> {
+    let (key, value) = line.trim().strip_prefix(METADATA_MARKER)?.split_once(':')?;
+    (key.trim() == VERSION_KEY).then(|| value.trim())
+}
+
+/// `@version: N` line for the current version
+pub(super) fn version_header() -> String {
+    format!(
+        "{}{}: {}",
+        METADATA_MARKER, VERSION_KEY, CURRENT_FORMAT_VERSION
+    )
+}
+
+/// Format version declared by `content` (1 when there is no version line)
+pub fn content_version(content: &str) -> Result<u32, ParseError> {
+    for (i, line) in content.lines().enumerate() {
+        if let Some(value) = version_line(line) {
+            let version = value
+                .parse::<u32>()
+                .ok()
+                .filter(|&v| v >= 1)
+                .ok_or_else(|| ParseError::InvalidFormat {
+                    line: i + 1,
+                    content: line.trim().to_string(),
+                })?;
+            if version > CURRENT_FORMAT_VERSION {
+                return Err(ParseError::UnsupportedVersion(version));
+            }
+            return Ok(version);
+        }
+    }
+    Ok(1)
+}
+
+/// Apply every migration needed by `content`, keeping line numbers intact
+///
+/// Returns `None` when the content is already current.
+pub fn migrate_lines(content: &str) -> Result<Option<(String, MigrationReport)>, ParseError> {
+    let from_version = content_version(content)?;
+    if from_version == CURRENT_FORMAT_VERSION {
+        return Ok(None);
+    }
+
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let mut changes = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| m.from >= from_version) {
+        for (i, line) in lines.iter_mut().enumerate() {
+            if let Some(after) = (migration.apply)(line.trim()) {
+                changes.push(MigrationChange {
+                    line: i + 1,
+                    before: line.clone(),
+                    after: after.clone(),
+                });
+                *line = after;
+            }
+        }
+    }
+
+    let report = MigrationReport {
+        from_version,
+        to_version: CURRENT_FORMAT_VERSION,
+        changes,
+    };
+    Ok(Some((lines.join("\n"), report)))
+}
+
+/// Upgrade `content` for writing back to disk, with a current version line
+///
+/// Returns `None` when the content is already current.
+pub fn upgrade_content(content: &str) -> Result<Option<(String, MigrationReport)>, ParseError> {
+    let Some((migrated, report)) = migrate_lines(content)? else {
+        return Ok(None);
+    };
+
+    let header = version_header();
+    let mut lines: Vec<&str> = migrated
+        .lines()
+        .filter(|line| version_line(line).is_none())
+        .collect();
+    lines.insert(0, &header);
+
+    let mut upgraded = lines.join("\n");
+    upgraded.push('\n');
+    Ok(Some((upgraded, report)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upgrade_rewrites_hold_keys() {
+        let content = "#,物理,,|\nhold-2,リーフォン,必殺技,|\n!!!!!";
+        let (upgraded, report) = upgrade_content(content).unwrap().unwrap();
+
+        assert_eq!(
+            upgraded,
+            "@version: 2\n#,物理,,|\nU2,リーフォン,必殺技,|\n!!!!!\n"
+        );
+        assert_eq!(report.from_version, 1);
+        assert_eq!(report.changes.len(), 1);
+        assert_eq!(report.changes[0].line, 2);
+
+        assert!(upgrade_content(&upgraded).unwrap().is_none());
+        assert!(matches!(
+            content_version("@version: 9\n2,リーフォン,戦技,|"),
+            Err(ParseError::UnsupportedVersion(9))
+        ));
+    }
+}
@@ -2,16 +2,95 @@
 //! 
 //! Handles parsing of the custom combo file format used by AKEF ComboNavi.
 
+pub mod alias;
+pub mod binary;
+pub mod csv;
+pub mod encoding;
+pub mod export;
+pub mod icon;
+pub mod include;
+pub mod locale;
+pub mod merge;
+pub mod migrate;
+pub mod roster;
+pub mod share;
+pub mod source;
+pub mod sp;
+pub mod stats;
+pub mod validate;
+
+pub use alias::CharacterAlias;
+pub use csv::{import_csv, CsvColumns};
+pub use export::{export_combo, ExportFormat};
+pub use merge::{merge_combos, MergeStrategy};
+pub use migrate::{MigrationReport, CURRENT_FORMAT_VERSION};
+pub use roster::Roster;
+pub use share::{decode_share_code, encode_share_code, encode_share_qr};
+pub use source::SourceLine;
+pub use sp::{project_sp, SpProjection};
+pub use stats::{stats, CharacterStats, ComboStats};
+pub use validate::{
+    check_roster, lint, save_errors, validate, validate_combo, ComboLintError, Diagnostic, Severity,
+};
+
 use serde::{Deserialize, Serialize};
 use std::path::Path;
+use std::sync::Arc;
 
 /// Input type for a combo command
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum InputType {
     /// Normal tap input - triggered on key down
     Tap,
-    /// Hold input - requires holding key for specified duration
-    Hold { duration_ms: u64 },
+    /// Hold input - requires holding key for the given duration (`U2:800`),
+    /// or for the configured hold threshold when `None` (`U2`)
+    Hold { duration_ms: Option<u64> },
+    /// Start holding the key (`+2`); done once it is pressed, and the key
+    /// stays held through the following steps
+    HoldStart,
+    /// Let go of a key held since a `HoldStart` step (`-2`)
+    HoldRelease,
+    /// Several keys pressed together (e.g. `1+E`)
+    Chord(Vec<KeyIdentifier>),
+    /// Hold with several stages (`U2:400/1000`); completes on release,
+    /// reporting the last stage reached (thresholds in ms, ascending)
+    ChargedHold { stages: Vec<u64> },
+    /// Keys pressed one after another, each within `window_ms` of the one
+    /// before (`2>E`, `2>E:800`)
+    Sequence {
+        keys: Vec<KeyIdentifier>,
+        window_ms: u64,
+    },
+    /// Same key pressed twice within `window_ms` (`DD`, `D2:250`)
+    DoubleTap { window_ms: u64 },
+    /// Same key pressed `count` times, each press within `window_ms` of the
+    /// one before (`L*3`, `L*3:400`)
+    MultiTap { count: u32, window_ms: u64 },
+    /// Fixed wait (`WAIT:1500`); completes by itself once the time is up
+    Wait { duration_ms: u64 },
+    /// Branch point - the player picks which path to continue with
+    Branch { options: Vec<BranchOption> },
+    /// Start of a block repeated `count` times (marker row, not a step)
+    LoopStart { count: u32 },
+    /// End of the innermost open loop block (marker row, not a step)
+    LoopEnd,
+    /// Comment line, kept verbatim in `memo` (not a step)
+    Comment,
+    /// Reminder shown with the next step (`!ボス第2形態を待つ`); the text is
+    /// in `memo` (not a step)
+    Info,
+    /// `@include` of another combo file's steps (marker row, replaced when a
+    /// combo is loaded for playback)
+    Include { path: String },
+}
+
+/// A selectable path of a branch step
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BranchOption {
+    /// Label shown to the player
+    pub label: String,
+    /// Step index to jump to (`None` continues with the next step)
+    pub target: Option<usize>,
 }
 
 impl Default for InputType {
@@ -27,10 +106,31 @@ pub enum KeyIdentifier {
     Number(u8),
     /// E key for chain/link attacks
     Chain,
-    /// Left click or configured heavy attack key
+    /// Configured heavy attack key (left click by default)
     HeavyAttack,
-    /// Mouse left button
+    /// Mouse left button, whatever the heavy attack is bound to
     MouseLeft,
+    /// Mouse right button
+    MouseRight,
+    /// Mouse middle button (wheel click)
+    MouseMiddle,
+    /// Mouse back side button (X1)
+    MouseX1,
+    /// Mouse forward side button (X2)
+    MouseX2,
+    /// Dodge (Space or Shift)
+    Dodge,
+    /// Operator swap (Q)
+    Swap,
+    /// Mouse wheel notch (`WU` / `WD`)
+    Wheel(WheelDirection),
+}
+
+/// Mouse wheel scroll direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WheelDirection {
+    Up,
+    Down,
 }
 
 impl KeyIdentifier {
@@ -49,9 +149,59 @@ impl KeyIdentifier {
         match s.to_uppercase().as_str() {
             "E" => Some(KeyIdentifier::Chain),
             "L" => Some(KeyIdentifier::HeavyAttack),
+            "LC" => Some(KeyIdentifier::MouseLeft),
+            "D" | "SPACE" | "SHIFT" => Some(KeyIdentifier::Dodge),
+            "Q" => Some(KeyIdentifier::Swap),
+            "R" | "RC" => Some(KeyIdentifier::MouseRight),
+            "MC" => Some(KeyIdentifier::MouseMiddle),
+            "X1" => Some(KeyIdentifier::MouseX1),
+            "X2" => Some(KeyIdentifier::MouseX2),
+            "WU" => Some(KeyIdentifier::Wheel(WheelDirection::Up)),
+            "WD" => Some(KeyIdentifier::Wheel(WheelDirection::Down)),
+            _ => None,
+        }
+    }
+
+    /// Whether this is a wheel notch, which can't be held or combined
+    pub fn is_wheel(&self) -> bool {
+        matches!(self, KeyIdentifier::Wheel(_))
+    }
+}
+
+/// Movement direction held while pressing a step's key (`W~L`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
+    /// W
+    Forward,
+    /// S
+    Back,
+    /// A
+    Left,
+    /// D
+    Right,
+}
+
+impl Direction {
+    /// Parse a movement key token (`W`, `A`, `S`, `D`)
+    pub fn from_token(s: &str) -> Option<Self> {
+        match s.trim().to_uppercase().as_str() {
+            "W" => Some(Direction::Forward),
+            "S" => Some(Direction::Back),
+            "A" => Some(Direction::Left),
+            "D" => Some(Direction::Right),
             _ => None,
         }
     }
+
+    /// Movement key token
+    pub fn label(self) -> &'static str {
+        match self {
+            Direction::Forward => "W",
+            Direction::Back => "S",
+            Direction::Left => "A",
+            Direction::Right => "D",
+        }
+    }
 }
 
 /// A single combo command entry
@@ -67,17 +217,549 @@ pub struct ComboCommand {
     pub skill_type: String,
     /// Optional memo/note
     pub memo: String,
+    /// Movement key that must be held when pressing `key`
+    #[serde(default)]
+    pub direction: Option<Direction>,
+    /// Expected delay after the previous input in milliseconds (shown as a wait hint)
+    #[serde(default)]
+    pub gap_ms: Option<u64>,
+    /// Skill points spent by this step
+    #[serde(default)]
+    pub sp_cost: Option<u32>,
+    /// Skill points generated by this step
+    #[serde(default)]
+    pub sp_gain: Option<u32>,
+    /// Filler step that may be dropped (`?` key prefix): pressing the next
+    /// step's key skips it
+    #[serde(default)]
+    pub optional: bool,
+    /// Labels for this step (seventh column, `/`-separated)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Image path or icon id shown with the step (eighth column, see [`icon`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// Columns after the icon column, kept as written
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unknown_columns: Vec<String>,
+    /// Line this row was read from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceLine>,
+    /// Cancel window (`GROUP` ... `ENDGROUP`) the step belongs to
+    #[serde(default)]
+    pub group_id: Option<u32>,
     /// Whether this is a title/header line
     pub is_title: bool,
 }
 
+impl ComboCommand {
+    /// Create a tap step
+    pub fn tap(key: KeyIdentifier, character: &str, skill_type: &str) -> Self {
+        Self {
+            key,
+            input_type: InputType::Tap,
+            character: character.to_string(),
+            skill_type: skill_type.to_string(),
+            memo: String::new(),
+            direction: None,
+            gap_ms: None,
+            sp_cost: None,
+            sp_gain: None,
+            optional: false,
+            tags: Vec::new(),
+            icon: None,
+            unknown_columns: Vec::new(),
+            source: None,
+            group_id: None,
+            is_title: false,
+        }
+    }
+
+    /// Create a hold step (`None` = follow the hold threshold)
+    pub fn hold(
+        key: KeyIdentifier,
+        duration_ms: Option<u64>,
+        character: &str,
+        skill_type: &str,
+    ) -> Self {
+        Self {
+            input_type: InputType::Hold { duration_ms },
+            ..Self::tap(key, character, skill_type)
+        }
+    }
+
+    /// Create a title (`#`) row
+    pub fn title(name: &str) -> Self {
+        Self {
+            is_title: true,
+            ..Self::tap(KeyIdentifier::Number(0), name, "")
+        }
+    }
+
+    /// Set the memo
+    pub fn with_memo(mut self, memo: &str) -> Self {
+        self.memo = memo.to_string();
+        self
+    }
+
+    /// Whether this row is an input step (not a title, loop marker or comment)
+    pub fn is_step(&self) -> bool {
+        !self.is_title
+            && !matches!(
+                self.input_type,
+                InputType::LoopStart { .. }
+                    | InputType::LoopEnd
+                    | InputType::Comment
+                    | InputType::Info
+                    | InputType::Include { .. }
+            )
+    }
+}
+
+/// A loop block resolved to step indices
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoopBlock {
+    /// First step of the block
+    pub start: usize,
+    /// Last step of the block (inclusive)
+    pub end: usize,
+    /// Number of passes through the block
+    pub count: u32,
+}
+
+impl LoopBlock {
+    fn contains(&self, index: usize) -> bool {
+        (self.start..=self.end).contains(&index)
+    }
+
+    fn span(&self) -> usize {
+        self.end - self.start
+    }
+}
+
+/// A phase started by a `#` header line
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Phase {
+    /// Header text
+    pub name: String,
+    /// First step of the phase
+    pub start: usize,
+}
+
+/// Position of a step within its cancel window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepGroup {
+    pub id: u32,
+    /// 1-based position of the step in the group
+    pub position: usize,
+    /// Number of steps in the group
+    pub size: usize,
+}
+
+/// Provenance details from the `@key: value` header block
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ComboMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub game_version: Option<String>,
+    /// Operators in the team, in slot order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub team: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Free-form labels for searching (`boss`, `aoe`, ...)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// Split a `/`-separated header list
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split('/')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+impl ComboMetadata {
+    /// Whether no field is set
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Set a field from a header entry; returns false for unknown keys
+    fn set(&mut self, key: &str, value: &str) -> bool {
+        let value = value.trim().to_string();
+        match key.trim() {
+            "author" => self.author = Some(value),
+            "game_version" => self.game_version = Some(value),
+            "team" => self.team = split_list(&value),
+            "created" => self.created = Some(value),
+            "updated" => self.updated = Some(value),
+            "description" => self.description = Some(value),
+            "tags" => self.tags = split_list(&value),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Header entries in file order
+    fn entries(&self) -> Vec<(&'static str, String)> {
+        let team = (!self.team.is_empty()).then(|| self.team.join("/"));
+        let tags = (!self.tags.is_empty()).then(|| self.tags.join("/"));
+        [
+            ("author", self.author.clone()),
+            ("game_version", self.game_version.clone()),
+            ("team", team),
+            ("created", self.created.clone()),
+            ("updated", self.updated.clone()),
+            ("description", self.description.clone()),
+            ("tags", tags),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, value?)))
+        .collect()
+    }
+}
+
 /// Parsed combo file
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComboFile {
     /// Title/preset name (first line with #)
     pub title: String,
+    /// Optional header block (author, team, ...)
+    #[serde(default)]
+    pub metadata: ComboMetadata,
+    /// `@alias` short codes for operator names
+    #[serde(default)]
+    pub aliases: Vec<CharacterAlias>,
     /// List of combo commands
-    pub commands: Vec<ComboCommand>,
+    ///
+    /// Commands are reference-counted so the navigation state and the input
+    /// handler can share the active step without cloning it.
+    pub commands: Vec<Arc<ComboCommand>>,
+    /// Comment lines written after the EOF marker
+    #[serde(default)]
+    pub footer: Vec<String>,
+}
+
+impl ComboFile {
+    /// Create a combo with a title row and no steps
+    pub fn new(title: &str) -> Self {
+        let mut combo = Self {
+            title: String::new(),
+            metadata: ComboMetadata::default(),
+            aliases: Vec::new(),
+            commands: vec![Arc::new(ComboCommand::title(title))],
+            footer: Vec::new(),
+        };
+        combo.refresh_title();
+        combo
+    }
+
+    /// Append a row
+    pub fn append(&mut self, command: ComboCommand) -> &mut Self {
+        self.commands.push(Arc::new(command));
+        self.refresh_title();
+        self
+    }
+
+    /// Insert a row before `index` (clamped, so past-the-end appends)
+    pub fn insert(&mut self, index: usize, command: ComboCommand) -> &mut Self {
+        self.insert_row(index, Arc::new(command));
+        self
+    }
+
+    /// Insert a row before `index` (clamped), keeping branch targets on the
+    /// steps they pointed at
+    ///
+    /// The targets of `command` itself are taken as they are.
+    pub fn insert_row(&mut self, index: usize, command: Arc<ComboCommand>) {
+        let index = index.min(self.commands.len());
+        if command.is_step() {
+            let step = self.steps_before_row(index);
+            self.remap_branch_targets(|t| if t >= step { t + 1 } else { t });
+        }
+        self.commands.insert(index, command);
+        self.refresh_title();
+    }
+
+    /// Remove the row at `index`, keeping branch targets on the steps they
+    /// pointed at
+    ///
+    /// Targets of a removed step move on to the step after it; see
+    /// [`Self::branch_into_row`] to refuse such removals.
+    pub fn remove(&mut self, index: usize) -> Option<Arc<ComboCommand>> {
+        if index >= self.commands.len() {
+            return None;
+        }
+        let step = self.step_at_row(index);
+        let removed = self.commands.remove(index);
+        if let Some(step) = step {
+            self.remap_branch_targets(|t| if t > step { t - 1 } else { t });
+        }
+        self.refresh_title();
+        Some(removed)
+    }
+
+    /// Move the row at `from` so that it ends up at `to`, keeping branch
+    /// targets on the steps they pointed at. Returns false if out of range.
+    pub fn reorder(&mut self, from: usize, to: usize) -> bool {
+        let len = self.commands.len();
+        if from >= len || to >= len {
+            return false;
+        }
+        let old = self.step_at_row(from);
+        let command = self.commands.remove(from);
+        self.commands.insert(to, command);
+        if let (Some(old), Some(new)) = (old, self.step_at_row(to)) {
+            self.remap_branch_targets(|t| {
+                if t == old {
+                    new
+                } else if old < t && t <= new {
+                    t - 1
+                } else if new <= t && t < old {
+                    t + 1
+                } else {
+                    t
+                }
+            });
+        }
+        self.refresh_title();
+        true
+    }
+
+    /// Step index of the row at `row` (`None` if it is no input step)
+    fn step_at_row(&self, row: usize) -> Option<usize> {
+        self.commands
+            .get(row)?
+            .is_step()
+            .then(|| self.steps_before_row(row))
+    }
+
+    /// Pass every branch target through `map`
+    fn remap_branch_targets(&mut self, map: impl Fn(usize) -> usize) {
+        for cmd in &mut self.commands {
+            *cmd = include::remap_targets(cmd, &map);
+        }
+    }
+
+    /// Step index of a branch jumping to the step at `row`, if any
+    pub fn branch_into_row(&self, row: usize) -> Option<usize> {
+        let step = self.step_at_row(row)?;
+        self.steps().position(|cmd| match &cmd.input_type {
+            InputType::Branch { options } => options.iter().any(|o| o.target == Some(step)),
+            _ => false,
+        })
+    }
+
+    /// Iterate over input steps (title lines, loop markers and comments excluded)
+    pub fn steps(&self) -> impl Iterator<Item = &Arc<ComboCommand>> {
+        self.commands.iter().filter(|c| c.is_step())
+    }
+
+    /// Number of input steps (title lines, loop markers and comments excluded)
+    pub fn step_count(&self) -> usize {
+        self.steps().count()
+    }
+
+    /// Get the input step at the given step index
+    pub fn step(&self, index: usize) -> Option<&Arc<ComboCommand>> {
+        self.steps().nth(index)
+    }
+
+    /// Row index of the input step at the given step index
+    pub fn row_of_step(&self, index: usize) -> Option<usize> {
+        self.commands
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.is_step())
+            .nth(index)
+            .map(|(row, _)| row)
+    }
+
+    /// Number of input steps before `row` (the step index of the row, or of
+    /// the next step when the row is no step)
+    pub fn steps_before_row(&self, row: usize) -> usize {
+        self.commands
+            .iter()
+            .take(row)
+            .filter(|c| c.is_step())
+            .count()
+    }
+
+    /// Texts of the info rows between step `index` and the step before it
+    pub fn notes_before(&self, index: usize) -> Vec<&str> {
+        let Some(row) = self.row_of_step(index) else {
+            return Vec::new();
+        };
+        let mut notes: Vec<&str> = self.commands[..row]
+            .iter()
+            .rev()
+            .take_while(|c| !c.is_step())
+            .filter(|c| c.input_type == InputType::Info)
+            .map(|c| c.memo.as_str())
+            .collect();
+        notes.reverse();
+        notes
+    }
+
+    /// Phases in file order
+    ///
+    /// Headers without any following step are dropped; consecutive headers keep
+    /// the last one.
+    pub fn phases(&self) -> Vec<Phase> {
+        let mut phases: Vec<Phase> = Vec::new();
+        let mut step = 0;
+        for cmd in &self.commands {
+            if cmd.is_title {
+                if phases.last().is_some_and(|p| p.start == step) {
+                    phases.pop();
+                }
+                phases.push(Phase {
+                    name: cmd.character.clone(),
+                    start: step,
+                });
+            } else if cmd.is_step() {
+                step += 1;
+            }
+        }
+        if phases.last().is_some_and(|p| p.start >= step) {
+            phases.pop();
+        }
+        phases
+    }
+
+    /// Index into `phases()` of the phase containing the given step
+    pub fn phase_at(&self, index: usize) -> Option<usize> {
+        self.phases().iter().rposition(|p| p.start <= index)
+    }
+
+    /// Give every run of consecutive steps sharing a group number its own id
+    pub fn renumber_groups(&mut self) {
+        let mut previous = None;
+        let mut id = 0;
+        for cmd in self.commands.iter_mut().filter(|c| c.is_step()) {
+            let group = cmd.group_id;
+            if group.is_some() && group != previous {
+                id += 1;
+            }
+            previous = group;
+            let renumbered = group.map(|_| id);
+            if cmd.group_id != renumbered {
+                Arc::make_mut(cmd).group_id = renumbered;
+            }
+        }
+    }
+
+    /// Cancel window of the given step, if it is in one
+    pub fn step_group(&self, index: usize) -> Option<StepGroup> {
+        let id = self.step(index)?.group_id?;
+        let members: Vec<usize> = self
+            .steps()
+            .enumerate()
+            .filter(|(_, step)| step.group_id == Some(id))
+            .map(|(i, _)| i)
+            .collect();
+        Some(StepGroup {
+            id,
+            position: members.iter().position(|&i| i == index)? + 1,
+            size: members.len(),
+        })
+    }
+
+    /// Resolve loop markers to step ranges
+    ///
+    /// Unmatched markers and loops without steps are ignored.
+    pub fn loops(&self) -> Vec<LoopBlock> {
+        let mut loops = Vec::new();
+        let mut open = Vec::new();
+        let mut step = 0;
+        for cmd in &self.commands {
+            match cmd.input_type {
+                InputType::LoopStart { count } => open.push((step, count)),
+                InputType::LoopEnd => {
+                    if let Some((start, count)) = open.pop() {
+                        if step > start {
+                            loops.push(LoopBlock {
+                                start,
+                                end: step - 1,
+                                count,
+                            });
+                        }
+                    }
+                }
+                _ if cmd.is_step() => step += 1,
+                _ => {}
+            }
+        }
+        loops
+    }
+
+    /// Check that every branch target points at an existing step
+    pub fn validate_branches(&self) -> Result<(), ParseError> {
+        let total = self.step_count();
+        for (step, cmd) in self.steps().enumerate() {
+            if let InputType::Branch { options } = &cmd.input_type {
+                for target in options.iter().filter_map(|o| o.target) {
+                    if target >= total {
+                        return Err(ParseError::InvalidBranchTarget {
+                            step: step + 1,
+                            target: target + 1,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Clear branch targets past the last step, returning an error for each
+    fn clear_invalid_branch_targets(&mut self) -> Vec<ParseError> {
+        let total = self.step_count();
+        let mut errors = Vec::new();
+        let mut step = 0;
+        for cmd in self.commands.iter_mut() {
+            if !cmd.is_step() {
+                continue;
+            }
+            step += 1;
+            let invalid = |target: &Option<usize>| target.is_some_and(|t| t >= total);
+            let InputType::Branch { options } = &cmd.input_type else {
+                continue;
+            };
+            if !options.iter().any(|o| invalid(&o.target)) {
+                continue;
+            }
+            let cmd = Arc::make_mut(cmd);
+            if let InputType::Branch { options } = &mut cmd.input_type {
+                for option in options.iter_mut().filter(|o| invalid(&o.target)) {
+                    errors.push(ParseError::InvalidBranchTarget {
+                        step,
+                        target: option.target.unwrap_or_default() + 1,
+                    });
+                    option.target = None;
+                }
+            }
+        }
+        errors
+    }
+
+    /// Re-derive `title` from the first title line
+    pub fn refresh_title(&mut self) {
+        // Use character field as title for # lines
+        self.title = match self.commands.iter().find(|c| c.is_title) {
+            Some(cmd) if cmd.character.is_empty() => "Untitled".to_string(),
+            Some(cmd) => cmd.character.clone(),
+            None => String::new(),
+        };
+    }
 }
 
 /// Parse error types
@@ -89,6 +771,20 @@ pub enum ParseError {
     InvalidFormat { line: usize, content: String },
     /// Invalid key identifier
     InvalidKey { line: usize, key: String },
+    /// Branch target outside the combo (1-based step numbers)
+    InvalidBranchTarget { step: usize, target: usize },
+    /// Malformed YAML document
+    InvalidYaml(String),
+    /// File holds several `##` combos; pick one with the library parser
+    MultipleCombos,
+    /// Written by a newer version of the app
+    UnsupportedVersion(u32),
+    /// `@include` chain that leads back to a file already being included
+    IncludeCycle(String),
+    /// `@include`d file that does not load
+    InvalidInclude { path: String, message: String },
+    /// Share code that does not decode
+    InvalidShareCode(String),
     /// IO error
     IoError(String),
 }
@@ -103,6 +799,23 @@ impl std::fmt::Display for ParseError {
             ParseError::InvalidKey { line, key } => {
                 write!(f, "Invalid key '{}' at line {}", key, line)
             }
+            ParseError::InvalidBranchTarget { step, target } => {
+                write!(
+                    f,
+                    "Branch at step {} points to missing step {}",
+                    step, target
+                )
+            }
+            ParseError::InvalidYaml(msg) => write!(f, "Invalid YAML: {}", msg),
+            ParseError::MultipleCombos => write!(f, "File contains several combos"),
+            ParseError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported combo format version {}", version)
+            }
+            ParseError::IncludeCycle(path) => write!(f, "Include cycle through {}", path),
+            ParseError::InvalidInclude { path, message } => {
+                write!(f, "Cannot include {}: {}", path, message)
+            }
+            ParseError::InvalidShareCode(msg) => write!(f, "Invalid share code: {}", msg),
             ParseError::IoError(msg) => write!(f, "IO error: {}", msg),
         }
     }
@@ -110,17 +823,410 @@ impl std::fmt::Display for ParseError {
 
 impl std::error::Error for ParseError {}
 
-/// Default hold duration in milliseconds (300ms)
-const DEFAULT_HOLD_DURATION_MS: u64 = 300;
+impl ParseError {
+    /// 1-based source line the error points at, if any
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            ParseError::InvalidFormat { line, .. } | ParseError::InvalidKey { line, .. } => {
+                Some(*line)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Parse error reported to the UI while loading leniently
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParseWarning {
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl From<&ParseError> for ParseWarning {
+    fn from(error: &ParseError) -> Self {
+        Self {
+            line: error.line(),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Default time allowed between the presses of a double tap in milliseconds
+pub const DEFAULT_DOUBLE_TAP_WINDOW_MS: u64 = 300;
+
+/// Default time allowed between the presses of a sequence in milliseconds
+pub const DEFAULT_SEQUENCE_WINDOW_MS: u64 = 500;
+
+/// Default time allowed between the presses of a multi-tap in milliseconds
+pub const DEFAULT_MULTI_TAP_WINDOW_MS: u64 = 400;
 
 /// EOF marker
 const EOF_MARKER: &str = "!!!!!";
 
+/// Loop start token (`LOOP n`)
+const LOOP_START_MARKER: &str = "LOOP";
+
+/// Loop end token
+const LOOP_END_MARKER: &str = "ENDLOOP";
+
+/// Start of a cancel window: steps pressed within one animation cancel
+const GROUP_START_MARKER: &str = "GROUP";
+
+/// End of a cancel window
+const GROUP_END_MARKER: &str = "ENDGROUP";
+
+/// Header entry prefix (`@author: name`)
+const METADATA_MARKER: &str = "@";
+
+/// Separator starting a named combo in a multi-combo file (`## 名前`)
+const COMBO_SEPARATOR: &str = "##";
+
+/// Comment line prefixes
+const COMMENT_MARKERS: [&str; 2] = ["//", ";"];
+
+/// Info row prefix (`!ボス第2形態を待つ`)
+const INFO_MARKER: &str = "!";
+
+/// Build a marker row (loop marker or comment) that is not an input step
+fn marker_row(input_type: InputType) -> ComboCommand {
+    ComboCommand {
+        input_type,
+        ..ComboCommand::tap(KeyIdentifier::Number(0), "", "")
+    }
+}
+
+/// Split a movement prefix off a key token (`W~L` -> `Forward`, `L`)
+///
+/// `W+L`, `A+L` and `S+L` are still read as movement, but `D+` stays a chord
+/// with dodge since `D` is also a key.
+fn split_direction(key_str: &str) -> (Option<Direction>, &str) {
+    if let Some((prefix, rest)) = key_str.split_once(DIRECTION_MARKER) {
+        if let Some(direction) = Direction::from_token(prefix) {
+            if !rest.trim().is_empty() {
+                return (Some(direction), rest.trim());
+            }
+        }
+    }
+    if let Some((prefix, rest)) = key_str.split_once('+') {
+        if KeyIdentifier::from_str(prefix).is_none() {
+            if let Some(direction) = Direction::from_token(prefix) {
+                if !rest.trim().is_empty() {
+                    return (Some(direction), rest.trim());
+                }
+            }
+        }
+    }
+    (None, key_str)
+}
+
+/// Movement prefix written before a key token
+fn direction_prefix(direction: Option<Direction>) -> String {
+    match direction {
+        Some(direction) => format!("{}{}", direction.label(), DIRECTION_MARKER),
+        None => String::new(),
+    }
+}
+
+/// Parse a key specification such as `2`, `U2`, `U2:800`, `D2`, `L*3`, `1+E`, `2>E`, `E` or `L`
+fn parse_key_spec(key_str: &str) -> Option<(KeyIdentifier, InputType)> {
+    // Timed wait: no key, the step advances by itself
+    if let Some((marker, ms)) = key_str.split_once(':') {
+        if marker.eq_ignore_ascii_case(WAIT_MARKER) {
+            let duration_ms = ms.trim().parse().ok().filter(|&ms| ms > 0)?;
+            return Some((KeyIdentifier::Number(0), InputType::Wait { duration_ms }));
+        }
+    }
+
+    // Charge-and-release: one step presses the key, a later one lets go
+    for (marker, input_type) in [
+        (HOLD_START_MARKER, InputType::HoldStart),
+        (HOLD_RELEASE_MARKER, InputType::HoldRelease),
+    ] {
+        if let Some(rest) = key_str.strip_prefix(marker) {
+            let key = KeyIdentifier::from_str(rest).filter(|k| !k.is_wheel())?;
+            return Some((key, input_type));
+        }
+    }
+
+    if key_str.contains(SEQUENCE_MARKER) {
+        // Sequence: keys pressed in order, optionally with their own window
+        let (keys_part, window_ms) = match key_str.split_once(':') {
+            Some((keys_part, ms)) => (keys_part, ms.trim().parse().ok().filter(|&ms| ms > 0)?),
+            None => (key_str, DEFAULT_SEQUENCE_WINDOW_MS),
+        };
+        let keys = keys_part
+            .split(SEQUENCE_MARKER)
+            .map(|key| KeyIdentifier::from_str(key.trim()))
+            .collect::<Option<Vec<_>>>()?;
+        if keys.len() < 2 || keys.iter().any(KeyIdentifier::is_wheel) {
+            return None;
+        }
+        return Some((keys[0].clone(), InputType::Sequence { keys, window_ms }));
+    }
+
+    if let Some((key_part, rest)) = key_str.split_once(MULTI_TAP_MARKER) {
+        // Multi-tap: one key pressed several times, optionally with its own window
+        let (count, window_ms) = match rest.split_once(':') {
+            Some((count, ms)) => (count, ms.trim().parse().ok().filter(|&ms| ms > 0)?),
+            None => (rest, DEFAULT_MULTI_TAP_WINDOW_MS),
+        };
+        let count = count
+            .trim()
+            .parse()
+            .ok()
+            .filter(|count| (2..=MAX_REPEAT).contains(count))?;
+        let key = KeyIdentifier::from_str(key_part).filter(|k| !k.is_wheel())?;
+        return Some((key, InputType::MultiTap { count, window_ms }));
+    }
+
+    if key_str.contains('+') {
+        // Chord: all keys pressed together
+        let keys = key_str
+            .split('+')
+            .map(KeyIdentifier::from_str)
+            .collect::<Option<Vec<_>>>()?;
+        if keys.len() < 2 || keys.iter().any(KeyIdentifier::is_wheel) {
+            return None;
+        }
+        return Some((keys[0].clone(), InputType::Chord(keys)));
+    }
+
+    // Double tap; a lone `D` is the dodge key
+    if let Some(rest) = key_str
+        .strip_prefix(DOUBLE_TAP_MARKER)
+        .or_else(|| key_str.strip_prefix(&DOUBLE_TAP_MARKER.to_lowercase()))
+        .filter(|rest| !rest.is_empty())
+    {
+        let (key_part, window_ms) = match rest.split_once(':') {
+            Some((key_part, ms)) => (key_part, ms.trim().parse().ok().filter(|&ms| ms > 0)?),
+            None => (rest, DEFAULT_DOUBLE_TAP_WINDOW_MS),
+        };
+        let key = KeyIdentifier::from_str(key_part).filter(|k| !k.is_wheel())?;
+        return Some((key, InputType::DoubleTap { window_ms }));
+    }
+
+    if key_str.starts_with('U') || key_str.starts_with('u') {
+        // Ultimate/Hold input, optionally with its own duration
+        if let Some((key_part, stages)) = key_str[1..]
+            .split_once(':')
+            .filter(|(_, ms)| ms.contains('/'))
+        {
+            let stages = parse_charge_stages(stages)?;
+            let key = KeyIdentifier::from_str(key_part).filter(|k| !k.is_wheel())?;
+            return Some((key, InputType::ChargedHold { stages }));
+        }
+        let (key_part, duration_ms) = match key_str[1..].split_once(':') {
+            Some((key_part, ms)) => (key_part, Some(ms.trim().parse().ok().filter(|&ms| ms > 0)?)),
+            None => (&key_str[1..], None),
+        };
+        let key = KeyIdentifier::from_str(key_part).filter(|k| !k.is_wheel())?;
+        Some((key, InputType::Hold { duration_ms }))
+    } else {
+        // Normal tap input
+        Some((KeyIdentifier::from_str(key_str)?, InputType::Tap))
+    }
+}
+
+/// Branch key token
+const BRANCH_MARKER: &str = "?";
+
+/// Key prefix of a step that starts holding a key (`+2`)
+const HOLD_START_MARKER: &str = "+";
+
+/// Key prefix of a step that releases a held key (`-2`)
+const HOLD_RELEASE_MARKER: &str = "-";
+
+/// Key prefix of an optional (skippable) step (`?2`)
+const OPTIONAL_MARKER: &str = "?";
+
+/// Separator of the keys of a sequence step (`2>E`)
+const SEQUENCE_MARKER: char = '>';
+
+/// Separator of a movement key and the key pressed with it held (`W~L`)
+const DIRECTION_MARKER: char = '~';
+
+/// Separator of the key and press count of a multi-tap step (`L*3`)
+const MULTI_TAP_MARKER: char = '*';
+
+/// Key prefix of a double tap step (`D2`)
+const DOUBLE_TAP_MARKER: &str = "D";
+
+/// Key of a timed wait step (`WAIT:1500`)
+const WAIT_MARKER: &str = "WAIT";
+
+/// Separator of a repeat count and its key (`3xL`)
+const REPEAT_MARKER: char = 'x';
+
+/// Memo placeholder replaced by the copy number of a repeated step
+const REPEAT_COUNTER: &str = "{n}";
+
+/// Highest repeat count of a `3xL` line
+const MAX_REPEAT: u32 = 99;
+
+/// Split a `3xL,...` line into its repeat count and the row it repeats
+fn split_repeat(line: &str) -> Option<(u32, &str)> {
+    let line = line.trim();
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let rest = line[digits..].strip_prefix(|c: char| c.eq_ignore_ascii_case(&REPEAT_MARKER))?;
+    if digits == 0 || rest.is_empty() || rest.starts_with(|c: char| c.is_whitespace() || c == ',') {
+        return None;
+    }
+    Some((line[..digits].parse().ok()?, rest))
+}
+
+/// Parse branch options written as `label>step/label>step`
+///
+/// Steps are 1-based; an option without `>step` continues with the next step.
+fn parse_branch_options(spec: &str) -> Option<Vec<BranchOption>> {
+    spec.split('/')
+        .map(|option| {
+            let (label, target) = match option.split_once('>') {
+                Some((label, step)) => {
+                    let step: usize = step.trim().parse().ok()?;
+                    (label, Some(step.checked_sub(1)?))
+                }
+                None => (option, None),
+            };
+            Some(BranchOption {
+                label: label.trim().to_string(),
+                target,
+            })
+        })
+        .collect()
+}
+
+/// Format branch options back into `label>step/label>step`
+fn format_branch_options(options: &[BranchOption]) -> String {
+    options
+        .iter()
+        .map(|o| match o.target {
+            Some(target) => format!("{}>{}", o.label, target + 1),
+            None => o.label.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Key label used in combo files
+fn key_label(key: &KeyIdentifier) -> String {
+    match key {
+        KeyIdentifier::Number(n) => n.to_string(),
+        KeyIdentifier::Chain => "E".to_string(),
+        KeyIdentifier::HeavyAttack => "L".to_string(),
+        KeyIdentifier::MouseLeft => "LC".to_string(),
+        KeyIdentifier::Dodge => "D".to_string(),
+        KeyIdentifier::Swap => "Q".to_string(),
+        KeyIdentifier::MouseRight => "R".to_string(),
+        KeyIdentifier::MouseMiddle => "MC".to_string(),
+        KeyIdentifier::MouseX1 => "X1".to_string(),
+        KeyIdentifier::MouseX2 => "X2".to_string(),
+        KeyIdentifier::Wheel(WheelDirection::Up) => "WU".to_string(),
+        KeyIdentifier::Wheel(WheelDirection::Down) => "WD".to_string(),
+    }
+}
+
+/// Key token of a double tap (`D2`, `D2:250`)
+fn double_tap_label(key: &KeyIdentifier, window_ms: u64) -> String {
+    if window_ms == DEFAULT_DOUBLE_TAP_WINDOW_MS {
+        format!("{}{}", DOUBLE_TAP_MARKER, key_label(key))
+    } else {
+        format!("{}{}:{}", DOUBLE_TAP_MARKER, key_label(key), window_ms)
+    }
+}
+
+/// Key token of a multi-tap (`L*3`, `L*3:400`)
+fn multi_tap_label(key: &KeyIdentifier, count: u32, window_ms: u64) -> String {
+    if window_ms == DEFAULT_MULTI_TAP_WINDOW_MS {
+        format!("{}{}{}", key_label(key), MULTI_TAP_MARKER, count)
+    } else {
+        format!(
+            "{}{}{}:{}",
+            key_label(key),
+            MULTI_TAP_MARKER,
+            count,
+            window_ms
+        )
+    }
+}
+
+/// Stage thresholds of a charged hold (`400/1000`), which must rise
+fn parse_charge_stages(spec: &str) -> Option<Vec<u64>> {
+    let stages = spec
+        .split('/')
+        .map(|ms| ms.trim().parse().ok().filter(|&ms: &u64| ms > 0))
+        .collect::<Option<Vec<u64>>>()?;
+    stages
+        .windows(2)
+        .all(|pair| pair[0] < pair[1])
+        .then_some(stages)
+}
+
+/// Key label of a charged hold (`U2:400/1000`)
+fn charge_label(key: &KeyIdentifier, stages: &[u64]) -> String {
+    let stages = stages
+        .iter()
+        .map(|ms| ms.to_string())
+        .collect::<Vec<_>>()
+        .join("/");
+    format!("U{}:{}", key_label(key), stages)
+}
+
+/// Key token of a sequence (`2>E`, `2>E:800`)
+fn sequence_label(keys: &[KeyIdentifier], window_ms: u64) -> String {
+    let keys = keys
+        .iter()
+        .map(key_label)
+        .collect::<Vec<_>>()
+        .join(&SEQUENCE_MARKER.to_string());
+    if window_ms == DEFAULT_SEQUENCE_WINDOW_MS {
+        keys
+    } else {
+        format!("{}:{}", keys, window_ms)
+    }
+}
+
+/// Key label of a chord (`1+E`)
+fn chord_label(keys: &[KeyIdentifier]) -> String {
+    keys.iter().map(key_label).collect::<Vec<_>>().join("+")
+}
+
+/// Optional trailing columns: timing gap (`,ms`), SP (`,-100+20`) and any
+/// unknown columns read from the file
+fn extra_columns(cmd: &ComboCommand) -> String {
+    let mut columns = vec![
+        cmd.gap_ms.map(|ms| ms.to_string()).unwrap_or_default(),
+        sp::format_sp(cmd.sp_cost, cmd.sp_gain),
+        cmd.tags.join("/"),
+        cmd.icon.clone().unwrap_or_default(),
+    ];
+    columns.extend(cmd.unknown_columns.iter().cloned());
+    while columns.last().is_some_and(|c| c.is_empty()) {
+        columns.pop();
+    }
+    columns.iter().map(|c| format!(",{}", c)).collect()
+}
+
 /// Parse a single line of the combo file
 /// 
-/// Format: `KEY,CHARACTER,SKILL_TYPE,MEMO|`
-/// - KEY: `[0-9]` for tap, `U[0-9]` for hold, `E` for chain, `L` for heavy attack
+/// Format: `KEY,CHARACTER,SKILL_TYPE,MEMO[,GAP_MS[,SP[,TAGS]]]|`
+/// - KEY: `[0-9]` for tap, `U[0-9]` for hold (`U[0-9]:ms` with a custom duration),
+///   `E` for chain, `L` for heavy attack, `1+E` for keys pressed together,
+///   `W~L` with a movement key (`W`/`A`/`S`/`D`) held, `?2` for an optional step,
+///   `WU`/`WD` for a mouse wheel notch up/down, `+2` to start holding a key
+///   and `-2` on a later step to let go of it
 /// - `#` prefix indicates title line
+/// - `?` starts a branch: `?,PROMPT,label>step/label>step,MEMO|`
+/// - `LOOP n` ... `ENDLOOP` repeats the enclosed steps `n` times
+/// - `GROUP` ... `ENDGROUP` marks steps pressed within one cancel window
+/// - `//` or `;` starts a comment line
+/// - `@key: value` lines before the first row form the metadata header
+/// - `@alias LF=リーフォン` in the header lets rows write `LF` for the operator
+/// - `@include path` splices in the steps of another combo file when loaded for playback
+/// - A memo written `{ja=..., en=...}` holds one text per language
+/// - An optional fifth column holds the expected delay after the previous input in ms
+/// - An optional sixth column notes SP spent (`-100`) and/or generated (`+20`)
+/// - An optional seventh column holds `/`-separated tags and an eighth one an
+///   image path or icon id; further columns are kept as written
 fn parse_line(line: &str, line_number: usize) -> Result<Option<ComboCommand>, ParseError> {
     let line = line.trim();
     
@@ -134,11 +1240,26 @@ fn parse_line(line: &str, line_number: usize) -> Result<Option<ComboCommand>, Pa
         return Ok(None);
     }
     
+    // Header entries and combo separators are read by the file parsers
+    if line.starts_with(METADATA_MARKER) || line.starts_with(COMBO_SEPARATOR) {
+        return Ok(None);
+    }
+    
+    // Comment lines are kept verbatim so re-saving doesn't drop them
+    if COMMENT_MARKERS.iter().any(|m| line.starts_with(m)) {
+        return Ok(Some(marker_row(InputType::Comment).with_memo(line)));
+    }
+
+    // Info rows are shown to the player but never pressed
+    if let Some(text) = line.strip_prefix(INFO_MARKER) {
+        let text = text.trim().trim_end_matches('|').trim();
+        return Ok(Some(marker_row(InputType::Info).with_memo(text)));
+    }
+    
     // Remove trailing pipe if present
     let line = line.trim_end_matches('|').trim();
     
-    // Split by comma
-    let parts: Vec<&str> = line.split(',').collect();
+    let parts = split_columns(line);
     if parts.is_empty() {
         return Err(ParseError::InvalidFormat {
             line: line_number,
@@ -151,111 +1272,1163 @@ fn parse_line(line: &str, line_number: usize) -> Result<Option<ComboCommand>, Pa
     let skill_type = parts.get(2).unwrap_or(&"").trim().to_string();
     let memo = parts.get(3).unwrap_or(&"").trim().to_string();
     
-    // Check if this is a title line
-    if key_str.starts_with('#') {
-        return Ok(Some(ComboCommand {
-            key: KeyIdentifier::Number(0),
-            input_type: InputType::Tap,
-            character,
-            skill_type,
-            memo,
-            is_title: true,
-        }));
+    let mut cmd = parse_fields(key_str, character, skill_type, memo, line_number, line)?;
+    cmd.gap_ms = parse_gap(parts.get(4).unwrap_or(&""), line_number, line)?;
+    (cmd.sp_cost, cmd.sp_gain) = sp::parse_sp(parts.get(5).unwrap_or(&""), line_number, line)?;
+    cmd.tags = split_list(parts.get(6).unwrap_or(&""));
+    cmd.icon = parse_icon(parts.get(7).unwrap_or(&""), line_number, line)?;
+    cmd.unknown_columns = parts.iter().skip(8).map(|p| p.trim().to_string()).collect();
+    Ok(Some(cmd))
+}
+
+/// Split a row at commas outside `{...}` (localized memos hold commas)
+fn split_columns(line: &str) -> Vec<&str> {
+    let mut columns = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in line.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                columns.push(&line[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
     }
-    
-    // Parse key and input type
-    let (key, input_type) = if key_str.starts_with('U') || key_str.starts_with('u') {
-        // Ultimate/Hold input
-        let key_part = &key_str[1..];
-        let key = KeyIdentifier::from_str(key_part).ok_or_else(|| ParseError::InvalidKey {
+    columns.push(&line[start..]);
+    columns
+}
+
+/// Parse the timing gap column (empty means none)
+fn parse_gap(field: &str, line_number: usize, line: &str) -> Result<Option<u64>, ParseError> {
+    let field = field.trim();
+    if field.is_empty() {
+        return Ok(None);
+    }
+    field
+        .parse::<u64>()
+        .map(Some)
+        .map_err(|_| ParseError::InvalidFormat {
             line: line_number,
-            key: key_str.to_string(),
-        })?;
-        (key, InputType::Hold { duration_ms: DEFAULT_HOLD_DURATION_MS })
-    } else {
-        // Normal tap input
-        let key = KeyIdentifier::from_str(key_str).ok_or_else(|| ParseError::InvalidKey {
+            content: line.to_string(),
+        })
+}
+
+/// Parse the optional icon column
+fn parse_icon(field: &str, line_number: usize, line: &str) -> Result<Option<String>, ParseError> {
+    let field = field.trim();
+    if field.is_empty() {
+        return Ok(None);
+    }
+    if !icon::is_valid_icon(field) {
+        return Err(ParseError::InvalidFormat {
             line: line_number,
-            key: key_str.to_string(),
-        })?;
-        (key, InputType::Tap)
+            content: line.to_string(),
+        });
+    }
+    Ok(Some(field.to_string()))
+}
+
+/// Build a row from its fields; `line` is the source shown in errors
+fn parse_fields(
+    key_str: &str,
+    character: String,
+    skill_type: String,
+    memo: String,
+    line_number: usize,
+    line: &str,
+) -> Result<ComboCommand, ParseError> {
+    // Check if this is a title line
+    if key_str.starts_with('#') {
+        return Ok(ComboCommand {
+            skill_type,
+            memo,
+            ..ComboCommand::title(&character)
+        });
+    }
+    
+    // Loop markers
+    let upper = key_str.to_uppercase();
+    if upper == LOOP_END_MARKER {
+        return Ok(marker_row(InputType::LoopEnd));
+    }
+    if let Some(count) = upper.strip_prefix(LOOP_START_MARKER) {
+        let count = count
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|&c| c > 0)
+            .ok_or_else(|| ParseError::InvalidFormat {
+                line: line_number,
+                content: line.to_string(),
+            })?;
+        return Ok(marker_row(InputType::LoopStart { count }));
+    }
+    
+    // Branch point: options take the place of the skill type
+    if key_str == BRANCH_MARKER {
+        let options =
+            parse_branch_options(&skill_type).ok_or_else(|| ParseError::InvalidFormat {
+                line: line_number,
+                content: line.to_string(),
+            })?;
+        return Ok(ComboCommand {
+            input_type: InputType::Branch { options },
+            memo,
+            ..ComboCommand::tap(KeyIdentifier::Number(0), &character, "")
+        });
+    }
+    
+    // Parse key and input type
+    let (optional, token) = match key_str.strip_prefix(OPTIONAL_MARKER) {
+        Some(rest) => (true, rest.trim()),
+        None => (false, key_str),
     };
+    let (direction, key_spec) = split_direction(token);
+    let (key, input_type) = parse_key_spec(key_spec).ok_or_else(|| ParseError::InvalidKey {
+        line: line_number,
+        key: key_str.to_string(),
+    })?;
     
-    Ok(Some(ComboCommand {
-        key,
+    Ok(ComboCommand {
         input_type,
-        character,
-        skill_type,
         memo,
-        is_title: false,
-    }))
+        direction,
+        optional,
+        ..ComboCommand::tap(key, &character, &skill_type)
+    })
 }
 
-/// Parse combo file content
+/// Parse combo file content, upgrading older format versions
 pub fn parse_combo_content(content: &str) -> Result<ComboFile, ParseError> {
-    let mut title = String::new();
+    match migrate::migrate_lines(content)? {
+        Some((migrated, _)) => parse_current_content(&migrated),
+        None => parse_current_content(content),
+    }
+}
+
+/// Remember the line `cmd` was parsed from
+fn with_source(mut cmd: ComboCommand, line: &str, aliases: &[CharacterAlias]) -> ComboCommand {
+    let canonical = format_row(&cmd, aliases);
+    cmd.source = Some(SourceLine {
+        text: line.to_string(),
+        canonical,
+        repeat: None,
+    });
+    cmd
+}
+
+/// Parse combo file content already in the current format version
+fn parse_current_content(content: &str) -> Result<ComboFile, ParseError> {
+    parse_rows(content, false).map(|(combo, _)| combo)
+}
+
+/// Parse combo file content, skipping invalid lines instead of failing
+///
+/// Returns the combo built from the valid lines together with every error
+/// found, in line order. Unclosed loops are dropped and branch targets past
+/// the end are cleared. Empty files, `##` multi-combo files and unsupported
+/// versions still fail.
+pub fn parse_combo_content_lenient(
+    content: &str,
+) -> Result<(ComboFile, Vec<ParseError>), ParseError> {
+    match migrate::migrate_lines(content)? {
+        Some((migrated, _)) => parse_rows(&migrated, true),
+        None => parse_rows(content, true),
+    }
+}
+
+/// Errors of a parse: strict parsing stops at the first, lenient parsing
+/// collects them all
+struct ErrorSink {
+    lenient: bool,
+    errors: Vec<ParseError>,
+}
+
+impl ErrorSink {
+    fn report(&mut self, error: ParseError) -> Result<(), ParseError> {
+        if !self.lenient {
+            return Err(error);
+        }
+        self.errors.push(error);
+        Ok(())
+    }
+}
+
+/// Parse the rows of a single combo
+fn parse_rows(content: &str, lenient: bool) -> Result<(ComboFile, Vec<ParseError>), ParseError> {
+    let mut sink = ErrorSink {
+        lenient,
+        errors: Vec::new(),
+    };
     let mut commands = Vec::new();
+    let mut metadata = ComboMetadata::default();
+    let mut aliases: Vec<CharacterAlias> = Vec::new();
+    let mut footer = Vec::new();
+    let mut open_loops = Vec::new();
+    let mut open_group: Option<(usize, &str, u32)> = None;
+    let mut group_count = 0;
+    let mut after_eof = false;
     
     for (line_number, line) in content.lines().enumerate() {
-        if let Some(cmd) = parse_line(line, line_number + 1)? {
-            if cmd.is_title && title.is_empty() {
-                // Use character field as title for # lines
-                title = if cmd.character.is_empty() {
-                    "Untitled".to_string()
-                } else {
-                    cmd.character.clone()
-                };
+        let invalid_line = || ParseError::InvalidFormat {
+            line: line_number + 1,
+            content: line.trim().to_string(),
+        };
+        if line.trim().starts_with(EOF_MARKER) {
+            after_eof = true;
+        }
+        if line.trim().starts_with(COMBO_SEPARATOR) {
+            return Err(ParseError::MultipleCombos);
+        }
+        if migrate::version_line(line).is_some() {
+            continue;
+        }
+        // Blank lines are kept as empty comments so re-saving keeps the layout
+        if line.trim().is_empty() {
+            if after_eof {
+                footer.push(String::new());
+            } else {
+                commands.push(Arc::new(marker_row(InputType::Comment)));
+            }
+            continue;
+        }
+        if let Some(path) = include::include_line(line) {
+            let cmd = marker_row(InputType::Include {
+                path: path.to_string(),
+            });
+            commands.push(Arc::new(with_source(cmd, line, &aliases)));
+            continue;
+        }
+        // The header block must come before the first row other than comments
+        if let Some(entry) = line.trim().strip_prefix(METADATA_MARKER) {
+            let in_header = commands
+                .iter()
+                .all(|cmd: &Arc<ComboCommand>| cmd.input_type == InputType::Comment);
+            let valid = if let Some(body) = alias::alias_line(line) {
+                match alias::parse_alias(body) {
+                    Some(entry) if in_header => {
+                        aliases.retain(|a| a.alias != entry.alias);
+                        aliases.push(entry);
+                        true
+                    }
+                    _ => false,
+                }
+            } else {
+                match entry.split_once(':') {
+                    Some((key, value)) => in_header && metadata.set(key, value),
+                    None => false,
+                }
+            };
+            if !valid {
+                sink.report(invalid_line())?;
+            }
+            continue;
+        }
+        // Cancel windows tag their steps instead of adding rows
+        let marker = line.trim().trim_end_matches('|').trim().to_uppercase();
+        if marker == GROUP_START_MARKER {
+            if open_group.is_some() {
+                sink.report(invalid_line())?;
+            } else {
+                group_count += 1;
+                open_group = Some((line_number + 1, line, group_count));
             }
-            commands.push(cmd);
+            continue;
+        }
+        if marker == GROUP_END_MARKER {
+            if open_group.take().is_none() {
+                sink.report(invalid_line())?;
+            }
+            continue;
+        }
+        // `3xL,...` expands into three copies of the step
+        let (repeat, row) = match split_repeat(line) {
+            Some((count, row)) => (Some(count), row),
+            None => (None, line),
+        };
+        let mut cmd = match parse_line(row, line_number + 1) {
+            Ok(Some(cmd)) => cmd,
+            Ok(None) => continue,
+            Err(e) => {
+                sink.report(e)?;
+                continue;
+            }
+        };
+        if let Some(count) = repeat {
+            let repeatable = cmd.is_step() && !matches!(cmd.input_type, InputType::Branch { .. });
+            if !repeatable || !(1..=MAX_REPEAT).contains(&count) {
+                sink.report(invalid_line())?;
+                continue;
+            }
+        }
+        if cmd.is_step() && !matches!(cmd.input_type, InputType::Branch { .. }) {
+            cmd.character = alias::expand(&aliases, &cmd.character).to_string();
+            cmd.group_id = open_group.map(|(_, _, id)| id);
+        } else if open_group.is_some() && cmd.input_type != InputType::Comment {
+            // Only input steps (and comments) fit in a cancel window
+            sink.report(invalid_line())?;
+        }
+        match cmd.input_type {
+            // Comments after the EOF marker stay there when re-saved
+            InputType::Comment if after_eof => {
+                footer.push(cmd.memo.clone());
+                continue;
+            }
+            InputType::LoopStart { .. } => open_loops.push((line_number + 1, line, commands.len())),
+            InputType::LoopEnd if open_loops.pop().is_none() => {
+                sink.report(invalid_line())?;
+                continue;
+            }
+            _ => {}
+        }
+        let Some(count) = repeat else {
+            commands.push(Arc::new(with_source(cmd, line, &aliases)));
+            continue;
+        };
+        for n in 1..=count {
+            let copy = ComboCommand {
+                memo: cmd.memo.replace(REPEAT_COUNTER, &n.to_string()),
+                ..cmd.clone()
+            };
+            let mut copy = with_source(copy, line, &aliases);
+            if let Some(source) = copy.source.as_mut() {
+                source.repeat = Some(n);
+            }
+            commands.push(Arc::new(copy));
         }
     }
     
-    if commands.is_empty() {
+    if let Some((line, content, _)) = open_group {
+        sink.report(ParseError::InvalidFormat {
+            line,
+            content: content.trim().to_string(),
+        })?;
+    }
+
+    // Every LOOP needs a matching ENDLOOP
+    while let Some((line, content, row)) = open_loops.pop() {
+        sink.report(ParseError::InvalidFormat {
+            line,
+            content: content.trim().to_string(),
+        })?;
+        commands.remove(row);
+    }
+    
+    // Blank lines alone don't make a combo
+    let blank =
+        |cmd: &Arc<ComboCommand>| cmd.input_type == InputType::Comment && cmd.memo.is_empty();
+    if commands.iter().all(blank) {
         return Err(ParseError::EmptyFile);
     }
     
-    Ok(ComboFile { title, commands })
+    let mut combo = ComboFile {
+        title: String::new(),
+        metadata,
+        aliases,
+        commands,
+        footer,
+    };
+    combo.refresh_title();
+    for error in combo.clear_invalid_branch_targets() {
+        sink.report(error)?;
+    }
+    let mut errors = sink.errors;
+    errors.sort_by_key(|e| e.line().unwrap_or(usize::MAX));
+    Ok((combo, errors))
+}
+
+/// A combo within a multi-combo file
+#[derive(Debug, Clone)]
+pub struct NamedCombo {
+    /// Name from the `##` line (`None` for files without sections)
+    pub name: Option<String>,
+    pub combo: ComboFile,
+}
+
+impl NamedCombo {
+    /// Name shown to the player; falls back to the combo title
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.combo.title)
+    }
+}
+
+/// A file holding one or more combos separated by `## 名前` lines
+#[derive(Debug, Clone)]
+pub struct ComboLibraryFile {
+    pub combos: Vec<NamedCombo>,
+}
+
+impl ComboLibraryFile {
+    /// Names of the combos in file order
+    pub fn names(&self) -> Vec<String> {
+        self.combos
+            .iter()
+            .map(|c| c.display_name().to_string())
+            .collect()
+    }
+
+    /// Take the combo called `name`, or the first one when `name` is `None`
+    pub fn take(self, name: Option<&str>) -> Option<NamedCombo> {
+        let mut combos = self.combos.into_iter();
+        match name {
+            Some(name) => combos.find(|c| c.display_name() == name),
+            None => combos.next(),
+        }
+    }
+}
+
+/// Move line numbers of a section's parse error to file line numbers
+fn offset_error_line(error: ParseError, offset: usize) -> ParseError {
+    match error {
+        ParseError::InvalidFormat { line, content } => ParseError::InvalidFormat {
+            line: line + offset,
+            content,
+        },
+        ParseError::InvalidKey { line, key } => ParseError::InvalidKey {
+            line: line + offset,
+            key,
+        },
+        other => other,
+    }
+}
+
+/// Parse text content that may hold several `##` combos
+///
+/// Only blank, comment and version lines may come before the first `##`
+/// line. Files without separators yield a single unnamed combo.
+pub fn parse_combo_library_content(content: &str) -> Result<ComboLibraryFile, ParseError> {
+    let migrated = migrate::migrate_lines(content)?.map(|(migrated, _)| migrated);
+    let content = migrated.as_deref().unwrap_or(content);
+    let lines: Vec<&str> = content.lines().collect();
+    let separators: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.trim().starts_with(COMBO_SEPARATOR))
+        .map(|(i, _)| i)
+        .collect();
+
+    let Some(&first) = separators.first() else {
+        let combo = parse_current_content(content)?;
+        return Ok(ComboLibraryFile {
+            combos: vec![NamedCombo { name: None, combo }],
+        });
+    };
+
+    for (i, line) in lines[..first].iter().enumerate() {
+        let line = line.trim();
+        let ignored = line.is_empty()
+            || COMMENT_MARKERS.iter().any(|m| line.starts_with(m))
+            || migrate::version_line(line).is_some();
+        if !ignored {
+            return Err(ParseError::InvalidFormat {
+                line: i + 1,
+                content: line.to_string(),
+            });
+        }
+    }
+
+    let mut combos: Vec<NamedCombo> = Vec::new();
+    for (n, &start) in separators.iter().enumerate() {
+        let end = separators.get(n + 1).copied().unwrap_or(lines.len());
+        let name = lines[start].trim()[COMBO_SEPARATOR.len()..].trim();
+        if name.is_empty() || combos.iter().any(|c| c.name.as_deref() == Some(name)) {
+            return Err(ParseError::InvalidFormat {
+                line: start + 1,
+                content: lines[start].trim().to_string(),
+            });
+        }
+
+        let section = lines[start + 1..end].join("\n");
+        let combo = parse_current_content(&section).map_err(|e| offset_error_line(e, start + 1))?;
+        combos.push(NamedCombo {
+            name: Some(name.to_string()),
+            combo,
+        });
+    }
+
+    Ok(ComboLibraryFile { combos })
+}
+
+/// Parse a combo file that may hold several combos (YAML files hold one)
+///
+/// `@include` rows are replaced with the steps of the included files.
+pub fn parse_combo_library_file<P: AsRef<Path>>(path: P) -> Result<ComboLibraryFile, ParseError> {
+    let path = path.as_ref();
+    let format = ComboFormat::from_path(path);
+    let content = encoding::read_text(path)?;
+    let mut library = match format {
+        ComboFormat::Text => parse_combo_library_content(&content)?,
+        ComboFormat::Yaml => ComboLibraryFile {
+            combos: vec![NamedCombo {
+                name: None,
+                combo: parse_combo_yaml(&content)?,
+            }],
+        },
+    };
+    for named in &mut library.combos {
+        include::resolve_includes(&mut named.combo, path)?;
+    }
+    Ok(library)
+}
+
+/// Combo file format, chosen by file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComboFormat {
+    /// Positional `KEY,CHARACTER,SKILL_TYPE,MEMO|` lines
+    Text,
+    /// YAML with explicit fields
+    Yaml,
+}
+
+impl ComboFormat {
+    /// Detect the format from a file path (`.yaml`/`.yml` are YAML, anything else is text)
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml") => {
+                ComboFormat::Yaml
+            }
+            _ => ComboFormat::Text,
+        }
+    }
 }
 
 /// Parse combo file from path
 pub fn parse_combo_file<P: AsRef<Path>>(path: P) -> Result<ComboFile, ParseError> {
-    let content = std::fs::read_to_string(path).map_err(|e| ParseError::IoError(e.to_string()))?;
-    parse_combo_content(&content)
+    let format = ComboFormat::from_path(&path);
+    let content = encoding::read_text(path)?;
+    match format {
+        ComboFormat::Text => parse_combo_content(&content),
+        ComboFormat::Yaml => parse_combo_yaml(&content),
+    }
+}
+
+/// Key field of a YAML step (`key: 2` and `key: "U2"` are both accepted)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum YamlKey {
+    // Signed so an unquoted `key: -2` still reads as a release step
+    Number(i64),
+    Text(String),
+}
+
+impl YamlKey {
+    fn as_string(&self) -> String {
+        match self {
+            YamlKey::Number(n) => n.to_string(),
+            YamlKey::Text(s) => s.trim().to_string(),
+        }
+    }
+}
+
+/// A branch option in YAML (`goto` is a 1-based step number)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct YamlBranchOption {
+    label: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    goto: Option<usize>,
+}
+
+/// A YAML step: an input, a branch, or a section with nested steps
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum YamlStep {
+    Comment {
+        comment: String,
+    },
+    Info {
+        info: String,
+    },
+    Include {
+        include: String,
+    },
+    Loop {
+        #[serde(rename = "loop")]
+        count: u32,
+        #[serde(default)]
+        steps: Vec<YamlStep>,
+    },
+    Branch {
+        branch: String,
+        options: Vec<YamlBranchOption>,
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        memo: String,
+    },
+    Section {
+        section: String,
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        memo: String,
+        #[serde(default)]
+        steps: Vec<YamlStep>,
+    },
+    Input {
+        key: YamlKey,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        hold_ms: Option<u64>,
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        character: String,
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        skill: String,
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        memo: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        gap_ms: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sp_cost: Option<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sp_gain: Option<u32>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        optional: bool,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        icon: Option<String>,
+        /// Cancel window; consecutive steps with the same number form one
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group: Option<u32>,
+    },
+}
+
+/// YAML combo document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct YamlCombo {
+    /// Format version (YAML files start at the current one)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    /// Memo of the title row
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    memo: String,
+    #[serde(default, skip_serializing_if = "ComboMetadata::is_empty")]
+    metadata: ComboMetadata,
+    #[serde(default)]
+    steps: Vec<YamlStep>,
+}
+
+/// Flatten YAML steps into combo commands (sections become title lines)
+fn flatten_yaml_steps(
+    steps: Vec<YamlStep>,
+    commands: &mut Vec<Arc<ComboCommand>>,
+) -> Result<(), ParseError> {
+    for step in steps {
+        match step {
+            YamlStep::Section {
+                section,
+                memo,
+                steps,
+            } => {
+                commands.push(Arc::new(ComboCommand::title(&section).with_memo(&memo)));
+                flatten_yaml_steps(steps, commands)?;
+            }
+            YamlStep::Comment { comment } => {
+                let line = if COMMENT_MARKERS.iter().any(|m| comment.starts_with(m)) {
+                    comment
+                } else {
+                    format!("// {}", comment)
+                };
+                commands.push(Arc::new(marker_row(InputType::Comment).with_memo(&line)));
+            }
+            YamlStep::Info { info } => {
+                commands.push(Arc::new(marker_row(InputType::Info).with_memo(&info)));
+            }
+            YamlStep::Include { include } => {
+                commands.push(Arc::new(marker_row(InputType::Include { path: include })));
+            }
+            YamlStep::Loop { count, steps } => {
+                if count == 0 {
+                    return Err(ParseError::InvalidYaml(
+                        "loop count must be at least 1".to_string(),
+                    ));
+                }
+                commands.push(Arc::new(marker_row(InputType::LoopStart { count })));
+                flatten_yaml_steps(steps, commands)?;
+                commands.push(Arc::new(marker_row(InputType::LoopEnd)));
+            }
+            YamlStep::Branch {
+                branch,
+                options,
+                memo,
+            } => {
+                let options = options
+                    .into_iter()
+                    .map(|o| {
+                        let target = match o.goto {
+                            Some(step) => Some(step.checked_sub(1).ok_or_else(|| {
+                                ParseError::InvalidBranchTarget {
+                                    step: commands.len() + 1,
+                                    target: step,
+                                }
+                            })?),
+                            None => None,
+                        };
+                        Ok(BranchOption {
+                            label: o.label,
+                            target,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, ParseError>>()?;
+                commands.push(Arc::new(ComboCommand {
+                    input_type: InputType::Branch { options },
+                    memo,
+                    ..ComboCommand::tap(KeyIdentifier::Number(0), &branch, "")
+                }));
+            }
+            YamlStep::Input {
+                key,
+                hold_ms,
+                character,
+                skill,
+                memo,
+                gap_ms,
+                sp_cost,
+                sp_gain,
+                optional,
+                tags,
+                icon,
+                group,
+            } => {
+                if icon.as_deref().is_some_and(|i| !icon::is_valid_icon(i)) {
+                    return Err(ParseError::InvalidYaml(format!(
+                        "invalid icon '{}'",
+                        icon.unwrap_or_default()
+                    )));
+                }
+                let key_str = key.as_string();
+                let (direction, key_spec) = split_direction(&key_str);
+                let (key, mut input_type) =
+                    parse_key_spec(key_spec).ok_or_else(|| ParseError::InvalidKey {
+                        line: commands.len() + 1,
+                        key: key_str.clone(),
+                    })?;
+                if let Some(duration_ms) = hold_ms {
+                    input_type = InputType::Hold {
+                        duration_ms: Some(duration_ms),
+                    };
+                }
+                commands.push(Arc::new(ComboCommand {
+                    input_type,
+                    memo,
+                    direction,
+                    gap_ms,
+                    sp_cost,
+                    sp_gain,
+                    optional,
+                    tags,
+                    icon,
+                    group_id: group,
+                    ..ComboCommand::tap(key, &character, &skill)
+                }));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parse YAML combo content
+///
+/// ```yaml
+/// title: 物理
+/// steps:
+///   - key: U2
+///     character: リーフォン
+///     skill: 必殺技
+///   - section: ループ
+///     steps:
+///       - { key: 2, hold_ms: 800, memo: 長押し }
+///       - { key: E, character: チェン }
+/// ```
+///
+/// Sections become title lines; error line numbers refer to the flattened step.
+pub fn parse_combo_yaml(content: &str) -> Result<ComboFile, ParseError> {
+    let doc: YamlCombo =
+        serde_yaml::from_str(content).map_err(|e| ParseError::InvalidYaml(e.to_string()))?;
+    if let Some(version) = doc.version.filter(|&v| v > CURRENT_FORMAT_VERSION) {
+        return Err(ParseError::UnsupportedVersion(version));
+    }
+
+    let mut commands = Vec::new();
+    if let Some(title) = doc.title {
+        flatten_yaml_steps(
+            vec![YamlStep::Section {
+                section: title,
+                memo: doc.memo,
+                steps: Vec::new(),
+            }],
+            &mut commands,
+        )?;
+    }
+    flatten_yaml_steps(doc.steps, &mut commands)?;
+
+    if commands.is_empty() {
+        return Err(ParseError::EmptyFile);
+    }
+
+    let mut combo = ComboFile {
+        title: String::new(),
+        metadata: doc.metadata,
+        aliases: Vec::new(),
+        commands,
+        footer: Vec::new(),
+    };
+    combo.renumber_groups();
+    combo.refresh_title();
+    combo.validate_branches()?;
+    Ok(combo)
+}
+
+/// Canonical text of one row (without the line break)
+fn format_row(cmd: &ComboCommand, aliases: &[CharacterAlias]) -> String {
+    match &cmd.input_type {
+        InputType::LoopStart { count } => return format!("{} {}", LOOP_START_MARKER, count),
+        InputType::LoopEnd => return LOOP_END_MARKER.to_string(),
+        InputType::Comment => return cmd.memo.clone(),
+        InputType::Info => return format!("{}{}", INFO_MARKER, cmd.memo),
+        InputType::Include { path } => return include::format_include(path),
+        InputType::Branch { options } => {
+            return format!(
+                "{},{},{},{}{}|",
+                BRANCH_MARKER,
+                cmd.character,
+                format_branch_options(options),
+                cmd.memo,
+                extra_columns(cmd)
+            );
+        }
+        _ => {}
+    }
+
+    let (key_str, character) = if cmd.is_title {
+        ("#".to_string(), cmd.character.as_str())
+    } else {
+        let key_base = key_label(&cmd.key);
+        let key_str = match &cmd.input_type {
+            InputType::Hold {
+                duration_ms: Some(duration_ms),
+            } => format!("U{}:{}", key_base, duration_ms),
+            InputType::Hold { duration_ms: None } => format!("U{}", key_base),
+            InputType::HoldStart => format!("{}{}", HOLD_START_MARKER, key_base),
+            InputType::HoldRelease => format!("{}{}", HOLD_RELEASE_MARKER, key_base),
+            InputType::Chord(keys) => chord_label(keys),
+            InputType::ChargedHold { stages } => charge_label(&cmd.key, stages),
+            InputType::Sequence { keys, window_ms } => sequence_label(keys, *window_ms),
+            InputType::DoubleTap { window_ms } => double_tap_label(&cmd.key, *window_ms),
+            InputType::MultiTap { count, window_ms } => {
+                multi_tap_label(&cmd.key, *count, *window_ms)
+            }
+            InputType::Wait { duration_ms } => format!("{}:{}", WAIT_MARKER, duration_ms),
+            _ => key_base,
+        };
+        let optional = if cmd.optional { OPTIONAL_MARKER } else { "" };
+        (
+            format!("{}{}{}", optional, direction_prefix(cmd.direction), key_str),
+            alias::abbreviate(aliases, &cmd.character),
+        )
+    };
+    format!(
+        "{},{},{},{}{}|",
+        key_str,
+        character,
+        cmd.skill_type,
+        cmd.memo,
+        extra_columns(cmd)
+    )
 }
 
 /// Serialize combo file to string
 pub fn serialize_combo_file(combo: &ComboFile) -> String {
-    let mut output = String::new();
+    let mut output = migrate::version_header();
+    output.push('\n');
+
+    for (key, value) in combo.metadata.entries() {
+        output.push_str(&format!("{}{}: {}\n", METADATA_MARKER, key, value));
+    }
+    for entry in &combo.aliases {
+        output.push_str(&alias::format_alias(entry));
+        output.push('\n');
+    }
     
-    for cmd in &combo.commands {
-        let key_str = if cmd.is_title {
-            "#".to_string()
-        } else {
-            let key_base = match &cmd.key {
-                KeyIdentifier::Number(n) => n.to_string(),
-                KeyIdentifier::Chain => "E".to_string(),
-                KeyIdentifier::HeavyAttack => "L".to_string(),
-                KeyIdentifier::MouseLeft => "L".to_string(),
-            };
-            
-            match &cmd.input_type {
-                InputType::Tap => key_base,
-                InputType::Hold { .. } => format!("U{}", key_base),
-            }
+    let mut open_group = None;
+    let mut repeated = 0;
+    for (index, cmd) in combo.commands.iter().enumerate() {
+        // Copies already written as one `3xL` line
+        if repeated > 0 {
+            repeated -= 1;
+            continue;
+        }
+        // Comments stay inside an open cancel window; other rows close it
+        let group = match (cmd.is_step(), &cmd.input_type) {
+            (true, _) => cmd.group_id,
+            (false, InputType::Comment) => open_group,
+            _ => None,
         };
-        
-        output.push_str(&format!(
-            "{},{},{},{}|\n",
-            key_str, cmd.character, cmd.skill_type, cmd.memo
-        ));
+        if group != open_group {
+            if open_group.is_some() {
+                output.push_str(GROUP_END_MARKER);
+                output.push('\n');
+            }
+            if group.is_some() {
+                output.push_str(GROUP_START_MARKER);
+                output.push('\n');
+            }
+            open_group = group;
+        }
+
+        let canonical = |row: &ComboCommand| format_row(row, &combo.aliases);
+        match source::unchanged_repeat(&combo.commands[index..], canonical) {
+            Some((text, count)) => {
+                output.push_str(text);
+                repeated = count - 1;
+            }
+            None => output.push_str(source::render(cmd.source.as_ref(), &canonical(cmd))),
+        }
+        output.push('\n');
+    }
+    if open_group.is_some() {
+        output.push_str(GROUP_END_MARKER);
+        output.push('\n');
     }
     
     output.push_str(EOF_MARKER);
     output.push('\n');
+    for line in &combo.footer {
+        output.push_str(line);
+        output.push('\n');
+    }
     
     output
 }
 
+/// Open container while building the YAML step tree
+enum YamlFrame {
+    Root,
+    Section { section: String, memo: String },
+    Loop { count: u32 },
+}
+
+/// Close the innermost open container, moving it into its parent
+fn close_yaml_frame(stack: &mut Vec<(YamlFrame, Vec<YamlStep>)>) {
+    if stack.len() < 2 {
+        return;
+    }
+    let (frame, steps) = stack.pop().expect("stack has a parent frame");
+    let step = match frame {
+        YamlFrame::Section { section, memo } => YamlStep::Section {
+            section,
+            memo,
+            steps,
+        },
+        YamlFrame::Loop { count } => YamlStep::Loop { count, steps },
+        YamlFrame::Root => unreachable!("root frame is never closed"),
+    };
+    if let Some((_, parent)) = stack.last_mut() {
+        parent.push(step);
+    }
+}
+
+/// Serialize combo file to YAML
+///
+/// The first title line becomes `title`; later title lines open a section that
+/// holds the steps following it. Loop markers become nested `loop` blocks.
+pub fn serialize_combo_yaml(combo: &ComboFile) -> Result<String, ParseError> {
+    let mut title = None;
+    let mut memo = String::new();
+    let mut stack = vec![(YamlFrame::Root, Vec::new())];
+
+    for cmd in &combo.commands {
+        if cmd.is_title {
+            if title.is_none() && stack.len() == 1 && stack[0].1.is_empty() {
+                title = Some(cmd.character.clone());
+                memo = cmd.memo.clone();
+            } else {
+                if matches!(stack.last(), Some((YamlFrame::Section { .. }, _))) {
+                    close_yaml_frame(&mut stack);
+                }
+                stack.push((
+                    YamlFrame::Section {
+                        section: cmd.character.clone(),
+                        memo: cmd.memo.clone(),
+                    },
+                    Vec::new(),
+                ));
+            }
+            continue;
+        }
+
+        let step = match &cmd.input_type {
+            // Blank lines of text files have no YAML counterpart
+            InputType::Comment if cmd.memo.is_empty() => continue,
+            InputType::Comment => YamlStep::Comment {
+                comment: cmd.memo.clone(),
+            },
+            InputType::Info => YamlStep::Info {
+                info: cmd.memo.clone(),
+            },
+            InputType::Include { path } => YamlStep::Include {
+                include: path.clone(),
+            },
+            InputType::LoopStart { count } => {
+                stack.push((YamlFrame::Loop { count: *count }, Vec::new()));
+                continue;
+            }
+            InputType::LoopEnd => {
+                // Sections opened inside the loop end with it
+                while matches!(stack.last(), Some((YamlFrame::Section { .. }, _))) {
+                    close_yaml_frame(&mut stack);
+                }
+                if matches!(stack.last(), Some((YamlFrame::Loop { .. }, _))) {
+                    close_yaml_frame(&mut stack);
+                }
+                continue;
+            }
+            InputType::Branch { options } => YamlStep::Branch {
+                branch: cmd.character.clone(),
+                options: options
+                    .iter()
+                    .map(|o| YamlBranchOption {
+                        label: o.label.clone(),
+                        goto: o.target.map(|t| t + 1),
+                    })
+                    .collect(),
+                memo: cmd.memo.clone(),
+            },
+            input_type => {
+                let (key, hold_ms) = match input_type {
+                    InputType::Hold {
+                        duration_ms: Some(duration_ms),
+                    } => (key_label(&cmd.key), Some(*duration_ms)),
+                    InputType::Hold { duration_ms: None } => {
+                        (format!("U{}", key_label(&cmd.key)), None)
+                    }
+                    InputType::HoldStart => (
+                        format!("{}{}", HOLD_START_MARKER, key_label(&cmd.key)),
+                        None,
+                    ),
+                    InputType::HoldRelease => (
+                        format!("{}{}", HOLD_RELEASE_MARKER, key_label(&cmd.key)),
+                        None,
+                    ),
+                    InputType::Chord(keys) => (chord_label(keys), None),
+                    InputType::ChargedHold { stages } => (charge_label(&cmd.key, stages), None),
+                    InputType::Sequence { keys, window_ms } => {
+                        (sequence_label(keys, *window_ms), None)
+                    }
+                    InputType::DoubleTap { window_ms } => {
+                        (double_tap_label(&cmd.key, *window_ms), None)
+                    }
+                    InputType::MultiTap { count, window_ms } => {
+                        (multi_tap_label(&cmd.key, *count, *window_ms), None)
+                    }
+                    InputType::Wait { duration_ms } => {
+                        (format!("{}:{}", WAIT_MARKER, duration_ms), None)
+                    }
+                    _ => (key_label(&cmd.key), None),
+                };
+                YamlStep::Input {
+                    key: YamlKey::Text(format!("{}{}", direction_prefix(cmd.direction), key)),
+                    hold_ms,
+                    character: cmd.character.clone(),
+                    skill: cmd.skill_type.clone(),
+                    memo: cmd.memo.clone(),
+                    gap_ms: cmd.gap_ms,
+                    sp_cost: cmd.sp_cost,
+                    sp_gain: cmd.sp_gain,
+                    optional: cmd.optional,
+                    tags: cmd.tags.clone(),
+                    icon: cmd.icon.clone(),
+                    group: cmd.group_id,
+                }
+            }
+        };
+        if let Some((_, steps)) = stack.last_mut() {
+            steps.push(step);
+        }
+    }
+
+    while stack.len() > 1 {
+        close_yaml_frame(&mut stack);
+    }
+    if let Some((_, steps)) = stack.last_mut() {
+        steps.extend(
+            combo
+                .footer
+                .iter()
+                .filter(|line| !line.is_empty())
+                .map(|line| YamlStep::Comment {
+                    comment: line.clone(),
+                }),
+        );
+    }
+    let doc = YamlCombo {
+        version: Some(CURRENT_FORMAT_VERSION),
+        title,
+        memo,
+        metadata: combo.metadata.clone(),
+        steps: stack.pop().map(|(_, steps)| steps).unwrap_or_default(),
+    };
+    serde_yaml::to_string(&doc).map_err(|e| ParseError::InvalidYaml(e.to_string()))
+}
+
+/// Loop pass counters used while navigating a combo
+#[derive(Debug, Clone, Default)]
+pub struct LoopState {
+    /// Completed passes per loop, in `ComboFile::loops` order
+    passes: Vec<u32>,
+}
+
+impl LoopState {
+    /// Forget all pass counts
+    pub fn reset(&mut self) {
+        self.passes.clear();
+    }
+
+    /// Get the step that follows `index`, repeating loop blocks as needed
+    pub fn next_index(&mut self, combo: &ComboFile, index: usize) -> usize {
+        let loops = combo.loops();
+        self.passes.resize(loops.len(), 0);
+
+        // Innermost loops ending at this step get the first say
+        let mut ending: Vec<usize> = (0..loops.len())
+            .filter(|&i| loops[i].end == index)
+            .collect();
+        ending.sort_by_key(|&i| loops[i].span());
+        for i in ending {
+            if self.passes[i] + 1 < loops[i].count {
+                self.passes[i] += 1;
+                return loops[i].start;
+            }
+            self.passes[i] = 0;
+        }
+
+        (index + 1) % combo.step_count().max(1)
+    }
+
+    /// Note a jump to `index`, resetting loops that no longer contain it
+    pub fn jump(&mut self, combo: &ComboFile, index: usize) {
+        let loops = combo.loops();
+        self.passes.resize(loops.len(), 0);
+        for (passes, block) in self.passes.iter_mut().zip(&loops) {
+            if !block.contains(index) {
+                *passes = 0;
+            }
+        }
+    }
+
+    /// Passes left (including the current one) of the innermost loop containing `index`
+    pub fn remaining(&self, combo: &ComboFile, index: usize) -> Option<u32> {
+        combo
+            .loops()
+            .iter()
+            .enumerate()
+            .filter(|(_, block)| block.contains(index))
+            .min_by_key(|(_, block)| block.span())
+            .map(|(i, block)| block.count - self.passes.get(i).copied().unwrap_or(0))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -276,7 +2449,36 @@ mod tests {
         let result = parse_combo_content(content).unwrap();
         assert_eq!(result.commands.len(), 1);
         assert_eq!(result.commands[0].key, KeyIdentifier::Number(2));
-        assert!(matches!(result.commands[0].input_type, InputType::Hold { duration_ms: 300 }));
+        assert!(matches!(
+            result.commands[0].input_type,
+            InputType::Hold { duration_ms: None }
+        ));
+    }
+    
+    #[test]
+    fn test_parse_hold_duration() {
+        let result =
+            parse_combo_content("U3:800,ポグ,必殺技,|\nU2:300,リーフォン,必殺技,|").unwrap();
+        assert_eq!(result.commands[0].key, KeyIdentifier::Number(3));
+        assert_eq!(
+            result.commands[0].input_type,
+            InputType::Hold {
+                duration_ms: Some(800)
+            }
+        );
+        // An explicit duration equal to the default threshold is kept
+        assert_eq!(
+            result.commands[1].input_type,
+            InputType::Hold {
+                duration_ms: Some(300)
+            }
+        );
+        let text = serialize_combo_file(&result);
+        assert!(text.contains("\nU3:800,"));
+        assert!(text.contains("\nU2:300,"));
+        let yaml = parse_combo_yaml(&serialize_combo_yaml(&result).unwrap()).unwrap();
+        assert_eq!(yaml.commands[1].input_type, result.commands[1].input_type);
+        assert!(parse_combo_content("U3:abc,ポグ,必殺技,|").is_err());
     }
     
     #[test]
@@ -287,6 +2489,363 @@ mod tests {
         assert_eq!(result.commands[0].key, KeyIdentifier::Chain);
     }
     
+    #[test]
+    fn test_parse_extra_keys() {
+        let content = "D,管理人,回避,|\nSpace,管理人,回避,|\nQ,ペリカ,交代,|\nURC,チェン,狙撃,|";
+        let result = parse_combo_content(content).unwrap();
+        assert_eq!(result.commands[0].key, KeyIdentifier::Dodge);
+        assert_eq!(result.commands[1].key, KeyIdentifier::Dodge);
+        assert_eq!(result.commands[2].key, KeyIdentifier::Swap);
+        assert_eq!(result.commands[3].key, KeyIdentifier::MouseRight);
+        assert!(matches!(
+            result.commands[3].input_type,
+            InputType::Hold { .. }
+        ));
+        assert!(serialize_combo_file(&result).contains("\nD,管理人,回避,|\nSpace,"));
+
+        let result =
+            parse_combo_content("MC,管理人,重撃,|\nx1,管理人,回避,|\nUX2,管理人,,|").unwrap();
+        assert_eq!(result.commands[0].key, KeyIdentifier::MouseMiddle);
+        assert_eq!(result.commands[1].key, KeyIdentifier::MouseX1);
+        assert_eq!(result.commands[2].key, KeyIdentifier::MouseX2);
+
+        let result = parse_combo_content("wu,管理人,重撃,|\nWD,管理人,重撃,|").unwrap();
+        assert_eq!(
+            result.commands[0].key,
+            KeyIdentifier::Wheel(WheelDirection::Up)
+        );
+        assert_eq!(result.commands[0].input_type, InputType::Tap);
+        assert_eq!(
+            result.commands[1].key,
+            KeyIdentifier::Wheel(WheelDirection::Down)
+        );
+        assert!(parse_combo_content("UWU,管理人,重撃,|").is_err());
+        assert!(parse_combo_content("1+WD,管理人,重撃,|").is_err());
+
+        let result = parse_combo_content("+2,リーフォン,必殺技,|\n-2,リーフォン,必殺技,|").unwrap();
+        assert_eq!(result.commands[0].input_type, InputType::HoldStart);
+        assert_eq!(result.commands[1].input_type, InputType::HoldRelease);
+        assert_eq!(result.commands[1].key, KeyIdentifier::Number(2));
+        assert!(serialize_combo_file(&result).contains("+2,リーフォン,必殺技,|\n-2,"));
+    }
+    
+    #[test]
+    fn test_parse_chord() {
+        let result = parse_combo_content("1+E,管理人,連携,|").unwrap();
+        assert_eq!(
+            result.commands[0].input_type,
+            InputType::Chord(vec![KeyIdentifier::Number(1), KeyIdentifier::Chain])
+        );
+        assert!(serialize_combo_file(&result).contains("\n1+E,"));
+        assert!(parse_combo_content("1+X,管理人,連携,|").is_err());
+    }
+
+    #[test]
+    fn test_parse_sequence() {
+        let result = parse_combo_content("2>E,ポグ,戦技,追撃|\n1>2>3:800,管理人,戦技,|").unwrap();
+        assert_eq!(
+            result.commands[0].input_type,
+            InputType::Sequence {
+                keys: vec![KeyIdentifier::Number(2), KeyIdentifier::Chain],
+                window_ms: DEFAULT_SEQUENCE_WINDOW_MS,
+            }
+        );
+        assert_eq!(result.commands[0].key, KeyIdentifier::Number(2));
+        let serialized = serialize_combo_file(&result);
+        assert!(serialized.contains("\n2>E,"));
+        assert!(serialized.contains("\n1>2>3:800,"));
+        assert!(parse_combo_content("2>,ポグ,戦技,|").is_err());
+    }
+
+    #[test]
+    fn test_parse_multi_tap() {
+        let result = parse_combo_content("L*3,管理人,通常攻撃,連打|\nE*5:250,ポグ,連携,|").unwrap();
+        assert_eq!(result.commands[0].key, KeyIdentifier::HeavyAttack);
+        assert_eq!(
+            result.commands[0].input_type,
+            InputType::MultiTap {
+                count: 3,
+                window_ms: DEFAULT_MULTI_TAP_WINDOW_MS,
+            }
+        );
+        let serialized = serialize_combo_file(&result);
+        assert!(serialized.contains("\nL*3,"));
+        assert!(serialized.contains("\nE*5:250,"));
+        assert!(parse_combo_content("L*1,管理人,通常攻撃,|").is_err());
+    }
+
+    #[test]
+    fn test_parse_double_tap() {
+        let result = parse_combo_content("DD,管理人,回避,ダッシュ|\nd2:250,ポグ,戦技,|").unwrap();
+        assert_eq!(result.commands[0].key, KeyIdentifier::Dodge);
+        assert_eq!(
+            result.commands[0].input_type,
+            InputType::DoubleTap {
+                window_ms: DEFAULT_DOUBLE_TAP_WINDOW_MS
+            }
+        );
+        assert_eq!(
+            result.commands[1].input_type,
+            InputType::DoubleTap { window_ms: 250 }
+        );
+
+        let yaml = serialize_combo_yaml(&result).unwrap();
+        assert!(yaml.contains("key: DD\n"));
+        assert!(yaml.contains("key: D2:250\n"));
+        assert!(parse_combo_content("DWU,管理人,重撃,|").is_err());
+        assert!(parse_combo_content("D2:0,ポグ,戦技,|").is_err());
+    }
+    
+    #[test]
+    fn test_parse_charged_hold() {
+        let result = parse_combo_content("u2:400/1000,ポグ,必殺技,|").unwrap();
+        assert_eq!(
+            result.commands[0].input_type,
+            InputType::ChargedHold {
+                stages: vec![400, 1000]
+            }
+        );
+
+        let yaml = serialize_combo_yaml(&result).unwrap();
+        assert!(yaml.contains("key: U2:400/1000\n"));
+        assert!(parse_combo_content("U2:1000/400,ポグ,必殺技,|").is_err());
+        assert!(parse_combo_content("U2:0/400,ポグ,必殺技,|").is_err());
+    }
+
+    #[test]
+    fn test_parse_direction_prefix() {
+        let result =
+            parse_combo_content("W~L,管理人,重撃,|\nD~U2:800,管理人,,|\nS+LC,管理人,,|").unwrap();
+        assert_eq!(result.commands[0].direction, Some(Direction::Forward));
+        assert_eq!(result.commands[0].key, KeyIdentifier::HeavyAttack);
+        assert_eq!(result.commands[1].direction, Some(Direction::Right));
+        assert_eq!(
+            result.commands[1].input_type,
+            InputType::Hold {
+                duration_ms: Some(800)
+            }
+        );
+        assert_eq!(result.commands[2].key, KeyIdentifier::MouseLeft);
+
+        let output = serialize_combo_file(&result);
+        assert!(output.contains("\nW~L,"));
+        assert!(output.contains("\nD~U2:800,"));
+        assert!(output.contains("\nS+LC,"));
+
+        // `D+E` is the dodge + chain chord, not movement
+        let chord = parse_combo_content("D+E,チェン,連携,|").unwrap();
+        assert_eq!(chord.commands[0].direction, None);
+        assert_eq!(
+            chord.commands[0].input_type,
+            InputType::Chord(vec![KeyIdentifier::Dodge, KeyIdentifier::Chain])
+        );
+    }
+    
+    #[test]
+    fn test_parse_optional_step() {
+        let content = "?L,管理人,重撃,|\n?W~L,管理人,重撃,|\nE,チェン,連携,|";
+        let result = parse_combo_content(content).unwrap();
+        assert!(result.commands[0].optional);
+        assert_eq!(result.commands[0].key, KeyIdentifier::HeavyAttack);
+        assert_eq!(result.commands[1].direction, Some(Direction::Forward));
+        assert!(!result.commands[2].optional);
+
+        let output = serialize_combo_file(&result);
+        assert!(output.contains("\n?L,管理人,重撃,|\n?W~L,"));
+        assert!(parse_combo_content("?X,管理人,重撃,|").is_err());
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+        let content = "#,物理,,|\nLOOP 2\n2,リーフォン,戦技,|\nX,?,?,|\n@author: late\n?,続ける？,はい>9/いいえ,|\nE,チェン,連携,,abc|\nENDLOOP\nENDLOOP\nLOOP 3\n3,ポグ,戦技,|";
+        assert!(parse_combo_content(content).is_err());
+
+        let (combo, errors) = parse_combo_content_lenient(content).unwrap();
+        assert_eq!(combo.step_count(), 3);
+        assert_eq!(combo.loops().len(), 1);
+        let lines: Vec<Option<usize>> = errors.iter().map(ParseError::line).collect();
+        assert_eq!(lines, [Some(4), Some(5), Some(7), Some(9), Some(10), None]);
+        assert!(matches!(
+            errors[5],
+            ParseError::InvalidBranchTarget { step: 2, target: 9 }
+        ));
+        match &combo.step(1).unwrap().input_type {
+            InputType::Branch { options } => assert!(options.iter().all(|o| o.target.is_none())),
+            _ => panic!("expected a branch"),
+        }
+
+        assert!(matches!(
+            parse_combo_content_lenient("X,?,?,|"),
+            Err(ParseError::EmptyFile)
+        ));
+    }
+
+    #[test]
+    fn test_parse_cancel_group() {
+        let content = "#,物理,,|\nGROUP\n2,リーフォン,戦技,|\n// 同時に\nE,チェン,連携,|\nENDGROUP\ngroup\nL,管理人,重撃,|\nendgroup\n3,ポグ,戦技,|";
+        let combo = parse_combo_content(content).unwrap();
+        let groups: Vec<Option<u32>> = combo.steps().map(|s| s.group_id).collect();
+        assert_eq!(groups, [Some(1), Some(1), Some(2), None]);
+        assert_eq!(
+            combo.step_group(1),
+            Some(StepGroup {
+                id: 1,
+                position: 2,
+                size: 2
+            })
+        );
+        assert_eq!(combo.step_group(3), None);
+
+        let output = serialize_combo_file(&combo);
+        assert!(output.contains(
+            "\nGROUP\n2,リーフォン,戦技,|\n// 同時に\nE,チェン,連携,|\nENDGROUP\nGROUP\nL,"
+        ));
+        assert!(output.contains("\nL,管理人,重撃,|\nENDGROUP\n3,"));
+        let yaml = parse_combo_yaml(&serialize_combo_yaml(&combo).unwrap()).unwrap();
+        let groups: Vec<Option<u32>> = yaml.steps().map(|s| s.group_id).collect();
+        assert_eq!(groups, [Some(1), Some(1), Some(2), None]);
+
+        assert!(parse_combo_content("GROUP\nGROUP\n2,リーフォン,戦技,|").is_err());
+        assert!(
+            parse_combo_content("GROUP\nLOOP 2\n2,リーフォン,戦技,|\nENDLOOP\nENDGROUP").is_err()
+        );
+        assert!(parse_combo_content("GROUP\n2,リーフォン,戦技,|").is_err());
+        assert!(parse_combo_content("2,リーフォン,戦技,|\nENDGROUP").is_err());
+    }
+
+    #[test]
+    fn test_parse_timing_gap() {
+        let content = "#,物理,,|\n2,リーフォン,戦技,,|\nE,チェン,連携,待つ,1200|";
+        let result = parse_combo_content(content).unwrap();
+        assert_eq!(result.step(0).unwrap().gap_ms, None);
+        assert_eq!(result.step(1).unwrap().gap_ms, Some(1200));
+        assert_eq!(result.step(1).unwrap().memo, "待つ");
+
+        let output = serialize_combo_file(&result);
+        assert!(output.contains("\n2,リーフォン,戦技,,|\n"));
+        assert!(output.contains("\nE,チェン,連携,待つ,1200|\n"));
+        let yaml = serialize_combo_yaml(&result).unwrap();
+        assert_eq!(
+            parse_combo_yaml(&yaml).unwrap().step(1).unwrap().gap_ms,
+            Some(1200)
+        );
+
+        assert!(parse_combo_content("E,チェン,連携,,1.2s|").is_err());
+    }
+
+    #[test]
+    fn test_step_rows() {
+        let combo = parse_combo_content("#,物理,,|\n2,リーフォン,戦技,|\n// メモ\nE,チェン,連携,|")
+            .unwrap();
+        assert_eq!(combo.row_of_step(0), Some(1));
+        assert_eq!(combo.row_of_step(1), Some(3));
+        assert_eq!(combo.row_of_step(2), None);
+        assert_eq!(combo.steps_before_row(2), 1);
+        assert_eq!(combo.steps_before_row(3), 1);
+        assert_eq!(combo.steps_before_row(4), 2);
+    }
+
+    #[test]
+    fn test_parse_step_tags() {
+        let content = "U2,リーフォン,必殺技,,,,ボス/ バースト,lifeng|\nE,チェン,連携,,,,,,予備|";
+        let result = parse_combo_content(content).unwrap();
+        assert_eq!(result.commands[0].tags, ["ボス", "バースト"]);
+        assert_eq!(result.commands[0].icon.as_deref(), Some("lifeng"));
+        assert!(parse_combo_content("E,チェン,連携,,,,,../chen.png|").is_err());
+        assert!(result.commands[1].tags.is_empty());
+        assert_eq!(result.commands[1].unknown_columns, ["予備"]);
+
+        let yaml = serialize_combo_yaml(&result).unwrap();
+        assert_eq!(
+            parse_combo_yaml(&yaml).unwrap().step(0).unwrap().tags,
+            ["ボス", "バースト"]
+        );
+
+        let mut combo = ComboFile::new("物理");
+        let mut step = ComboCommand::tap(KeyIdentifier::Chain, "チェン", "連携");
+        step.tags = vec!["ボス".to_string()];
+        combo.append(step);
+        assert!(serialize_combo_file(&combo).contains("\nE,チェン,連携,,,,ボス|\n"));
+    }
+
+    #[test]
+    fn test_parse_repeat() {
+        let content = "#,物理,,|\n3xL,リーフォン,通常攻撃,{n}段目|\n2,リーフォン,戦技,|\n";
+        let mut combo = parse_combo_content(content).unwrap();
+        let memos: Vec<_> = combo.steps().map(|s| s.memo.as_str()).collect();
+        assert_eq!(memos, ["1段目", "2段目", "3段目", ""]);
+        assert_eq!(combo.step(2).unwrap().key, KeyIdentifier::HeavyAttack);
+        let output = serialize_combo_file(&combo);
+        assert!(
+            output.ends_with("\n3xL,リーフォン,通常攻撃,{n}段目|\n2,リーフォン,戦技,|\n!!!!!\n")
+        );
+
+        // Removing a copy writes the rest out one row each
+        combo.remove(1);
+        let output = serialize_combo_file(&combo);
+        assert!(output.contains("\nL,リーフォン,通常攻撃,2段目|\nL,リーフォン,通常攻撃,3段目|\n"));
+
+        assert!(parse_combo_content("3x#,物理,,|").is_err());
+        assert!(parse_combo_content("0xL,リーフォン,通常攻撃,|").is_err());
+    }
+
+    #[test]
+    fn test_parse_info_rows() {
+        let content = "#,物理,,|\n2,リーフォン,戦技,|\n!ボス第2形態を待つ|\n// メモ\n!ゲージ確認\nE,チェン,連携,|\n!!!!!\n";
+        let combo = parse_combo_content(content).unwrap();
+        assert_eq!(combo.step_count(), 2);
+        assert_eq!(combo.notes_before(1), ["ボス第2形態を待つ", "ゲージ確認"]);
+        assert!(combo.notes_before(0).is_empty());
+        assert!(serialize_combo_file(&combo).ends_with(content));
+
+        let yaml = serialize_combo_yaml(&combo).unwrap();
+        assert!(yaml.contains("info: ボス第2形態を待つ"));
+        let combo = parse_combo_yaml(&yaml).unwrap();
+        assert_eq!(combo.notes_before(1), ["ボス第2形態を待つ", "ゲージ確認"]);
+    }
+
+    #[test]
+    fn test_parse_metadata_header() {
+        let content =
+            "// 共有用\n@author: saica\n@team: リーフォン/チェン\n#,物理,,|\n2,リーフォン,戦技,|";
+        let result = parse_combo_content(content).unwrap();
+        assert_eq!(result.metadata.author.as_deref(), Some("saica"));
+        assert_eq!(result.metadata.team, vec!["リーフォン", "チェン"]);
+        assert_eq!(result.title, "物理");
+
+        let output = serialize_combo_file(&result);
+        assert!(output.starts_with("@version: 2\n@author: saica\n@team: リーフォン/チェン\n"));
+        assert_eq!(
+            parse_combo_content(&output).unwrap().metadata,
+            result.metadata
+        );
+
+        assert!(parse_combo_content("@colour: red\n2,リーフォン,戦技,|").is_err());
+        assert!(parse_combo_content("2,リーフォン,戦技,|\n@author: saica").is_err());
+    }
+    
+    #[test]
+    fn test_parse_combo_library() {
+        let content = "// ボス\n## 第1形態\n#,物理,,|\n2,リーフォン,戦技,|\n!!!!!\n## 第2形態\nX,チェン,連携,|";
+        match parse_combo_library_content(content) {
+            Err(ParseError::InvalidKey { line, .. }) => assert_eq!(line, 7),
+            other => panic!("unexpected result: {:?}", other.map(|l| l.names())),
+        }
+
+        let content = content.replace("X,", "E,");
+        let library = parse_combo_library_content(&content).unwrap();
+        assert_eq!(library.names(), vec!["第1形態", "第2形態"]);
+        let second = library.take(Some("第2形態")).unwrap();
+        assert_eq!(second.combo.step_count(), 1);
+
+        assert!(matches!(
+            parse_combo_content(&content),
+            Err(ParseError::MultipleCombos)
+        ));
+        let single = parse_combo_library_content("#,物理,,|\n2,リーフォン,戦技,|").unwrap();
+        assert_eq!(single.names(), vec!["物理"]);
+    }
+    
     #[test]
     fn test_parse_full_file() {
         let content = r#"#,物理,,|
@@ -298,4 +2857,157 @@ E,チェン,連携,|
         assert_eq!(result.title, "物理");
         assert_eq!(result.commands.len(), 4); // Including title line
     }
+
+    #[test]
+    fn test_step_lookup_skips_titles() {
+        let content = "#,物理,,|\nU2,リーフォン,必殺技,|\nE,チェン,連携,|";
+        let result = parse_combo_content(content).unwrap();
+        assert_eq!(result.step_count(), 2);
+        assert_eq!(result.step(1).unwrap().key, KeyIdentifier::Chain);
+        assert!(result.step(2).is_none());
+    }
+
+    #[test]
+    fn test_yaml_round_trip() {
+        let content = r#"
+title: 物理
+steps:
+  - { key: U2, character: リーフォン, skill: 必殺技 }
+  - section: ループ
+    steps:
+      - { key: 2, hold_ms: 800, memo: 長押し }
+      - { key: E, character: チェン }
+"#;
+        let result = parse_combo_yaml(content).unwrap();
+        assert_eq!(result.title, "物理");
+        assert_eq!(result.commands.len(), 5);
+        assert_eq!(result.step_count(), 3);
+        assert_eq!(
+            result.step(1).unwrap().input_type,
+            InputType::Hold {
+                duration_ms: Some(800)
+            }
+        );
+
+        let reparsed = parse_combo_yaml(&serialize_combo_yaml(&result).unwrap()).unwrap();
+        assert_eq!(reparsed.commands.len(), 5);
+        assert_eq!(reparsed.commands[3].memo, "長押し");
+
+        // The title row's memo survives text → YAML → text
+        let text = parse_combo_content("#,物理,,ヘッダーのメモ|\n2,リーフォン,戦技,|").unwrap();
+        let yaml = parse_combo_yaml(&serialize_combo_yaml(&text).unwrap()).unwrap();
+        assert_eq!(yaml.commands[0].memo, "ヘッダーのメモ");
+        assert_eq!(serialize_combo_file(&yaml), serialize_combo_file(&text));
+        assert!(matches!(
+            parse_combo_yaml("steps:\n  - key: X"),
+            Err(ParseError::InvalidKey { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_branch() {
+        let content = "2,リーフォン,戦技,|\n?,必殺技は使える？,はい>4/いいえ,|\nE,チェン,連携,|\nU2,リーフォン,必殺技,|";
+        let result = parse_combo_content(content).unwrap();
+        let InputType::Branch { options } = &result.step(1).unwrap().input_type else {
+            panic!("expected branch");
+        };
+        assert_eq!(options[0].target, Some(3));
+        assert_eq!(options[1].target, None);
+
+        let reparsed = parse_combo_content(&serialize_combo_file(&result)).unwrap();
+        assert_eq!(
+            reparsed.step(1).unwrap().input_type,
+            result.step(1).unwrap().input_type
+        );
+        assert!(matches!(
+            parse_combo_content("?,x,a>9,|"),
+            Err(ParseError::InvalidBranchTarget { step: 1, target: 9 })
+        ));
+    }
+
+    #[test]
+    fn test_loop_navigation() {
+        let content = "#,物理,,|\nLOOP 2\n2,リーフォン,戦技,|\nLOOP 3\nE,チェン,連携,|\nENDLOOP\nENDLOOP\nL,管理人,重撃,|";
+        let combo = parse_combo_content(content).unwrap();
+        assert_eq!(combo.step_count(), 3);
+        assert_eq!(
+            combo.loops(),
+            vec![
+                LoopBlock {
+                    start: 1,
+                    end: 1,
+                    count: 3
+                },
+                LoopBlock {
+                    start: 0,
+                    end: 1,
+                    count: 2
+                },
+            ]
+        );
+
+        let mut state = LoopState::default();
+        let mut index = 0;
+        let mut visited = vec![index];
+        while visited.len() < 9 {
+            index = state.next_index(&combo, index);
+            visited.push(index);
+        }
+        assert_eq!(visited, vec![0, 1, 1, 1, 0, 1, 1, 1, 2]);
+        assert_eq!(state.remaining(&combo, 2), None);
+
+        let yaml = serialize_combo_yaml(&combo).unwrap();
+        assert_eq!(parse_combo_yaml(&yaml).unwrap().loops(), combo.loops());
+        let text = serialize_combo_file(&combo);
+        assert_eq!(parse_combo_content(&text).unwrap().loops(), combo.loops());
+        assert!(parse_combo_content("LOOP 2\n2,リーフォン,戦技,|").is_err());
+    }
+
+    #[test]
+    fn test_phases() {
+        let content = "2,リーフォン,戦技,|\n#,開幕,,|\nE,チェン,連携,|\n#,空,,|\n#,ループ,,|\nU2,リーフォン,必殺技,|\n#,末尾,,|";
+        let combo = parse_combo_content(content).unwrap();
+        let phases = combo.phases();
+        assert_eq!(phases.len(), 2);
+        assert_eq!(phases[1].name, "ループ");
+        assert_eq!(phases[1].start, 2);
+        assert_eq!(combo.phase_at(0), None);
+        assert_eq!(combo.phase_at(2), Some(1));
+    }
+
+    #[test]
+    fn test_builder() {
+        let skill = ComboCommand::tap(KeyIdentifier::Number(2), "リーフォン", "戦技");
+        let ult = ComboCommand::hold(KeyIdentifier::Number(2), None, "リーフォン", "必殺技");
+        let mut combo = ComboFile::new("物理");
+        combo
+            .append(skill)
+            .append(ComboCommand::tap(KeyIdentifier::Chain, "チェン", "連携"))
+            .insert(1, ult);
+        assert!(combo.reorder(3, 1));
+        assert!(!combo.reorder(0, 9));
+        assert_eq!(
+            combo.remove(2).unwrap().input_type,
+            InputType::Hold { duration_ms: None }
+        );
+
+        let text = serialize_combo_file(&combo);
+        assert_eq!(
+            text,
+            "@version: 2\n#,物理,,|\nE,チェン,連携,|\n2,リーフォン,戦技,|\n!!!!!\n"
+        );
+        assert_eq!(parse_combo_content(&text).unwrap().title, "物理");
+    }
+
+    #[test]
+    fn test_comments_round_trip() {
+        let content = "@version: 2\n#,物理,,|\n// 開幕は溜めてから\n2,リーフォン,戦技,|\n; 予備\nE,チェン,連携,|\n!!!!!\n// U = 必殺技\n";
+        let combo = parse_combo_content(content).unwrap();
+        assert_eq!(combo.step_count(), 2);
+        assert_eq!(combo.step(1).unwrap().key, KeyIdentifier::Chain);
+        assert_eq!(serialize_combo_file(&combo), content);
+
+        let yaml = parse_combo_yaml(&serialize_combo_yaml(&combo).unwrap()).unwrap();
+        assert_eq!(yaml.commands[1].memo, "// 開幕は溜めてから");
+    }
 }
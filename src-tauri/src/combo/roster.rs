@@ -0,0 +1,146 @@
+//! Operator roster
+//!
+//! Known operator names and skill types, used to catch typos in combo files.
+//! A roster is bundled with the app; a `roster.json` of the same shape next
+//! to the configuration replaces it when new operators are released.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Roster bundled with the application
+const BUILTIN_ROSTER: &str = include_str!("../../resources/roster.json");
+
+/// A playable operator
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Operator {
+    /// Name as written in combo files
+    pub name: String,
+    /// Other accepted spellings (full names, English names)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+}
+
+/// Operators and skill types a combo may use
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Roster {
+    pub operators: Vec<Operator>,
+    pub skill_types: Vec<String>,
+}
+
+/// Edit distance between two names, counted in characters
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            current.push(
+                (previous[j] + cost)
+                    .min(previous[j + 1] + 1)
+                    .min(current[j] + 1),
+            );
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Answer paired with the spelling closest to `name`
+///
+/// `candidates` pairs each spelling with the name it stands for. Only close
+/// matches count: about one typo per three characters.
+fn closest<'a>(
+    name: &str,
+    candidates: impl Iterator<Item = (&'a str, &'a str)>,
+) -> Option<&'a str> {
+    let limit = (name.chars().count() / 3).max(1);
+    candidates
+        .map(|(candidate, answer)| {
+            (
+                distance(&name.to_lowercase(), &candidate.to_lowercase()),
+                answer,
+            )
+        })
+        .filter(|&(d, _)| d <= limit)
+        .min_by_key(|&(d, _)| d)
+        .map(|(_, answer)| answer)
+}
+
+impl Roster {
+    /// Roster bundled with the application
+    pub fn builtin() -> Self {
+        serde_json::from_str(BUILTIN_ROSTER).expect("bundled roster is valid")
+    }
+
+    /// Read the roster at `path`, falling back to the bundled one when the
+    /// file is missing or invalid
+    pub fn load_or_builtin<P: AsRef<Path>>(path: P) -> Self {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(_) => return Self::builtin(),
+        };
+        serde_json::from_str(&content).unwrap_or_else(|e| {
+            eprintln!(
+                "[ERROR] Failed to read roster, using the bundled one: {}",
+                e
+            );
+            Self::builtin()
+        })
+    }
+
+    /// Whether `name` is an operator name or one of its aliases
+    pub fn has_operator(&self, name: &str) -> bool {
+        self.names()
+            .any(|(candidate, _)| candidate.eq_ignore_ascii_case(name))
+    }
+
+    /// Whether `skill_type` is a known skill type
+    pub fn has_skill_type(&self, skill_type: &str) -> bool {
+        self.skill_types.iter().any(|s| s == skill_type)
+    }
+
+    /// Operator name closest to a misspelled `name`
+    pub fn suggest_operator(&self, name: &str) -> Option<&str> {
+        closest(name, self.names())
+    }
+
+    /// Skill type closest to a misspelled `skill_type`
+    pub fn suggest_skill_type(&self, skill_type: &str) -> Option<&str> {
+        closest(
+            skill_type,
+            self.skill_types.iter().map(|s| (s.as_str(), s.as_str())),
+        )
+    }
+
+    /// Every accepted spelling with the operator name it stands for
+    fn names(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.operators.iter().flat_map(|op| {
+            std::iter::once(op.name.as_str())
+                .chain(op.aliases.iter().map(String::as_str))
+                .map(move |spelling| (spelling, op.name.as_str()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roster_lookup() {
+        let roster = Roster::builtin();
+        assert!(roster.has_operator("リーフォン"));
+        assert!(roster.has_operator("perlica"));
+        assert!(!roster.has_operator("リーフオン"));
+        assert_eq!(roster.suggest_operator("リーフオン"), Some("リーフォン"));
+        assert_eq!(roster.suggest_operator("Pogranichnk"), Some("ポグ"));
+        assert_eq!(roster.suggest_operator("まったく別の名前"), None);
+
+        assert!(roster.has_skill_type("戦技"));
+        assert_eq!(roster.suggest_skill_type("必殺"), Some("必殺技"));
+
+        let missing = std::env::temp_dir().join("akef_missing_roster.json");
+        assert_eq!(Roster::load_or_builtin(missing), roster);
+    }
+}
@@ -0,0 +1,146 @@
+//! Share codes
+//!
+//! A combo packed into one line (`ACN1:` + base64 of the deflated text
+//! format) so it can be pasted in chat. Decoding gives back the combo as it
+//! would be read from a file. The same code can be rendered as a QR code.
+
+use super::{parse_combo_content, serialize_combo_file, ComboFile, ParseError};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use qrcode::{Color, EcLevel, QrCode};
+use std::io::{Read, Write};
+
+/// Prefix of every share code; the digit is bumped if the payload changes
+const SHARE_CODE_PREFIX: &str = "ACN1:";
+
+/// Largest combo text a code may expand to
+const MAX_DECODED_BYTES: u64 = 1024 * 1024;
+
+/// Pixels per QR module
+const QR_MODULE_PX: usize = 8;
+
+/// Light border around the QR code, in modules (the spec asks for 4)
+const QR_QUIET_ZONE: usize = 4;
+
+/// QR code export error
+#[derive(Debug, Clone)]
+pub enum QrError {
+    /// Share code longer than the largest QR code holds
+    TooLarge,
+    /// PNG encoding failed
+    Image(String),
+}
+
+impl std::fmt::Display for QrError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QrError::TooLarge => write!(f, "Combo is too long for a QR code"),
+            QrError::Image(msg) => write!(f, "Cannot write QR image: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for QrError {}
+
+fn invalid(message: impl ToString) -> ParseError {
+    ParseError::InvalidShareCode(message.to_string())
+}
+
+/// Pack `combo` into a single-line share code
+pub fn encode_share_code(combo: &ComboFile) -> String {
+    let text = serialize_combo_file(combo);
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    // Writing into a Vec cannot fail
+    let _ = encoder.write_all(text.as_bytes());
+    let compressed = encoder.finish().unwrap_or_default();
+    format!(
+        "{}{}",
+        SHARE_CODE_PREFIX,
+        URL_SAFE_NO_PAD.encode(compressed)
+    )
+}
+
+/// Unpack a share code
+///
+/// Whitespace (line breaks added by chat clients) is ignored.
+pub fn decode_share_code(code: &str) -> Result<ComboFile, ParseError> {
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    let payload = code
+        .strip_prefix(SHARE_CODE_PREFIX)
+        .ok_or_else(|| invalid("missing ACN1: prefix"))?;
+    let compressed = URL_SAFE_NO_PAD.decode(payload).map_err(invalid)?;
+
+    let mut text = String::new();
+    DeflateDecoder::new(compressed.as_slice())
+        .take(MAX_DECODED_BYTES)
+        .read_to_string(&mut text)
+        .map_err(invalid)?;
+    parse_combo_content(&text)
+}
+
+/// Render the share code of `combo` as a black-on-white PNG QR code
+pub fn encode_share_qr(combo: &ComboFile) -> Result<Vec<u8>, QrError> {
+    let code = encode_share_code(combo);
+    // Low error correction leaves the most room; screens don't get scratched
+    let qr =
+        QrCode::with_error_correction_level(code, EcLevel::L).map_err(|_| QrError::TooLarge)?;
+
+    let modules = qr.width();
+    let colors = qr.to_colors();
+    let size = (modules + 2 * QR_QUIET_ZONE) * QR_MODULE_PX;
+    let mut pixels = vec![u8::MAX; size * size];
+    for (i, color) in colors.iter().enumerate() {
+        if *color != Color::Dark {
+            continue;
+        }
+        let x = (i % modules + QR_QUIET_ZONE) * QR_MODULE_PX;
+        let y = (i / modules + QR_QUIET_ZONE) * QR_MODULE_PX;
+        for row in y..y + QR_MODULE_PX {
+            pixels[row * size + x..row * size + x + QR_MODULE_PX].fill(0);
+        }
+    }
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, size as u32, size as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&pixels))
+        .map_err(|e| QrError::Image(e.to_string()))?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_share_code_round_trip() {
+        let content = "@author: saica\n#,物理,,|\nU2,リーフォン,必殺技,{ja=溜める, en=Charge},,-100|\nE,チェン,連携,|";
+        let combo = parse_combo_content(content).unwrap();
+
+        let code = encode_share_code(&combo);
+        assert!(code.starts_with(SHARE_CODE_PREFIX));
+        assert!(!code.contains(['\n', ',', '|']));
+
+        let (head, tail) = code.split_at(20);
+        let decoded = decode_share_code(&format!("{}\n{}", head, tail)).unwrap();
+        assert_eq!(decoded.title, "物理");
+        assert_eq!(decoded.metadata.author.as_deref(), Some("saica"));
+        assert_eq!(decoded.step_count(), 2);
+        assert_eq!(decoded.step(0).unwrap().sp_cost, Some(100));
+
+        assert!(matches!(
+            decode_share_code("ACN1:not-deflate"),
+            Err(ParseError::InvalidShareCode(_))
+        ));
+        assert!(decode_share_code("hello").is_err());
+
+        let png = encode_share_qr(&combo).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+    }
+}
@@ -0,0 +1,87 @@
+//! Source line tracking for lossless re-saving
+//!
+//! Every parsed row remembers the line it came from together with the text
+//! the serializer would write for it. A row that is saved unchanged is written
+//! back exactly as it was read, so key casing and spacing in shared files
+//! survive a parse, edit and save round trip.
+
+use super::{split_repeat, ComboCommand};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Original text of a parsed row
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceLine {
+    /// Line as written in the file
+    pub text: String,
+    /// Serializer output for the row as it was parsed
+    pub canonical: String,
+    /// Copy number (from 1) of a row expanded from a `3xL` line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat: Option<u32>,
+}
+
+/// Line to write for a row whose serializer output is `canonical`
+///
+/// The original text is kept while the row still serializes the same way;
+/// edited rows are written in canonical form.
+pub(super) fn render<'a>(source: Option<&'a SourceLine>, canonical: &'a str) -> &'a str {
+    match source {
+        Some(source) if source.canonical == canonical && source.repeat.is_none() => &source.text,
+        _ => canonical,
+    }
+}
+
+/// `3xL` line to write for the rows starting at `rows[0]`, with its count
+///
+/// The line is kept only while every copy is still there, in order and
+/// unchanged; otherwise each copy is written as a row of its own.
+pub(super) fn unchanged_repeat(
+    rows: &[Arc<ComboCommand>],
+    canonical: impl Fn(&ComboCommand) -> String,
+) -> Option<(&str, usize)> {
+    let source = rows.first()?.source.as_ref()?;
+    let (count, _) = split_repeat(&source.text)?;
+    let copies = rows.get(..count as usize)?;
+    let intact = copies.iter().zip(1..).all(|(row, n)| match &row.source {
+        Some(copy) => {
+            copy.text == source.text && copy.repeat == Some(n) && copy.canonical == canonical(row)
+        }
+        None => false,
+    });
+    intact.then_some((source.text.as_str(), copies.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::combo::{parse_combo_content, serialize_combo_file, ComboCommand, ParseError};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_lossless_round_trip() {
+        let rows = "#,物理,,|\n\n  e,チェン,連携,,,,メモ2|\nU2:800 , リーフォン ,必殺技,|\n// コメント\nloop 2\n3,ポグ,戦技,|\nENDLOOP\n!!!!!\n\n// 末尾\n";
+        let content = format!("@version: 2\n@author: saica\n{}", rows);
+        let mut combo = parse_combo_content(&content).unwrap();
+        assert_eq!(serialize_combo_file(&combo), content);
+
+        // Edited rows are rewritten; unknown columns stay
+        let step = combo.step(0).unwrap();
+        let edited = ComboCommand {
+            memo: "溜める".to_string(),
+            ..(**step).clone()
+        };
+        let index = combo
+            .commands
+            .iter()
+            .position(|c| Arc::ptr_eq(c, step))
+            .unwrap();
+        combo.commands[index] = Arc::new(edited);
+        let output = serialize_combo_file(&combo);
+        assert!(output.contains("\n\nE,チェン,連携,溜める,,,メモ2|\nU2:800 , リーフォン ,"));
+
+        assert!(matches!(
+            parse_combo_content("\n  \n"),
+            Err(ParseError::EmptyFile)
+        ));
+    }
+}
@@ -0,0 +1,169 @@
+//! Skill point (SP) annotations and projection
+//!
+//! Steps may note the SP they spend or generate. Walking one pass of the
+//! rotation (with loop blocks repeated) shows whether a skill would be cast
+//! before the team has the SP for it.
+
+use super::{ComboFile, LoopState, ParseError};
+use serde::{Deserialize, Serialize};
+
+/// SP at one step of the projected rotation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpStep {
+    /// Step index
+    pub index: usize,
+    pub sp_before: i64,
+    pub cost: u32,
+    pub gain: u32,
+    pub sp_after: i64,
+    /// Whether the step costs more SP than is available
+    pub starved: bool,
+}
+
+/// Running SP over one pass of the rotation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpProjection {
+    pub start: u32,
+    pub steps: Vec<SpStep>,
+    /// Lowest SP reached (negative when the rotation starves)
+    pub lowest: i64,
+    /// SP after the last step
+    pub end: i64,
+}
+
+impl SpProjection {
+    /// Whether any step is cast without enough SP
+    pub fn is_starved(&self) -> bool {
+        self.steps.iter().any(|s| s.starved)
+    }
+}
+
+/// Parse the SP column: `-100` spends, `+20` generates, `-100+20` both
+pub(super) fn parse_sp(
+    field: &str,
+    line_number: usize,
+    line: &str,
+) -> Result<(Option<u32>, Option<u32>), ParseError> {
+    let invalid = || ParseError::InvalidFormat {
+        line: line_number,
+        content: line.to_string(),
+    };
+
+    let mut cost = None;
+    let mut gain = None;
+    let mut rest = field.trim();
+    while !rest.is_empty() {
+        let sign = rest.chars().next().ok_or_else(invalid)?;
+        let digits = &rest[sign.len_utf8()..];
+        let end = digits
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(digits.len());
+        let value = digits[..end].parse::<u32>().map_err(|_| invalid())?;
+        let slot = match sign {
+            '-' => &mut cost,
+            '+' => &mut gain,
+            _ => return Err(invalid()),
+        };
+        if slot.replace(value).is_some() {
+            return Err(invalid());
+        }
+        rest = &digits[end..];
+    }
+    Ok((cost, gain))
+}
+
+/// SP column text (`-100+20`), empty when the step has no annotation
+pub(super) fn format_sp(cost: Option<u32>, gain: Option<u32>) -> String {
+    let cost = cost.map(|c| format!("-{}", c)).unwrap_or_default();
+    let gain = gain.map(|g| format!("+{}", g)).unwrap_or_default();
+    cost + &gain
+}
+
+/// Step indices of one pass of the rotation
+///
+/// Loop blocks are repeated their full count and branches follow their
+/// default (next step) path.
+pub(super) fn one_pass(combo: &ComboFile) -> Vec<usize> {
+    let total = combo.step_count();
+    let mut loop_state = LoopState::default();
+    let mut order = Vec::new();
+    let mut index = 0;
+
+    // Guards against a loop structure that never finishes
+    let limit = total.saturating_mul(1000).max(1);
+    while index < total && order.len() < limit {
+        order.push(index);
+        let next = loop_state.next_index(combo, index);
+        // Leaving the last step with every loop finished starts the next pass
+        if next == 0 && index + 1 == total && loop_state.passes.iter().all(|&p| p == 0) {
+            break;
+        }
+        index = next;
+    }
+    order
+}
+
+/// Walk one pass of the rotation (see [`one_pass`]) from `start` SP
+///
+/// `max` caps the SP pool when set.
+pub fn project_sp(combo: &ComboFile, start: u32, max: Option<u32>) -> SpProjection {
+    let mut steps = Vec::new();
+    let mut sp = i64::from(start);
+    let mut lowest = sp;
+
+    for index in one_pass(combo) {
+        let cmd = combo.step(index).expect("pass indices are steps");
+        let cost = cmd.sp_cost.unwrap_or(0);
+        let gain = cmd.sp_gain.unwrap_or(0);
+
+        let sp_before = sp;
+        sp -= i64::from(cost);
+        lowest = lowest.min(sp);
+        sp += i64::from(gain);
+        if let Some(max) = max {
+            sp = sp.min(i64::from(max));
+        }
+        steps.push(SpStep {
+            index,
+            sp_before,
+            cost,
+            gain,
+            sp_after: sp,
+            starved: i64::from(cost) > sp_before,
+        });
+    }
+
+    SpProjection {
+        start,
+        steps,
+        lowest,
+        end: sp,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combo::{parse_combo_content, serialize_combo_file};
+
+    #[test]
+    fn test_project_sp() {
+        assert_eq!(parse_sp("-100+20", 1, "").unwrap(), (Some(100), Some(20)));
+        assert_eq!(parse_sp("+15", 1, "").unwrap(), (None, Some(15)));
+        assert!(parse_sp("100", 1, "").is_err());
+        assert!(parse_sp("-10-20", 1, "").is_err());
+
+        let content = "#,物理,,|\nLOOP 2\nL,管理人,重撃,,,+40|\nENDLOOP\n2,リーフォン,戦技,,,-100|\n3,ポグ,戦技,,,-100+10|";
+        let combo = parse_combo_content(content).unwrap();
+        assert!(serialize_combo_file(&combo).contains("\nL,管理人,重撃,,,+40|\n"));
+        let projection = project_sp(&combo, 50, Some(300));
+
+        let after: Vec<i64> = projection.steps.iter().map(|s| s.sp_after).collect();
+        assert_eq!(after, [90, 130, 30, -60]);
+        assert!(projection.is_starved());
+        assert!(projection.steps[3].starved);
+        assert_eq!(projection.lowest, -70);
+
+        assert!(!project_sp(&combo, 200, Some(300)).is_starved());
+    }
+}
@@ -0,0 +1,126 @@
+//! Combo statistics
+//!
+//! Summary numbers for the library browser: who does what, how much of the
+//! rotation is held, and roughly how long one pass takes.
+
+use super::sp::one_pass;
+use super::{ComboFile, InputType};
+use serde::{Deserialize, Serialize};
+
+/// Steps of one operator
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CharacterStats {
+    pub character: String,
+    pub steps: usize,
+}
+
+/// Summary of a combo
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComboStats {
+    /// Input steps, as written (loops counted once)
+    pub step_count: usize,
+    /// Step counts per operator, in first-use order
+    pub characters: Vec<CharacterStats>,
+    pub tap_steps: usize,
+    pub hold_steps: usize,
+    /// Share of held steps among taps and holds (0.0 - 1.0)
+    pub hold_ratio: f32,
+    /// Sum of the timing annotations over one pass (loops repeated)
+    pub estimated_duration_ms: u64,
+    /// Steps of the pass with a timing annotation; the estimate is partial
+    /// when this is below `pass_steps`
+    pub timed_steps: usize,
+    /// Steps in one pass (loops repeated)
+    pub pass_steps: usize,
+    /// Number of `#` phases
+    pub phase_count: usize,
+}
+
+/// Compute the statistics of `combo`
+pub fn stats(combo: &ComboFile) -> ComboStats {
+    let mut characters: Vec<CharacterStats> = Vec::new();
+    let mut tap_steps = 0;
+    let mut hold_steps = 0;
+
+    // Branch rows hold a prompt instead of an operator; waits press nothing
+    let steps = combo.steps().filter(|step| {
+        !matches!(
+            step.input_type,
+            InputType::Branch { .. } | InputType::Wait { .. }
+        )
+    });
+    for step in steps {
+        match step.input_type {
+            InputType::Hold { .. } | InputType::ChargedHold { .. } => hold_steps += 1,
+            _ => tap_steps += 1,
+        }
+        if step.character.is_empty() {
+            continue;
+        }
+        match characters
+            .iter_mut()
+            .find(|c| c.character == step.character)
+        {
+            Some(entry) => entry.steps += 1,
+            None => characters.push(CharacterStats {
+                character: step.character.clone(),
+                steps: 1,
+            }),
+        }
+    }
+
+    let pass = one_pass(combo);
+    let gaps: Vec<u64> = pass
+        .iter()
+        .filter_map(|&index| combo.step(index)?.gap_ms)
+        .collect();
+
+    let inputs = tap_steps + hold_steps;
+    ComboStats {
+        step_count: combo.step_count(),
+        characters,
+        tap_steps,
+        hold_steps,
+        hold_ratio: if inputs == 0 {
+            0.0
+        } else {
+            hold_steps as f32 / inputs as f32
+        },
+        estimated_duration_ms: gaps.iter().sum(),
+        timed_steps: gaps.len(),
+        pass_steps: pass.len(),
+        phase_count: combo.phases().len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combo::parse_combo_content;
+
+    #[test]
+    fn test_combo_stats() {
+        let content = "#,開幕,,|\n2,リーフォン,戦技,|\nU2,リーフォン,必殺技,,500|\n#,ループ,,|\nLOOP 2\nE,チェン,連携,,1200|\nENDLOOP\n?,続ける？,はい>1/いいえ,|";
+        let stats = stats(&parse_combo_content(content).unwrap());
+
+        assert_eq!(stats.step_count, 4);
+        assert_eq!(
+            stats.characters,
+            [
+                CharacterStats {
+                    character: "リーフォン".to_string(),
+                    steps: 2
+                },
+                CharacterStats {
+                    character: "チェン".to_string(),
+                    steps: 1
+                },
+            ]
+        );
+        assert_eq!((stats.tap_steps, stats.hold_steps), (2, 1));
+        assert!((stats.hold_ratio - 1.0 / 3.0).abs() < f32::EPSILON);
+        assert_eq!(stats.estimated_duration_ms, 500 + 1200 * 2);
+        assert_eq!((stats.timed_steps, stats.pass_steps), (3, 5));
+        assert_eq!(stats.phase_count, 2);
+    }
+}
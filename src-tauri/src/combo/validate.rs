@@ -0,0 +1,418 @@
+//! Combo file linting
+//!
+//! Reports problems that still parse but are likely mistakes, so authors can
+//! fix a file before loading it mid-fight.
+
+use super::migrate::migrate_lines;
+use super::roster::Roster;
+use super::{
+    parse_combo_library_content, parse_combo_yaml, parse_line, serialize_combo_file, split_repeat,
+    ComboCommand, ComboFile, ComboFormat, InputType, KeyIdentifier, ParseError, EOF_MARKER,
+};
+use serde::{Deserialize, Serialize};
+
+/// Highest operator skill key
+const MAX_OPERATOR_KEY: u8 = 4;
+
+/// Kind of problem found in a combo file
+///
+/// Each kind has a stable code (see [`ComboLintError::code`]) that the UI
+/// can key messages and help links on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComboLintError {
+    /// The file does not parse
+    ParseError,
+    /// Number key outside the operator range 1-4
+    KeyOutOfRange,
+    /// Input step without a character name
+    EmptyCharacter,
+    /// Hold duration shorter than the tap/hold threshold
+    HoldTooShort,
+    /// No `!!!!!` line at the end of the file
+    MissingEofMarker,
+    /// Hold start without a later release of the same key, or the reverse
+    UnpairedHold,
+    /// Character name not in the operator roster
+    UnknownCharacter,
+    /// Skill type not in the operator roster
+    UnknownSkillType,
+}
+
+/// How serious a finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// The combo will not play as written
+    Error,
+    /// Likely a mistake, but the combo plays
+    Warning,
+}
+
+impl ComboLintError {
+    /// Stable code: `E` for errors, `W` for warnings
+    pub fn code(&self) -> &'static str {
+        match self {
+            ComboLintError::ParseError => "E000",
+            ComboLintError::KeyOutOfRange => "E001",
+            ComboLintError::HoldTooShort => "E002",
+            ComboLintError::UnpairedHold => "E003",
+            ComboLintError::EmptyCharacter => "W001",
+            ComboLintError::MissingEofMarker => "W002",
+            ComboLintError::UnknownCharacter => "W003",
+            ComboLintError::UnknownSkillType => "W004",
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        if self.code().starts_with('E') {
+            Severity::Error
+        } else {
+            Severity::Warning
+        }
+    }
+}
+
+/// A single validation finding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// 1-based line number (`0` for problems that concern the whole file)
+    pub line: usize,
+    pub kind: ComboLintError,
+    /// [`ComboLintError::code`] of `kind`
+    pub code: String,
+    pub severity: Severity,
+    pub message: String,
+    /// Likely intended value (e.g. the operator name closest to a typo)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+impl Diagnostic {
+    fn new(line: usize, kind: ComboLintError, message: String) -> Self {
+        Self {
+            line,
+            code: kind.code().to_string(),
+            severity: kind.severity(),
+            kind,
+            message,
+            suggestion: None,
+        }
+    }
+
+    fn with_suggestion(mut self, suggestion: Option<&str>) -> Self {
+        if let Some(suggestion) = suggestion {
+            self.message = format!("{} (did you mean '{}'?)", self.message, suggestion);
+            self.suggestion = Some(suggestion.to_string());
+        }
+        self
+    }
+}
+
+impl From<ParseError> for Diagnostic {
+    fn from(error: ParseError) -> Self {
+        let line = match error {
+            ParseError::InvalidFormat { line, .. } | ParseError::InvalidKey { line, .. } => line,
+            _ => 0,
+        };
+        Diagnostic::new(line, ComboLintError::ParseError, error.to_string())
+    }
+}
+
+/// Check the character and skill type of an input step against `roster`
+fn check_names(
+    line: usize,
+    cmd: &ComboCommand,
+    roster: &Roster,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    if !cmd.character.is_empty() && !roster.has_operator(&cmd.character) {
+        diagnostics.push(
+            Diagnostic::new(
+                line,
+                ComboLintError::UnknownCharacter,
+                format!("Unknown operator '{}'", cmd.character),
+            )
+            .with_suggestion(roster.suggest_operator(&cmd.character)),
+        );
+    }
+    if !cmd.skill_type.is_empty() && !roster.has_skill_type(&cmd.skill_type) {
+        diagnostics.push(
+            Diagnostic::new(
+                line,
+                ComboLintError::UnknownSkillType,
+                format!("Unknown skill type '{}'", cmd.skill_type),
+            )
+            .with_suggestion(roster.suggest_skill_type(&cmd.skill_type)),
+        );
+    }
+}
+
+/// Check individual rows; `line` is whatever position the caller can report
+fn check_rows<'a, I>(
+    rows: I,
+    hold_threshold_ms: u64,
+    roster: &Roster,
+    diagnostics: &mut Vec<Diagnostic>,
+) where
+    I: IntoIterator<Item = (usize, &'a ComboCommand)>,
+{
+    // Keys held by a `+` step, with the line of that step
+    let mut held: Vec<(KeyIdentifier, usize)> = Vec::new();
+
+    for (line, cmd) in rows {
+        if !cmd.is_step()
+            || matches!(
+                cmd.input_type,
+                InputType::Branch { .. } | InputType::Wait { .. }
+            )
+        {
+            continue;
+        }
+
+        match cmd.input_type {
+            InputType::HoldStart => held.push((cmd.key.clone(), line)),
+            InputType::HoldRelease => match held.iter().position(|(key, _)| *key == cmd.key) {
+                Some(index) => {
+                    held.remove(index);
+                }
+                None => diagnostics.push(Diagnostic::new(
+                    line,
+                    ComboLintError::UnpairedHold,
+                    "Release step without a preceding hold start".to_string(),
+                )),
+            },
+            _ => {}
+        }
+
+        if let KeyIdentifier::Number(n) = cmd.key {
+            if !(1..=MAX_OPERATOR_KEY).contains(&n) {
+                diagnostics.push(Diagnostic::new(
+                    line,
+                    ComboLintError::KeyOutOfRange,
+                    format!("Key {} is not an operator skill key (1-4)", n),
+                ));
+            }
+        }
+
+        if cmd.character.is_empty() {
+            diagnostics.push(Diagnostic::new(
+                line,
+                ComboLintError::EmptyCharacter,
+                "Character name is empty".to_string(),
+            ));
+        }
+        check_names(line, cmd, roster, diagnostics);
+
+        if let InputType::Hold {
+            duration_ms: Some(duration_ms),
+        } = cmd.input_type
+        {
+            if duration_ms < hold_threshold_ms {
+                diagnostics.push(Diagnostic::new(
+                    line,
+                    ComboLintError::HoldTooShort,
+                    format!(
+                        "Hold of {}ms is shorter than the hold threshold ({}ms)",
+                        duration_ms, hold_threshold_ms
+                    ),
+                ));
+            }
+        }
+    }
+
+    for (_, line) in held {
+        diagnostics.push(Diagnostic::new(
+            line,
+            ComboLintError::UnpairedHold,
+            "Hold start is never released".to_string(),
+        ));
+    }
+}
+
+/// Lint combo file content in the text format
+pub fn validate(content: &str, hold_threshold_ms: u64, roster: &Roster) -> Vec<Diagnostic> {
+    // Older format versions are checked as they will be read
+    let migrated = match migrate_lines(content) {
+        Ok(migrated) => migrated.map(|(migrated, _)| migrated),
+        Err(e) => return vec![e.into()],
+    };
+    let content = migrated.as_deref().unwrap_or(content);
+
+    let mut diagnostics = Vec::new();
+    let mut rows = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        // A `3xL` line is checked once, as the row it repeats
+        let row = split_repeat(line).map_or(line, |(_, row)| row);
+        match parse_line(row, i + 1) {
+            Ok(Some(cmd)) => rows.push((i + 1, cmd)),
+            Ok(None) => {}
+            Err(e) => diagnostics.push(e.into()),
+        }
+    }
+
+    // File-level checks (loop balance, branch targets) only make sense once
+    // every line parses
+    if diagnostics.is_empty() {
+        if let Err(e) = parse_combo_library_content(content) {
+            diagnostics.push(e.into());
+        }
+    }
+
+    check_rows(
+        rows.iter().map(|(line, cmd)| (*line, cmd)),
+        hold_threshold_ms,
+        roster,
+        &mut diagnostics,
+    );
+
+    if !content
+        .lines()
+        .any(|line| line.trim().starts_with(EOF_MARKER))
+    {
+        diagnostics.push(Diagnostic::new(
+            content.lines().count() + 1,
+            ComboLintError::MissingEofMarker,
+            format!("Missing end marker '{}'", EOF_MARKER),
+        ));
+    }
+
+    diagnostics.sort_by_key(|d| d.line);
+    diagnostics
+}
+
+/// Lint an already parsed combo (e.g. from YAML)
+///
+/// Without source lines, `line` holds the 1-based row number.
+pub fn validate_combo(
+    combo: &ComboFile,
+    hold_threshold_ms: u64,
+    roster: &Roster,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    check_rows(
+        combo
+            .commands
+            .iter()
+            .enumerate()
+            .map(|(i, cmd)| (i + 1, cmd.as_ref())),
+        hold_threshold_ms,
+        roster,
+        &mut diagnostics,
+    );
+    diagnostics
+}
+
+/// Errors that should keep an edited combo from being saved
+///
+/// The combo is checked in the text form it would be saved as, so loop and
+/// branch structure are covered along with the rows. Warnings don't count.
+pub fn save_errors(combo: &ComboFile, hold_threshold_ms: u64, roster: &Roster) -> Vec<Diagnostic> {
+    validate(&serialize_combo_file(combo), hold_threshold_ms, roster)
+        .into_iter()
+        .filter(|d| d.severity == Severity::Error)
+        .collect()
+}
+
+/// Check only the names in a loaded combo against `roster`
+///
+/// `line` holds the 1-based row number.
+pub fn check_roster(combo: &ComboFile, roster: &Roster) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for (i, cmd) in combo.commands.iter().enumerate() {
+        let named = !matches!(
+            cmd.input_type,
+            InputType::Branch { .. } | InputType::Wait { .. }
+        );
+        if cmd.is_step() && named {
+            check_names(i + 1, cmd, roster, &mut diagnostics);
+        }
+    }
+    diagnostics
+}
+
+/// Lint combo content in either format
+pub fn lint(
+    content: &str,
+    format: ComboFormat,
+    hold_threshold_ms: u64,
+    roster: &Roster,
+) -> Vec<Diagnostic> {
+    match format {
+        ComboFormat::Text => validate(content, hold_threshold_ms, roster),
+        ComboFormat::Yaml => match parse_combo_yaml(content) {
+            Ok(file) => validate_combo(&file, hold_threshold_ms, roster),
+            Err(e) => vec![e.into()],
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combo::parse_combo_content;
+
+    #[test]
+    fn test_validate_reports_lines() {
+        let content = "#,物理,,|\n6,リーフォン,戦技,|\nU2:100,,必殺技,|\nX,ポグ,戦技,|";
+        let roster = Roster::builtin();
+        let diagnostics = validate(content, 300, &roster);
+        let kinds: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.line, d.kind.clone()))
+            .collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                (2, ComboLintError::KeyOutOfRange),
+                (3, ComboLintError::EmptyCharacter),
+                (3, ComboLintError::HoldTooShort),
+                (4, ComboLintError::ParseError),
+                (5, ComboLintError::MissingEofMarker),
+            ]
+        );
+        assert_eq!(diagnostics[0].code, "E001");
+        assert_eq!(diagnostics[1].severity, Severity::Warning);
+        assert!(validate("2,リーフォン,戦技,|\n!!!!!", 300, &roster).is_empty());
+        assert_eq!(
+            lint("- key: X", ComboFormat::Yaml, 300, &roster)[0].code,
+            "E000"
+        );
+
+        let diagnostics = validate(
+            "-2,リーフォン,必殺技,|\n+3,ポグ,必殺技,|\n!!!!!",
+            300,
+            &roster,
+        );
+        let lines: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.line, d.kind.clone()))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                (1, ComboLintError::UnpairedHold),
+                (2, ComboLintError::UnpairedHold)
+            ]
+        );
+
+        let diagnostics = validate("2,リーフオン,必殺,|\n!!!!!", 300, &roster);
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.code.as_str(), d.suggestion.as_deref()))
+            .collect();
+        assert_eq!(
+            found,
+            [("W003", Some("リーフォン")), ("W004", Some("必殺技"))]
+        );
+
+        // Saving is only blocked by errors
+        let combo = parse_combo_content("U2:100,リーフォン,必殺,|").unwrap();
+        let errors = save_errors(&combo, 300, &roster);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, ComboLintError::HoldTooShort);
+        assert!(save_errors(&combo, 100, &roster).is_empty());
+    }
+}
@@ -0,0 +1,131 @@
+//! Key binding conflicts
+//!
+//! App hotkeys (including step navigation) act on every press of their key,
+//! so a hotkey sharing a key with a combo key or another hotkey fires during
+//! play. Combo keys may share a key with each other (the normal and heavy
+//! attack both default to left click).
+
+use super::KeyBindings;
+use crate::input::keymap::parse_key_name;
+use serde::{Deserialize, Serialize};
+
+/// Keys the game uses that have no binding of their own
+const FIXED_KEYS: [(&str, &str); 7] = [
+    ("swap", "Q"),
+    ("move_forward", "W"),
+    ("move_left", "A"),
+    ("move_back", "S"),
+    ("move_right", "D"),
+    ("dodge", "ShiftLeft"),
+    ("dodge", "ShiftRight"),
+];
+
+/// Bindings that share one key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BindingConflict {
+    /// Key as written in the first binding using it
+    pub key: String,
+    /// Names of the bindings using it (`toggle_overlay`, `operator1_skill`, ...)
+    pub bindings: Vec<String>,
+}
+
+/// Bindings of `bindings` that collide with a hotkey
+pub fn binding_conflicts(bindings: &KeyBindings) -> Vec<BindingConflict> {
+    let hotkeys = [
+        ("open_settings", &bindings.open_settings),
+        ("toggle_overlay", &bindings.toggle_overlay),
+        ("next_step", &bindings.next_step),
+        ("previous_step", &bindings.previous_step),
+        ("reset_combo", &bindings.reset_combo),
+        ("reload_combo", &bindings.reload_combo),
+        ("toggle_dnd", &bindings.toggle_dnd),
+        ("pause_tracking", &bindings.pause_tracking),
+        ("decrease_hold_threshold", &bindings.decrease_hold_threshold),
+        ("increase_hold_threshold", &bindings.increase_hold_threshold),
+        ("branch_option_1", &bindings.branch_option_1),
+        ("branch_option_2", &bindings.branch_option_2),
+    ];
+    let combo_keys = [
+        ("normal_attack", &bindings.normal_attack),
+        ("chain_attack", &bindings.chain_attack),
+        ("operator1_skill", &bindings.operator1_skill),
+        ("operator2_skill", &bindings.operator2_skill),
+        ("operator3_skill", &bindings.operator3_skill),
+        ("operator4_skill", &bindings.operator4_skill),
+        ("heavy_attack", &bindings.heavy_attack),
+        ("dodge", &bindings.dodge),
+    ];
+    let all = hotkeys
+        .iter()
+        .map(|&(name, key)| (name, key.as_str(), true))
+        .chain(
+            combo_keys
+                .iter()
+                .map(|&(name, key)| (name, key.as_str(), false)),
+        )
+        .chain(FIXED_KEYS.iter().map(|&(name, key)| (name, key, false)))
+        .filter(|(_, key, _)| !key.trim().is_empty());
+
+    // Bindings grouped by key: (identity, key as written, names, any hotkey)
+    let mut groups: Vec<(String, String, Vec<String>, bool)> = Vec::new();
+    for (name, key, hotkey) in all {
+        let identity = key_identity(key);
+        match groups.iter_mut().find(|group| group.0 == identity) {
+            Some(group) => {
+                if !group.2.iter().any(|bound| bound == name) {
+                    group.2.push(name.to_string());
+                }
+                group.3 |= hotkey;
+            }
+            None => groups.push((
+                identity,
+                key.trim().to_string(),
+                vec![name.to_string()],
+                hotkey,
+            )),
+        }
+    }
+    groups
+        .into_iter()
+        .filter(|(_, _, names, hotkey)| *hotkey && names.len() > 1)
+        .map(|(_, key, bindings, _)| BindingConflict { key, bindings })
+        .collect()
+}
+
+/// Key a binding name stands for, so `e` and `E` compare equal
+fn key_identity(name: &str) -> String {
+    match parse_key_name(name) {
+        Some(key) => format!("{:?}", key),
+        None => name.trim().to_ascii_lowercase(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binding_conflicts() {
+        // Normal and heavy attack sharing left click is fine
+        assert!(binding_conflicts(&KeyBindings::default()).is_empty());
+
+        let bindings = KeyBindings {
+            toggle_overlay: "1".to_string(),
+            reload_combo: "Q".to_string(),
+            ..KeyBindings::default()
+        };
+        assert_eq!(
+            binding_conflicts(&bindings),
+            vec![
+                BindingConflict {
+                    key: "1".to_string(),
+                    bindings: vec!["toggle_overlay".to_string(), "operator1_skill".to_string()],
+                },
+                BindingConflict {
+                    key: "Q".to_string(),
+                    bindings: vec!["reload_combo".to_string(), "swap".to_string()],
+                },
+            ]
+        );
+    }
+}
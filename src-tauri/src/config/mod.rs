@@ -2,6 +2,10 @@
 //!
 //! Handles reading and writing application configuration.
 
+pub mod conflicts;
+
+use crate::input::layout::KeyboardLayout;
+use crate::window_tracker::AnchorCorner;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -16,13 +20,46 @@ pub enum Language {
     ChineseTraditional,
 }
 
+impl Language {
+    /// Language code used by localized memos (`{ja=..., en=...}`)
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::Japanese => "ja",
+            Language::English => "en",
+            Language::ChineseSimplified => "zh-Hans",
+            Language::ChineseTraditional => "zh-Hant",
+        }
+    }
+}
+
 /// Key binding configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct KeyBindings {
     /// Key to open settings window
     pub open_settings: String,
     /// Key to toggle overlay visibility
     pub toggle_overlay: String,
+    /// Key to go to the next step
+    pub next_step: String,
+    /// Key to go back to the previous step
+    pub previous_step: String,
+    /// Key to go back to the first step
+    pub reset_combo: String,
+    /// Key to reload the current combo file from disk
+    pub reload_combo: String,
+    /// Key to toggle do-not-disturb mode
+    pub toggle_dnd: String,
+    /// Key to pause/resume combo tracking
+    pub pause_tracking: String,
+    /// Key to shorten the hold threshold
+    pub decrease_hold_threshold: String,
+    /// Key to lengthen the hold threshold
+    pub increase_hold_threshold: String,
+    /// Key to pick the first option of a branch step
+    pub branch_option_1: String,
+    /// Key to pick the second option of a branch step
+    pub branch_option_2: String,
     /// Normal attack key
     pub normal_attack: String,
     /// Chain/link attack key
@@ -37,6 +74,8 @@ pub struct KeyBindings {
     pub operator4_skill: String,
     /// Heavy attack key
     pub heavy_attack: String,
+    /// Dodge key (Shift always dodges too)
+    pub dodge: String,
 }
 
 impl Default for KeyBindings {
@@ -44,6 +83,16 @@ impl Default for KeyBindings {
         Self {
             open_settings: "Home".to_string(),
             toggle_overlay: "PageUp".to_string(),
+            next_step: "RightArrow".to_string(),
+            previous_step: "LeftArrow".to_string(),
+            reset_combo: "End".to_string(),
+            reload_combo: "F5".to_string(),
+            toggle_dnd: "PageDown".to_string(),
+            pause_tracking: "Pause".to_string(),
+            decrease_hold_threshold: "F7".to_string(),
+            increase_hold_threshold: "F8".to_string(),
+            branch_option_1: "F9".to_string(),
+            branch_option_2: "F10".to_string(),
             normal_attack: "MouseLeft".to_string(),
             chain_attack: "E".to_string(),
             operator1_skill: "1".to_string(),
@@ -51,12 +100,55 @@ impl Default for KeyBindings {
             operator3_skill: "3".to_string(),
             operator4_skill: "4".to_string(),
             heavy_attack: "MouseLeft".to_string(),
+            dodge: "Space".to_string(),
+        }
+    }
+}
+
+/// Gamepad button bindings, by button name (`South`, `East`, `North`,
+/// `West`, `LeftTrigger`, `RightTrigger2`, `DPadUp`, ...); empty = unbound
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GamepadBindings {
+    /// Whether controller buttons count as combo input
+    pub enabled: bool,
+    /// Chain/link attack button
+    pub chain_attack: String,
+    /// Operator 1 skill button
+    pub operator1_skill: String,
+    /// Operator 2 skill button
+    pub operator2_skill: String,
+    /// Operator 3 skill button
+    pub operator3_skill: String,
+    /// Operator 4 skill button
+    pub operator4_skill: String,
+    /// Heavy attack button
+    pub heavy_attack: String,
+    /// Dodge button
+    pub dodge: String,
+    /// Operator swap button
+    pub swap: String,
+}
+
+impl Default for GamepadBindings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            chain_attack: "North".to_string(),
+            operator1_skill: "DPadUp".to_string(),
+            operator2_skill: "DPadRight".to_string(),
+            operator3_skill: "DPadDown".to_string(),
+            operator4_skill: "DPadLeft".to_string(),
+            heavy_attack: "West".to_string(),
+            dodge: "East".to_string(),
+            swap: "LeftTrigger".to_string(),
         }
     }
 }
 
 /// Overlay window settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct OverlaySettings {
     /// Opacity (0.0 - 1.0, higher = more opaque)
     pub opacity: f32,
@@ -68,6 +160,20 @@ pub struct OverlaySettings {
     pub width: u32,
     /// Height
     pub height: u32,
+    /// Hide the overlay while another window is in front of the game
+    pub hide_when_unfocused: bool,
+    /// Hide the overlay when the game exits and bring it back as it was when
+    /// the game starts again
+    pub hide_when_game_closed: bool,
+    /// Keep the overlay at a corner of the game window, following it when
+    /// the game moves or resizes
+    pub anchor_to_game: bool,
+    /// Corner of the game window the overlay is anchored to
+    pub anchor_corner: AnchorCorner,
+    /// Horizontal distance in from the anchor corner
+    pub anchor_offset_x: i32,
+    /// Vertical distance in from the anchor corner
+    pub anchor_offset_y: i32,
 }
 
 impl Default for OverlaySettings {
@@ -78,6 +184,12 @@ impl Default for OverlaySettings {
             y: 100,
             width: 400,
             height: 100,
+            hide_when_unfocused: true,
+            hide_when_game_closed: true,
+            anchor_to_game: false,
+            anchor_corner: AnchorCorner::default(),
+            anchor_offset_x: 20,
+            anchor_offset_y: 20,
         }
     }
 }
@@ -97,6 +209,112 @@ impl Default for SettingsWindowSettings {
     }
 }
 
+/// Combo file backup settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupSettings {
+    /// Number of backups kept per combo file (0 disables backups)
+    pub retention: usize,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        Self { retention: 10 }
+    }
+}
+
+/// Input detection settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputSettings {
+    /// Hold threshold in milliseconds
+    pub hold_threshold_ms: u64,
+    /// Interval between hold progress updates in milliseconds
+    pub progress_interval_ms: u64,
+    /// Interval between hold progress updates in the last 100ms of a hold
+    pub progress_fine_interval_ms: u64,
+    /// Window in which all keys of a chord step must be pressed
+    pub chord_window_ms: u64,
+    /// Ignore repeated presses of a key within this many ms (0 = off)
+    pub debounce_ms: u64,
+    /// Report combo keys that don't match the current step as misses
+    pub strict_mode: bool,
+    /// Start the combo over after a miss (strict mode only)
+    pub reset_on_miss: bool,
+    /// Advance tap steps when the key is released instead of pressed
+    pub advance_on_release: bool,
+    /// Only count presses while the game is the foreground window
+    pub require_game_focus: bool,
+    /// Physical layout of the keyboard, so combo keys follow key positions
+    pub keyboard_layout: KeyboardLayout,
+    /// Step through the combo with the mouse wheel (down = next, up = back)
+    pub wheel_navigation: bool,
+    /// Key that must be held for wheel navigation (empty = none)
+    pub wheel_navigation_modifier: String,
+}
+
+impl Default for InputSettings {
+    fn default() -> Self {
+        Self {
+            hold_threshold_ms: crate::input::DEFAULT_HOLD_THRESHOLD_MS,
+            progress_interval_ms: crate::input::DEFAULT_PROGRESS_INTERVAL_MS,
+            progress_fine_interval_ms: crate::input::DEFAULT_PROGRESS_FINE_INTERVAL_MS,
+            chord_window_ms: crate::input::DEFAULT_CHORD_WINDOW_MS,
+            debounce_ms: crate::input::DEFAULT_DEBOUNCE_MS,
+            strict_mode: false,
+            reset_on_miss: false,
+            advance_on_release: false,
+            require_game_focus: true,
+            keyboard_layout: KeyboardLayout::default(),
+            wheel_navigation: false,
+            wheel_navigation_modifier: String::new(),
+        }
+    }
+}
+
+/// Practice session settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionSettings {
+    /// Seconds without input before session timers pause
+    pub idle_timeout_secs: u64,
+    /// Seconds without completing a step before the combo starts over
+    /// (0 = never)
+    pub auto_reset_secs: u64,
+}
+
+impl Default for SessionSettings {
+    fn default() -> Self {
+        Self {
+            idle_timeout_secs: crate::session::DEFAULT_IDLE_TIMEOUT_SECS,
+            auto_reset_secs: 0,
+        }
+    }
+}
+
+/// Background polling settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PollingSettings {
+    /// How often the process list is read where process events aren't
+    /// available
+    pub process_interval_ms: u64,
+    /// How often game focus, overlay visibility and the game window are checked
+    /// (both intervals are at least 100ms)
+    pub focus_interval_ms: u64,
+    /// Poll less often while the game isn't running
+    pub low_power: bool,
+}
+
+impl Default for PollingSettings {
+    fn default() -> Self {
+        Self {
+            process_interval_ms: crate::process::DEFAULT_PROCESS_INTERVAL_MS,
+            focus_interval_ms: crate::process::DEFAULT_FOCUS_INTERVAL_MS,
+            low_power: false,
+        }
+    }
+}
+
 /// Main configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
@@ -110,6 +328,25 @@ pub struct Config {
     pub settings_window: SettingsWindowSettings,
     /// Last loaded combo file path
     pub last_combo_file: Option<String>,
+    /// Combo library directory (defaults to `combos` next to the executable)
+    pub combo_directory: Option<String>,
+    /// Path or URI opened to start the game
+    pub game_launcher: Option<String>,
+    /// Combo file backup settings
+    #[serde(default)]
+    pub backups: BackupSettings,
+    /// Input detection settings
+    #[serde(default)]
+    pub input: InputSettings,
+    /// Gamepad button bindings
+    #[serde(default)]
+    pub gamepad: GamepadBindings,
+    /// Practice session settings
+    #[serde(default)]
+    pub session: SessionSettings,
+    /// Background polling settings
+    #[serde(default)]
+    pub polling: PollingSettings,
 }
 
 impl Config {
@@ -148,6 +385,19 @@ impl Config {
         PathBuf::from("config/General.toml")
     }
 
+    /// Get the combo library directory
+    pub fn combo_directory(&self) -> PathBuf {
+        if let Some(ref dir) = self.combo_directory {
+            return PathBuf::from(dir);
+        }
+        if let Ok(exe_path) = std::env::current_exe() {
+            if let Some(exe_dir) = exe_path.parent() {
+                return exe_dir.join("combos");
+            }
+        }
+        PathBuf::from("combos")
+    }
+
     /// Load from default path or create default config
     pub fn load_or_default() -> Self {
         let path = Self::default_path();
@@ -195,6 +445,23 @@ mod tests {
         assert_eq!(config.key_bindings.toggle_overlay, "PageUp");
     }
 
+    #[test]
+    fn test_missing_key_bindings_use_defaults() {
+        let bindings: KeyBindings = toml::from_str("open_settings = \"F2\"").unwrap();
+        assert_eq!(bindings.open_settings, "F2");
+        assert_eq!(bindings.reload_combo, "F5");
+        assert_eq!(bindings.next_step, "RightArrow");
+        assert_eq!(bindings.previous_step, "LeftArrow");
+    }
+
+    #[test]
+    fn test_missing_input_settings_use_defaults() {
+        let input: InputSettings = toml::from_str("wheel_navigation = true").unwrap();
+        assert!(input.wheel_navigation);
+        assert!(input.wheel_navigation_modifier.is_empty());
+        assert!(!input.strict_mode);
+    }
+
     #[test]
     fn test_config_serialization() {
         let config = Config::default();
@@ -0,0 +1,368 @@
+//! Combo editor document
+//!
+//! Holds a combo file being edited in memory and applies granular, undoable
+//! edit operations to its rows (title lines included).
+
+use crate::combo::{ComboCommand, ComboFile};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Maximum number of operations kept on the undo stack
+const MAX_UNDO_DEPTH: usize = 100;
+
+/// A reversible edit operation on the document rows
+#[derive(Debug, Clone)]
+enum EditOp {
+    Insert {
+        index: usize,
+        command: Arc<ComboCommand>,
+    },
+    Delete {
+        index: usize,
+        command: Arc<ComboCommand>,
+    },
+    Move {
+        from: usize,
+        to: usize,
+    },
+    Update {
+        index: usize,
+        old: Arc<ComboCommand>,
+        new: Arc<ComboCommand>,
+    },
+}
+
+impl EditOp {
+    /// Get the operation that reverts this one
+    fn inverse(&self) -> EditOp {
+        match self {
+            EditOp::Insert { index, command } => EditOp::Delete {
+                index: *index,
+                command: command.clone(),
+            },
+            EditOp::Delete { index, command } => EditOp::Insert {
+                index: *index,
+                command: command.clone(),
+            },
+            EditOp::Move { from, to } => EditOp::Move {
+                from: *to,
+                to: *from,
+            },
+            EditOp::Update { index, old, new } => EditOp::Update {
+                index: *index,
+                old: new.clone(),
+                new: old.clone(),
+            },
+        }
+    }
+
+    /// Apply the operation to a combo, keeping branch targets on their steps
+    fn apply(&self, combo: &mut ComboFile) {
+        match self {
+            EditOp::Insert { index, command } => combo.insert_row(*index, command.clone()),
+            EditOp::Delete { index, .. } => {
+                combo.remove(*index);
+            }
+            EditOp::Move { from, to } => {
+                combo.reorder(*from, *to);
+            }
+            EditOp::Update { index, new, .. } => {
+                combo.commands[*index] = new.clone();
+                combo.refresh_title();
+            }
+        }
+    }
+}
+
+/// Combo file being edited, with undo/redo history
+#[derive(Debug, Clone)]
+pub struct EditorDocument {
+    /// File the document was opened from or last saved to
+    pub path: Option<String>,
+    /// Current document content
+    pub combo: ComboFile,
+    /// Undo stack depth of the content last opened or saved (`None` once
+    /// that state can't be reached by undo or redo)
+    saved_depth: Option<usize>,
+    undo_stack: Vec<EditOp>,
+    redo_stack: Vec<EditOp>,
+}
+
+/// Serializable view of the document sent to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentSnapshot {
+    pub path: Option<String>,
+    pub combo: ComboFile,
+    pub dirty: bool,
+    pub can_undo: bool,
+    pub can_redo: bool,
+}
+
+impl EditorDocument {
+    /// Create a document from an existing combo
+    pub fn new(combo: ComboFile, path: Option<String>) -> Self {
+        Self {
+            path,
+            combo,
+            saved_depth: Some(0),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+        }
+    }
+
+    /// Get a snapshot of the document state
+    pub fn snapshot(&self) -> DocumentSnapshot {
+        DocumentSnapshot {
+            path: self.path.clone(),
+            combo: self.combo.clone(),
+            dirty: self.is_dirty(),
+            can_undo: !self.undo_stack.is_empty(),
+            can_redo: !self.redo_stack.is_empty(),
+        }
+    }
+
+    /// Whether the content differs from the one last opened or saved
+    pub fn is_dirty(&self) -> bool {
+        self.saved_depth != Some(self.undo_stack.len())
+    }
+
+    /// Record the current content as saved
+    pub fn mark_saved(&mut self) {
+        self.saved_depth = Some(self.undo_stack.len());
+    }
+
+    fn check_index(&self, index: usize, len: usize) -> Result<(), EditError> {
+        if index >= len {
+            return Err(EditError::IndexOutOfRange { index, len });
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, op: EditOp) {
+        op.apply(&mut self.combo);
+        // A saved state among the discarded redo history is gone for good
+        if self.saved_depth > Some(self.undo_stack.len()) {
+            self.saved_depth = None;
+        }
+        self.undo_stack.push(op);
+        if self.undo_stack.len() > MAX_UNDO_DEPTH {
+            self.undo_stack.remove(0);
+            self.saved_depth = self.saved_depth.and_then(|depth| depth.checked_sub(1));
+        }
+        self.redo_stack.clear();
+    }
+
+    /// Insert a row before `index` (`index == len` appends)
+    pub fn insert_step(&mut self, index: usize, command: ComboCommand) -> Result<(), EditError> {
+        self.check_index(index, self.combo.commands.len() + 1)?;
+        self.execute(EditOp::Insert {
+            index,
+            command: Arc::new(command),
+        });
+        Ok(())
+    }
+
+    /// Delete the row at `index`, unless a branch jumps to it
+    pub fn delete_step(&mut self, index: usize) -> Result<(), EditError> {
+        self.check_index(index, self.combo.commands.len())?;
+        if let Some(branch) = self.combo.branch_into_row(index) {
+            return Err(EditError::BranchTarget { index, branch });
+        }
+        let command = self.combo.commands[index].clone();
+        self.execute(EditOp::Delete { index, command });
+        Ok(())
+    }
+
+    /// Move the row at `from` so that it ends up at `to`
+    pub fn move_step(&mut self, from: usize, to: usize) -> Result<(), EditError> {
+        let len = self.combo.commands.len();
+        self.check_index(from, len)?;
+        self.check_index(to, len)?;
+        if from != to {
+            self.execute(EditOp::Move { from, to });
+        }
+        Ok(())
+    }
+
+    /// Replace the row at `index`
+    pub fn update_step(&mut self, index: usize, command: ComboCommand) -> Result<(), EditError> {
+        self.check_index(index, self.combo.commands.len())?;
+        let old = self.combo.commands[index].clone();
+        self.execute(EditOp::Update {
+            index,
+            old,
+            new: Arc::new(command),
+        });
+        Ok(())
+    }
+
+    /// Revert the last edit. Returns false if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.undo_stack.pop() {
+            Some(op) => {
+                op.inverse().apply(&mut self.combo);
+                self.redo_stack.push(op);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Re-apply the last undone edit. Returns false if there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        match self.redo_stack.pop() {
+            Some(op) => {
+                op.apply(&mut self.combo);
+                self.undo_stack.push(op);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Editor errors
+#[derive(Debug, Clone)]
+pub enum EditError {
+    /// No document is open
+    NoDocument,
+    /// Row index out of range
+    IndexOutOfRange { index: usize, len: usize },
+    /// Row a branch jumps to (`branch` is the branch's step index)
+    BranchTarget { index: usize, branch: usize },
+}
+
+impl std::fmt::Display for EditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EditError::NoDocument => write!(f, "No document is open"),
+            EditError::IndexOutOfRange { index, len } => {
+                write!(f, "Index {} out of range (length {})", index, len)
+            }
+            EditError::BranchTarget { index, branch } => {
+                write!(
+                    f,
+                    "Row {} is the target of the branch at step {}",
+                    index,
+                    branch + 1
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combo::{parse_combo_content, InputType};
+
+    fn document() -> EditorDocument {
+        let combo = parse_combo_content("#,物理,,|\n2,リーフォン,戦技,|\nE,チェン,連携,|").unwrap();
+        EditorDocument::new(combo, None)
+    }
+
+    #[test]
+    fn test_edit_undo_redo() {
+        let mut doc = document();
+        let extra = (*doc.combo.commands[1]).clone();
+
+        doc.insert_step(3, extra).unwrap();
+        doc.move_step(3, 1).unwrap();
+        doc.delete_step(2).unwrap();
+        assert_eq!(doc.combo.commands.len(), 3);
+
+        assert!(doc.undo());
+        assert!(doc.undo());
+        assert!(doc.undo());
+        assert!(!doc.undo());
+        assert_eq!(doc.combo.commands.len(), 3);
+        assert_eq!(doc.combo.commands[2].character, "チェン");
+
+        assert!(doc.redo());
+        assert_eq!(doc.combo.commands.len(), 4);
+        assert!(doc.snapshot().can_redo);
+    }
+
+    #[test]
+    fn test_dirty_follows_saved_state() {
+        let mut doc = document();
+        let extra = (*doc.combo.commands[1]).clone();
+        assert!(!doc.is_dirty());
+
+        doc.insert_step(3, extra.clone()).unwrap();
+        assert!(doc.is_dirty());
+        doc.undo();
+        assert!(!doc.snapshot().dirty);
+
+        doc.redo();
+        doc.mark_saved();
+        doc.undo();
+        assert!(doc.is_dirty());
+        doc.redo();
+        assert!(!doc.is_dirty());
+
+        // The saved state can't come back once a new edit drops it from redo
+        doc.undo();
+        doc.delete_step(1).unwrap();
+        doc.undo();
+        assert!(doc.is_dirty());
+
+        // Nor once it is trimmed off the undo stack
+        let mut doc = document();
+        for _ in 0..=MAX_UNDO_DEPTH {
+            doc.insert_step(1, extra.clone()).unwrap();
+        }
+        while doc.undo() {}
+        assert!(doc.is_dirty());
+    }
+
+    #[test]
+    fn test_update_title_row_refreshes_title() {
+        let mut doc = document();
+        let mut title = (*doc.combo.commands[0]).clone();
+        title.character = "熱".to_string();
+
+        doc.update_step(0, title).unwrap();
+        assert_eq!(doc.combo.title, "熱");
+        doc.undo();
+        assert_eq!(doc.combo.title, "物理");
+        assert!(doc.delete_step(5).is_err());
+    }
+
+    #[test]
+    fn test_edits_keep_branch_targets() {
+        let combo = parse_combo_content(
+            "?,続ける？,はい>3/いいえ>2,|\n2,リーフォン,戦技,|\nE,チェン,連携,|",
+        )
+        .unwrap();
+        let mut doc = EditorDocument::new(combo, None);
+        let targets = |doc: &EditorDocument| match &doc.combo.step(0).unwrap().input_type {
+            InputType::Branch { options } => options.iter().map(|o| o.target).collect::<Vec<_>>(),
+            _ => unreachable!(),
+        };
+        assert_eq!(targets(&doc), [Some(2), Some(1)]);
+
+        // A row inserted above the jump targets moves them down
+        let extra = (*doc.combo.commands[1]).clone();
+        doc.insert_step(1, extra).unwrap();
+        assert_eq!(targets(&doc), [Some(3), Some(2)]);
+        doc.move_step(3, 1).unwrap();
+        assert_eq!(doc.combo.commands[1].character, "チェン");
+        assert_eq!(targets(&doc), [Some(1), Some(3)]);
+
+        assert!(matches!(
+            doc.delete_step(1),
+            Err(EditError::BranchTarget {
+                index: 1,
+                branch: 0
+            })
+        ));
+        doc.delete_step(2).unwrap();
+        assert_eq!(targets(&doc), [Some(1), Some(2)]);
+
+        while doc.undo() {}
+        assert_eq!(targets(&doc), [Some(2), Some(1)]);
+    }
+}
@@ -0,0 +1,72 @@
+//! Chord detection
+//!
+//! A chord step (`1+E`) completes once every one of its keys is down and the
+//! presses lie within the chord window. Keys that went down earlier and are
+//! still held count, so the chord can be built up in any order.
+
+use crate::combo::KeyIdentifier;
+use rdev::Key;
+use std::time::{Duration, Instant};
+
+/// A key that is currently down
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownKey {
+    pub key: Key,
+    /// Combo key the press stands for
+    pub id: KeyIdentifier,
+    pub press_time: Instant,
+}
+
+/// Keys that complete `chord`, or `None` while it is incomplete
+///
+/// For each chord key the latest press still down is used, so an old press
+/// held far outside the window is replaced by a fresh one of the same key.
+pub fn complete_chord(
+    chord: &[KeyIdentifier],
+    down: &[DownKey],
+    window: Duration,
+) -> Option<Vec<Key>> {
+    let presses = chord
+        .iter()
+        .map(|id| {
+            down.iter()
+                .filter(|pressed| pressed.id == *id)
+                .max_by_key(|pressed| pressed.press_time)
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    let first = presses.iter().map(|pressed| pressed.press_time).min()?;
+    let last = presses.iter().map(|pressed| pressed.press_time).max()?;
+    (last.duration_since(first) <= window)
+        .then(|| presses.iter().map(|pressed| pressed.key).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete_chord() {
+        let chord = [KeyIdentifier::Number(1), KeyIdentifier::Chain];
+        let start = Instant::now();
+        let at = |key, id, ms| DownKey {
+            key,
+            id,
+            press_time: start + Duration::from_millis(ms),
+        };
+        let window = Duration::from_millis(100);
+
+        let mut down = vec![at(Key::Num1, KeyIdentifier::Number(1), 0)];
+        assert_eq!(complete_chord(&chord, &down, window), None);
+
+        down.push(at(Key::KeyE, KeyIdentifier::Chain, 500));
+        assert_eq!(complete_chord(&chord, &down, window), None);
+
+        // The keypad 1 pressed just before E completes it
+        down.push(at(Key::Kp1, KeyIdentifier::Number(1), 450));
+        assert_eq!(
+            complete_chord(&chord, &down, window),
+            Some(vec![Key::Kp1, Key::KeyE])
+        );
+    }
+}
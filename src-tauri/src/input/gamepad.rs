@@ -0,0 +1,244 @@
+//! Gamepad input
+//!
+//! Controller buttons are translated into the keys they stand for and run
+//! through the same [`InputHandler`] as the keyboard, so taps, holds and
+//! chords complete the same way. Buttons are read through gilrs on Windows,
+//! which covers XInput pads as well as DirectInput/HID controllers such as
+//! the DualShock 4 and DualSense. Button names always follow the Xbox layout
+//! (`South` is A on Xbox and ✕ on PlayStation); the glyphs shown to the
+//! player follow the connected controller.
+
+use super::{InputHandler, TimedKeyEvent};
+use crate::combo::KeyIdentifier;
+use crate::config::GamepadBindings;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::mpsc;
+
+/// USB vendor id of Sony controllers (DualShock 4, DualSense)
+const SONY_VENDOR_ID: u16 = 0x054c;
+
+/// Button names with their Xbox and PlayStation glyphs
+const GLYPHS: [(&str, &str, &str); 17] = [
+    ("South", "A", "✕"),
+    ("East", "B", "○"),
+    ("West", "X", "□"),
+    ("North", "Y", "△"),
+    ("LeftTrigger", "LB", "L1"),
+    ("LeftTrigger2", "LT", "L2"),
+    ("RightTrigger", "RB", "R1"),
+    ("RightTrigger2", "RT", "R2"),
+    ("Select", "View", "Create"),
+    ("Start", "Menu", "Options"),
+    ("Mode", "Xbox", "PS"),
+    ("LeftThumb", "LS", "L3"),
+    ("RightThumb", "RS", "R3"),
+    ("DPadUp", "↑", "↑"),
+    ("DPadDown", "↓", "↓"),
+    ("DPadLeft", "←", "←"),
+    ("DPadRight", "→", "→"),
+];
+
+/// Controller family, which decides the button glyphs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ControllerKind {
+    Xbox,
+    PlayStation,
+}
+
+impl ControllerKind {
+    /// Family of a controller by its USB vendor id
+    pub fn from_vendor_id(vendor_id: Option<u16>) -> Self {
+        match vendor_id {
+            Some(SONY_VENDOR_ID) => Self::PlayStation,
+            _ => Self::Xbox,
+        }
+    }
+
+    /// Glyph printed on a button; unknown buttons show their name
+    pub fn glyph(self, button: &str) -> String {
+        GLYPHS
+            .iter()
+            .find(|(name, _, _)| name.eq_ignore_ascii_case(button))
+            .map(|&(_, xbox, playstation)| match self {
+                Self::Xbox => xbox,
+                Self::PlayStation => playstation,
+            })
+            .unwrap_or(button)
+            .to_string()
+    }
+}
+
+/// Buttons bound to combo keys
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GamepadMapping {
+    buttons: Vec<(String, KeyIdentifier)>,
+}
+
+impl GamepadMapping {
+    /// Mapping for the configured bindings (empty when gamepads are disabled)
+    pub fn from_bindings(bindings: &GamepadBindings) -> Self {
+        if !bindings.enabled {
+            return Self::default();
+        }
+        let bound = [
+            (&bindings.chain_attack, KeyIdentifier::Chain),
+            (&bindings.operator1_skill, KeyIdentifier::Number(1)),
+            (&bindings.operator2_skill, KeyIdentifier::Number(2)),
+            (&bindings.operator3_skill, KeyIdentifier::Number(3)),
+            (&bindings.operator4_skill, KeyIdentifier::Number(4)),
+            (&bindings.heavy_attack, KeyIdentifier::HeavyAttack),
+            (&bindings.dodge, KeyIdentifier::Dodge),
+            (&bindings.swap, KeyIdentifier::Swap),
+        ];
+        Self {
+            buttons: bound
+                .into_iter()
+                .filter(|(button, _)| !button.trim().is_empty())
+                .map(|(button, key)| (button.trim().to_string(), key))
+                .collect(),
+        }
+    }
+
+    /// Combo key a button stands for; button names are matched
+    /// case-insensitively
+    pub fn key_for(&self, button: &str) -> Option<KeyIdentifier> {
+        self.buttons
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(button))
+            .map(|(_, id)| id.clone())
+    }
+
+    /// Glyph of each bound button on a `kind` controller
+    pub fn glyphs(&self, kind: ControllerKind) -> Vec<(KeyIdentifier, String)> {
+        self.buttons
+            .iter()
+            .map(|(button, key)| (key.clone(), kind.glyph(button)))
+            .collect()
+    }
+}
+
+/// Feed a button press or release through `handler`
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn on_button(
+    handler: &InputHandler,
+    tx: &mpsc::UnboundedSender<TimedKeyEvent>,
+    button: &str,
+    pressed: bool,
+) {
+    let at = Instant::now();
+    let Some(key) = handler.gamepad_key(button) else {
+        return;
+    };
+    let event = if pressed {
+        handler.on_key_press(key)
+    } else {
+        handler.on_key_release(key)
+    };
+    if let Some(event) = event {
+        let _ = tx.send(event.at(at));
+    }
+}
+
+/// Read controller buttons in a background thread
+#[cfg(target_os = "windows")]
+pub(super) fn spawn_listener(handler: Arc<InputHandler>, tx: mpsc::UnboundedSender<TimedKeyEvent>) {
+    std::thread::spawn(move || {
+        let mut gilrs = match gilrs::Gilrs::new() {
+            Ok(gilrs) => gilrs,
+            Err(e) => {
+                eprintln!("[ERROR] Failed to start gamepad input: {}", e);
+                return;
+            }
+        };
+        // Glyphs follow the first connected controller
+        let connected = |gilrs: &gilrs::Gilrs| {
+            gilrs
+                .gamepads()
+                .next()
+                .map(|(_, pad)| ControllerKind::from_vendor_id(pad.vendor_id()))
+        };
+        handler.set_controller(connected(&gilrs));
+        loop {
+            let Some(gilrs::Event { event, .. }) = gilrs.next_event_blocking(None) else {
+                continue;
+            };
+            // Button names follow gilrs (`South`, `DPadUp`, ...)
+            match event {
+                gilrs::EventType::ButtonPressed(button, _) => {
+                    on_button(&handler, &tx, &format!("{:?}", button), true)
+                }
+                gilrs::EventType::ButtonReleased(button, _) => {
+                    on_button(&handler, &tx, &format!("{:?}", button), false)
+                }
+                gilrs::EventType::Connected | gilrs::EventType::Disconnected => {
+                    handler.set_controller(connected(&gilrs))
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+/// Gamepads are only read on Windows
+#[cfg(not(target_os = "windows"))]
+pub(super) fn spawn_listener(
+    _handler: Arc<InputHandler>,
+    _tx: mpsc::UnboundedSender<TimedKeyEvent>,
+) {
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combo::ComboCommand;
+    use crate::input::KeyEvent;
+    use rdev::Key;
+
+    #[test]
+    fn test_gamepad_buttons_complete_steps() {
+        let mut bindings = GamepadBindings {
+            swap: String::new(),
+            ..GamepadBindings::default()
+        };
+        let mapping = GamepadMapping::from_bindings(&bindings);
+        assert_eq!(mapping.key_for("dpadup"), Some(KeyIdentifier::Number(1)));
+        assert_eq!(mapping.key_for("LeftTrigger"), None);
+
+        let handler = InputHandler::new().with_gamepad_mapping(mapping);
+        handler.set_current_command(Some(Arc::new(ComboCommand::tap(
+            KeyIdentifier::Chain,
+            "チェン",
+            "連携",
+        ))));
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        on_button(&handler, &tx, "North", true);
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(TimedKeyEvent {
+                event: KeyEvent::TapComplete(Key::KeyE),
+                ..
+            })
+        ));
+
+        assert_eq!(handler.gamepad_glyph(&KeyIdentifier::Chain), None);
+        handler.set_controller(Some(ControllerKind::from_vendor_id(Some(0x054c))));
+        assert_eq!(
+            handler.gamepad_glyph(&KeyIdentifier::Chain).as_deref(),
+            Some("△")
+        );
+        handler.set_controller(Some(ControllerKind::Xbox));
+        assert_eq!(
+            handler.gamepad_glyph(&KeyIdentifier::Dodge).as_deref(),
+            Some("B")
+        );
+
+        bindings.enabled = false;
+        assert_eq!(
+            GamepadMapping::from_bindings(&bindings),
+            GamepadMapping::default()
+        );
+    }
+}
@@ -0,0 +1,83 @@
+//! Synthetic input for tests and demos
+//!
+//! Injected events go through the same callback as the global hook, so
+//! hotkeys, auto-repeat filtering and combo matching behave as for real keys.
+//! Nothing is sent to the game or any other application.
+
+use super::keymap::parse_key_name;
+use super::{MOUSE_LEFT_KEY, MOUSE_MIDDLE_KEY, MOUSE_RIGHT_KEY, MOUSE_X1_KEY, MOUSE_X2_KEY};
+use rdev::{Button, EventType, Key};
+use serde::{Deserialize, Serialize};
+
+/// An input event to feed to the listener pipeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InjectedInput {
+    /// Key or mouse button going down, by configured key name (`"2"`,
+    /// `"E"`, `"Space"`, `"MouseLeft"`, ...)
+    KeyPress { key: String },
+    /// Key or mouse button going up
+    KeyRelease { key: String },
+    /// Wheel notches (positive = up)
+    Wheel { delta: i64 },
+}
+
+impl InjectedInput {
+    /// Hook event for this input (`None` for an unknown key name)
+    pub fn event_type(&self) -> Option<EventType> {
+        match self {
+            Self::KeyPress { key } => {
+                let key = parse_key_name(key)?;
+                Some(mouse_button(key).map_or(EventType::KeyPress(key), EventType::ButtonPress))
+            }
+            Self::KeyRelease { key } => {
+                let key = parse_key_name(key)?;
+                Some(mouse_button(key).map_or(EventType::KeyRelease(key), EventType::ButtonRelease))
+            }
+            Self::Wheel { delta } => Some(EventType::Wheel {
+                delta_x: 0,
+                delta_y: *delta,
+            }),
+        }
+    }
+}
+
+/// Button the hook reports for a mouse button sentinel key
+fn mouse_button(key: Key) -> Option<Button> {
+    match key {
+        MOUSE_LEFT_KEY => Some(Button::Left),
+        MOUSE_RIGHT_KEY => Some(Button::Right),
+        MOUSE_MIDDLE_KEY => Some(Button::Middle),
+        MOUSE_X1_KEY => Some(Button::Unknown(1)),
+        MOUSE_X2_KEY => Some(Button::Unknown(2)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_injected_input_event_types() {
+        let press = |key: &str| InjectedInput::KeyPress {
+            key: key.to_string(),
+        };
+        assert_eq!(
+            press("E").event_type(),
+            Some(EventType::KeyPress(Key::KeyE))
+        );
+        assert_eq!(
+            press("MouseX1").event_type(),
+            Some(EventType::ButtonPress(Button::Unknown(1)))
+        );
+        assert_eq!(press("Hyper").event_type(), None);
+        assert_eq!(
+            InjectedInput::KeyRelease {
+                key: "2".to_string()
+            }
+            .event_type(),
+            Some(EventType::KeyRelease(Key::Num2))
+        );
+    }
+}
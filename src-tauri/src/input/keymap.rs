@@ -0,0 +1,365 @@
+//! Keyboard bindings of combo keys
+//!
+//! Combo files name what to do (`1` for operator 1's skill, `E` for the chain
+//! attack, `L` for the heavy attack); the keys that do it come from
+//! `KeyBindings` in the configuration, so remapped in-game keys work too.
+
+use super::{
+    MOUSE_LEFT_KEY, MOUSE_MIDDLE_KEY, MOUSE_RIGHT_KEY, MOUSE_X1_KEY, MOUSE_X2_KEY, WHEEL_DOWN_KEY,
+    WHEEL_UP_KEY,
+};
+use crate::combo::{KeyIdentifier, WheelDirection};
+use crate::config::KeyBindings;
+use rdev::Key;
+
+/// Digit keys with their keypad twins, indexed by digit
+const DIGITS: [(Key, Key); 10] = [
+    (Key::Num0, Key::Kp0),
+    (Key::Num1, Key::Kp1),
+    (Key::Num2, Key::Kp2),
+    (Key::Num3, Key::Kp3),
+    (Key::Num4, Key::Kp4),
+    (Key::Num5, Key::Kp5),
+    (Key::Num6, Key::Kp6),
+    (Key::Num7, Key::Kp7),
+    (Key::Num8, Key::Kp8),
+    (Key::Num9, Key::Kp9),
+];
+
+/// Letter keys in alphabetical order
+const LETTERS: [Key; 26] = [
+    Key::KeyA,
+    Key::KeyB,
+    Key::KeyC,
+    Key::KeyD,
+    Key::KeyE,
+    Key::KeyF,
+    Key::KeyG,
+    Key::KeyH,
+    Key::KeyI,
+    Key::KeyJ,
+    Key::KeyK,
+    Key::KeyL,
+    Key::KeyM,
+    Key::KeyN,
+    Key::KeyO,
+    Key::KeyP,
+    Key::KeyQ,
+    Key::KeyR,
+    Key::KeyS,
+    Key::KeyT,
+    Key::KeyU,
+    Key::KeyV,
+    Key::KeyW,
+    Key::KeyX,
+    Key::KeyY,
+    Key::KeyZ,
+];
+
+/// Other keys by their configuration name
+///
+/// Names follow rdev's key names (which older configurations stored), except
+/// for Enter.
+const NAMED_KEYS: [(&str, Key); 59] = [
+    ("Space", Key::Space),
+    ("Enter", Key::Return),
+    ("Escape", Key::Escape),
+    ("Tab", Key::Tab),
+    ("Backspace", Key::Backspace),
+    ("Delete", Key::Delete),
+    ("Insert", Key::Insert),
+    ("Home", Key::Home),
+    ("End", Key::End),
+    ("PageUp", Key::PageUp),
+    ("PageDown", Key::PageDown),
+    ("UpArrow", Key::UpArrow),
+    ("DownArrow", Key::DownArrow),
+    ("LeftArrow", Key::LeftArrow),
+    ("RightArrow", Key::RightArrow),
+    ("ShiftLeft", Key::ShiftLeft),
+    ("ShiftRight", Key::ShiftRight),
+    ("ControlLeft", Key::ControlLeft),
+    ("ControlRight", Key::ControlRight),
+    ("Alt", Key::Alt),
+    ("AltGr", Key::AltGr),
+    ("MetaLeft", Key::MetaLeft),
+    ("MetaRight", Key::MetaRight),
+    ("Function", Key::Function),
+    ("CapsLock", Key::CapsLock),
+    ("NumLock", Key::NumLock),
+    ("ScrollLock", Key::ScrollLock),
+    ("PrintScreen", Key::PrintScreen),
+    ("Pause", Key::Pause),
+    ("F1", Key::F1),
+    ("F2", Key::F2),
+    ("F3", Key::F3),
+    ("F4", Key::F4),
+    ("F5", Key::F5),
+    ("F6", Key::F6),
+    ("F7", Key::F7),
+    ("F8", Key::F8),
+    ("F9", Key::F9),
+    ("F10", Key::F10),
+    ("F11", Key::F11),
+    ("F12", Key::F12),
+    ("BackQuote", Key::BackQuote),
+    ("Minus", Key::Minus),
+    ("Equal", Key::Equal),
+    ("LeftBracket", Key::LeftBracket),
+    ("RightBracket", Key::RightBracket),
+    ("BackSlash", Key::BackSlash),
+    ("IntlBackslash", Key::IntlBackslash),
+    ("SemiColon", Key::SemiColon),
+    ("Quote", Key::Quote),
+    ("Comma", Key::Comma),
+    ("Dot", Key::Dot),
+    ("Slash", Key::Slash),
+    ("KpReturn", Key::KpReturn),
+    ("KpMinus", Key::KpMinus),
+    ("KpPlus", Key::KpPlus),
+    ("KpMultiply", Key::KpMultiply),
+    ("KpDivide", Key::KpDivide),
+    ("KpDelete", Key::KpDelete),
+];
+
+/// Mouse buttons by their configuration name (the listener's sentinel keys)
+const MOUSE_BUTTONS: [(&str, Key); 5] = [
+    ("MouseLeft", MOUSE_LEFT_KEY),
+    ("MouseRight", MOUSE_RIGHT_KEY),
+    ("MouseMiddle", MOUSE_MIDDLE_KEY),
+    ("MouseX1", MOUSE_X1_KEY),
+    ("MouseX2", MOUSE_X2_KEY),
+];
+
+/// Key for a configured key name (`"1"`, `"E"`, `"Space"`, `"Kp1"`,
+/// `"MouseLeft"`, `"Unknown(240)"`, ...)
+pub fn parse_key_name(name: &str) -> Option<Key> {
+    let name = name.trim();
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if let Some(digit) = c.to_digit(10) {
+            return Some(DIGITS[digit as usize].0);
+        }
+        if c.is_ascii_alphabetic() {
+            let index = c.to_ascii_uppercase() as usize - 'A' as usize;
+            return Some(LETTERS[index]);
+        }
+    }
+    if let Some(digit) = name
+        .strip_prefix("Kp")
+        .and_then(|d| d.parse::<usize>().ok())
+    {
+        return DIGITS.get(digit).map(|(_, keypad)| *keypad);
+    }
+    // Keys rdev has no name for keep their raw code
+    if let Some(code) = name
+        .strip_prefix("Unknown(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        return code.parse().ok().map(Key::Unknown);
+    }
+    MOUSE_BUTTONS
+        .iter()
+        .chain(NAMED_KEYS.iter())
+        .find(|(key_name, _)| key_name.eq_ignore_ascii_case(name))
+        .map(|(_, key)| *key)
+}
+
+/// Configuration name of `key`; [`parse_key_name`] reads it back
+pub fn key_name(key: Key) -> String {
+    if let Some(digit) = DIGITS.iter().position(|(num, _)| *num == key) {
+        return digit.to_string();
+    }
+    if let Some(digit) = DIGITS.iter().position(|(_, keypad)| *keypad == key) {
+        return format!("Kp{}", digit);
+    }
+    if let Some(letter) = LETTERS.iter().position(|letter| *letter == key) {
+        return char::from(b'A' + letter as u8).to_string();
+    }
+    MOUSE_BUTTONS
+        .iter()
+        .chain(NAMED_KEYS.iter())
+        .find(|(_, named)| *named == key)
+        .map_or_else(|| format!("{:?}", key), |(name, _)| name.to_string())
+}
+
+/// Keys that trigger each combo key
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyMap {
+    /// Earlier entries win when a key is listed twice
+    keys: Vec<(Key, KeyIdentifier)>,
+}
+
+impl KeyMap {
+    /// Key map for the configured bindings
+    ///
+    /// Operator skills, the chain attack, the heavy attack and dodge follow
+    /// the bindings; digits 5–9, Shift (dodge), swap and the mouse buttons
+    /// are fixed. Left click stays `MouseLeft` even while it is also the
+    /// heavy attack.
+    /// A skill bound to a digit also answers to the keypad digit.
+    pub fn from_bindings(bindings: &KeyBindings) -> Self {
+        let bound = [
+            (&bindings.operator1_skill, KeyIdentifier::Number(1)),
+            (&bindings.operator2_skill, KeyIdentifier::Number(2)),
+            (&bindings.operator3_skill, KeyIdentifier::Number(3)),
+            (&bindings.operator4_skill, KeyIdentifier::Number(4)),
+            (&bindings.chain_attack, KeyIdentifier::Chain),
+            (&bindings.heavy_attack, KeyIdentifier::HeavyAttack),
+            (&bindings.dodge, KeyIdentifier::Dodge),
+        ];
+        let mut keys = Vec::new();
+        for (name, id) in bound {
+            let Some(key) = parse_key_name(name) else {
+                eprintln!("[ERROR] Unknown key binding '{}' for {:?}", name, id);
+                continue;
+            };
+            keys.push((key, id.clone()));
+            if let Some((_, keypad)) = DIGITS.iter().find(|(digit, _)| *digit == key) {
+                keys.push((*keypad, id));
+            }
+        }
+
+        for (n, (digit, keypad)) in DIGITS.iter().enumerate().skip(5) {
+            keys.push((*digit, KeyIdentifier::Number(n as u8)));
+            keys.push((*keypad, KeyIdentifier::Number(n as u8)));
+        }
+        keys.extend([
+            (Key::ShiftLeft, KeyIdentifier::Dodge),
+            (Key::ShiftRight, KeyIdentifier::Dodge),
+            (Key::KeyQ, KeyIdentifier::Swap),
+            (MOUSE_LEFT_KEY, KeyIdentifier::MouseLeft),
+            (MOUSE_RIGHT_KEY, KeyIdentifier::MouseRight),
+            (MOUSE_MIDDLE_KEY, KeyIdentifier::MouseMiddle),
+            (MOUSE_X1_KEY, KeyIdentifier::MouseX1),
+            (MOUSE_X2_KEY, KeyIdentifier::MouseX2),
+            (WHEEL_UP_KEY, KeyIdentifier::Wheel(WheelDirection::Up)),
+            (WHEEL_DOWN_KEY, KeyIdentifier::Wheel(WheelDirection::Down)),
+        ]);
+        Self { keys }
+    }
+
+    /// Combo key a key press stands for
+    pub fn identifier(&self, key: &Key) -> Option<KeyIdentifier> {
+        self.keys
+            .iter()
+            .find(|(bound, _)| bound == key)
+            .map(|(_, id)| id.clone())
+    }
+
+    /// Whether `key` triggers `id` (a key may stand for more than one)
+    pub fn triggers(&self, key: &Key, id: &KeyIdentifier) -> bool {
+        self.keys
+            .iter()
+            .any(|(bound, bound_id)| bound == key && bound_id == id)
+    }
+
+    /// Key that triggers `id` (the first one bound)
+    pub fn key(&self, id: &KeyIdentifier) -> Option<Key> {
+        self.keys
+            .iter()
+            .find(|(_, bound)| bound == id)
+            .map(|(key, _)| *key)
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self::from_bindings(&KeyBindings::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_names_round_trip() {
+        for key in [
+            Key::Num7,
+            Key::Kp7,
+            Key::KeyQ,
+            Key::F11,
+            Key::Dot,
+            Key::KpPlus,
+            Key::Return,
+            MOUSE_RIGHT_KEY,
+            Key::Unknown(240),
+        ] {
+            assert_eq!(parse_key_name(&key_name(key)), Some(key), "{:?}", key);
+        }
+        assert_eq!(key_name(Key::Return), "Enter");
+        assert_eq!(key_name(Key::Unknown(240)), "Unknown(240)");
+        // Names as older versions stored them
+        assert_eq!(parse_key_name("RightArrow"), Some(Key::RightArrow));
+        assert_eq!(parse_key_name("space"), Some(Key::Space));
+    }
+
+    #[test]
+    fn test_key_map_follows_bindings() {
+        let default = KeyMap::default();
+        assert_eq!(
+            default.identifier(&Key::Kp2),
+            Some(KeyIdentifier::Number(2))
+        );
+        assert_eq!(default.identifier(&Key::KeyE), Some(KeyIdentifier::Chain));
+        assert_eq!(
+            default.identifier(&MOUSE_LEFT_KEY),
+            Some(KeyIdentifier::HeavyAttack)
+        );
+        assert_eq!(default.key(&KeyIdentifier::MouseLeft), Some(MOUSE_LEFT_KEY));
+        assert!(default.triggers(&MOUSE_LEFT_KEY, &KeyIdentifier::MouseLeft));
+        // Ctrl+Break comes through the keyboard hook as `VK_CANCEL` (3)
+        assert_eq!(default.identifier(&Key::Unknown(3)), None);
+
+        let bindings = KeyBindings {
+            operator1_skill: "z".to_string(),
+            operator2_skill: "X".to_string(),
+            chain_attack: "F".to_string(),
+            heavy_attack: "nonsense".to_string(),
+            dodge: "MouseX1".to_string(),
+            ..KeyBindings::default()
+        };
+        let remapped = KeyMap::from_bindings(&bindings);
+        assert_eq!(
+            remapped.identifier(&Key::KeyZ),
+            Some(KeyIdentifier::Number(1))
+        );
+        assert_eq!(remapped.identifier(&Key::Num1), None);
+        assert_eq!(remapped.identifier(&Key::KeyE), None);
+        assert_eq!(remapped.key(&KeyIdentifier::Chain), Some(Key::KeyF));
+        assert_eq!(
+            remapped.identifier(&Key::Num3),
+            Some(KeyIdentifier::Number(3))
+        );
+        assert_eq!(remapped.key(&KeyIdentifier::HeavyAttack), None);
+        assert_eq!(
+            remapped.identifier(&MOUSE_LEFT_KEY),
+            Some(KeyIdentifier::MouseLeft)
+        );
+
+        // A keyboard heavy attack leaves left click to itself
+        let heavy_on_f = KeyMap::from_bindings(&KeyBindings {
+            heavy_attack: "F".to_string(),
+            ..KeyBindings::default()
+        });
+        assert_eq!(
+            heavy_on_f.identifier(&Key::KeyF),
+            Some(KeyIdentifier::HeavyAttack)
+        );
+        assert!(!heavy_on_f.triggers(&MOUSE_LEFT_KEY, &KeyIdentifier::HeavyAttack));
+        assert_eq!(
+            heavy_on_f.key(&KeyIdentifier::MouseLeft),
+            Some(MOUSE_LEFT_KEY)
+        );
+        assert_eq!(
+            remapped.identifier(&MOUSE_X1_KEY),
+            Some(KeyIdentifier::Dodge)
+        );
+        assert_eq!(remapped.identifier(&Key::Space), None);
+        assert_eq!(
+            remapped.identifier(&MOUSE_MIDDLE_KEY),
+            Some(KeyIdentifier::MouseMiddle)
+        );
+    }
+}
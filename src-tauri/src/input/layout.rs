@@ -0,0 +1,80 @@
+//! Keyboard layouts
+//!
+//! On Windows the hook reports keys by the virtual key the layout assigns
+//! them, while the game reads physical positions. With the player's layout
+//! configured, keys are translated back to the US QWERTY key at the same
+//! position before combo matching, so `Q` (swap) and `W~L` follow the physical
+//! key on AZERTY, QWERTZ and JIS keyboards.
+
+use rdev::Key;
+use serde::{Deserialize, Serialize};
+
+/// Virtual key → QWERTY position on AZERTY keyboards
+const AZERTY: [(Key, Key); 6] = [
+    (Key::KeyA, Key::KeyQ),
+    (Key::KeyQ, Key::KeyA),
+    (Key::KeyZ, Key::KeyW),
+    (Key::KeyW, Key::KeyZ),
+    (Key::KeyM, Key::SemiColon),
+    (Key::Comma, Key::KeyM),
+];
+
+/// Virtual key → QWERTY position on QWERTZ keyboards
+const QWERTZ: [(Key, Key); 2] = [(Key::KeyY, Key::KeyZ), (Key::KeyZ, Key::KeyY)];
+
+/// Virtual key → QWERTY position on JIS keyboards (letters and digits
+/// already match; only the symbol keys move)
+const JIS: [(Key, Key); 6] = [
+    (Key::Quote, Key::Equal),
+    (Key::BackQuote, Key::LeftBracket),
+    (Key::LeftBracket, Key::RightBracket),
+    (Key::RightBracket, Key::BackSlash),
+    (Key::SemiColon, Key::Quote),
+    (Key::Equal, Key::SemiColon),
+];
+
+/// Physical keyboard layout of the player
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyboardLayout {
+    #[default]
+    Qwerty,
+    Azerty,
+    Qwertz,
+    Jis,
+}
+
+impl KeyboardLayout {
+    /// US QWERTY key at the position of the key reported as `key`
+    pub fn physical(self, key: Key) -> Key {
+        let table: &[(Key, Key)] = match self {
+            Self::Qwerty => &[],
+            Self::Azerty => &AZERTY,
+            Self::Qwertz => &QWERTZ,
+            Self::Jis => &JIS,
+        };
+        table
+            .iter()
+            .find(|(reported, _)| *reported == key)
+            .map_or(key, |(_, position)| *position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layouts_map_to_qwerty_positions() {
+        assert_eq!(KeyboardLayout::Qwerty.physical(Key::KeyA), Key::KeyA);
+        // AZERTY's A sits where QWERTY has Q, and Z where it has W
+        assert_eq!(KeyboardLayout::Azerty.physical(Key::KeyA), Key::KeyQ);
+        assert_eq!(KeyboardLayout::Azerty.physical(Key::KeyZ), Key::KeyW);
+        assert_eq!(KeyboardLayout::Azerty.physical(Key::Num1), Key::Num1);
+        assert_eq!(KeyboardLayout::Qwertz.physical(Key::KeyY), Key::KeyZ);
+        assert_eq!(
+            KeyboardLayout::Jis.physical(Key::BackQuote),
+            Key::LeftBracket
+        );
+    }
+}
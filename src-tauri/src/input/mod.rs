@@ -2,17 +2,71 @@
 //!
 //! Handles global keyboard hooks and implements tap/hold detection logic.
 
-use parking_lot::RwLock;
+pub mod chord;
+pub mod gamepad;
+pub mod inject;
+pub mod keymap;
+pub mod layout;
+pub mod recorder;
+pub mod repeat;
+pub mod sequence;
+
+use parking_lot::{Condvar, Mutex, RwLock};
 use rdev::{listen, Event, EventType, Key};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::mpsc;
 
-use crate::combo::{ComboCommand, InputType, KeyIdentifier};
+use self::chord::DownKey;
+use self::gamepad::{ControllerKind, GamepadMapping};
+use self::keymap::KeyMap;
+use self::layout::KeyboardLayout;
+use self::recorder::Recording;
+use self::repeat::RepeatFilter;
+use self::sequence::SequenceProgress;
+use crate::combo::{ComboCommand, Direction, InputType, KeyIdentifier};
 
 /// Default hold threshold in milliseconds
-const DEFAULT_HOLD_THRESHOLD_MS: u64 = 300;
+pub const DEFAULT_HOLD_THRESHOLD_MS: u64 = 300;
+
+/// Step used when nudging the hold threshold at runtime
+pub const HOLD_THRESHOLD_STEP_MS: i64 = 25;
+
+/// First wait before restarting a failed global listener
+const LISTENER_RETRY_MIN: Duration = Duration::from_secs(1);
+
+/// Longest wait between listener restarts
+const LISTENER_RETRY_MAX: Duration = Duration::from_secs(30);
+
+/// A listener that ran this long before failing starts over at the shortest wait
+const LISTENER_STABLE: Duration = Duration::from_secs(60);
+
+/// A restarted listener that hasn't failed for this long is reported as running
+const LISTENER_GRACE: Duration = Duration::from_secs(2);
+
+/// Allowed hold threshold range in milliseconds
+const HOLD_THRESHOLD_RANGE_MS: (u64, u64) = (50, 2000);
+
+/// Default interval between hold progress updates in milliseconds
+pub const DEFAULT_PROGRESS_INTERVAL_MS: u64 = 50;
+
+/// Default interval between hold progress updates close to completion
+pub const DEFAULT_PROGRESS_FINE_INTERVAL_MS: u64 = 10;
+
+/// Remaining hold time below which the fine interval is used
+const PROGRESS_FINE_WINDOW_MS: u64 = 100;
+
+/// Allowed progress interval range in milliseconds
+const PROGRESS_INTERVAL_RANGE_MS: (u64, u64) = (5, 500);
+
+/// Default window in which all keys of a chord must be pressed
+pub const DEFAULT_CHORD_WINDOW_MS: u64 = 150;
+
+/// Default debounce window (0 = off)
+pub const DEFAULT_DEBOUNCE_MS: u64 = 0;
 
 /// Key event types for the input handler
 #[derive(Debug, Clone)]
@@ -25,10 +79,94 @@ pub enum KeyEvent {
     HoldComplete(Key),
     /// Tap completed (key released before hold threshold)
     TapComplete(Key),
-    /// Hold progress update (key, progress 0.0-1.0)
-    HoldProgress(Key, f32),
+    /// Every key of the current chord step is down
+    ChordComplete(Vec<Key>),
+    /// Key of a release step was let go
+    ReleaseComplete(Key),
+    /// Hold progress update
+    HoldProgress(Key, HoldProgressInfo),
     /// Hold cancelled/reset (key released before threshold)
     HoldReset(Key),
+    /// The next step's key was pressed on an optional step, which is skipped
+    ///
+    /// The key stays pressed so [`InputHandler::evaluate_press`] can count it
+    /// towards the next step once that is current.
+    StepSkipped(Key),
+    /// Progress of the current wait step
+    WaitProgress(HoldProgressInfo),
+    /// The time of the current wait step is up
+    WaitComplete,
+    /// A combo key that doesn't belong to the current step was pressed
+    /// (strict mode only)
+    Miss(Key),
+    /// The OS repeated the press of a held key (auto-repeat)
+    KeyRepeat(Key),
+    /// The global listener failed or came back
+    ListenerStatus(ListenerStatus),
+    /// A charged hold crossed one of its stages
+    ChargeStage(Key, ChargeStageInfo),
+    /// A charged hold was let go after reaching at least its first stage
+    ChargeComplete(Key, ChargeStageInfo),
+    /// A press of a multi-tap step that doesn't complete it yet
+    TapProgress(Key, TapCountInfo),
+}
+
+impl KeyEvent {
+    /// This event, stamped with the time its input happened
+    pub fn at(self, at: Instant) -> TimedKeyEvent {
+        TimedKeyEvent { event: self, at }
+    }
+}
+
+/// A [`KeyEvent`] with the time it was captured
+///
+/// Stamped in the hook callback (or by the timer that detected it), so
+/// timings measured by the receiver don't include the wait in the channel.
+#[derive(Debug, Clone)]
+pub struct TimedKeyEvent {
+    pub event: KeyEvent,
+    /// When the input behind the event happened
+    pub at: Instant,
+}
+
+/// Hold (or wait) progress payload sent to the frontend
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct HoldProgressInfo {
+    /// Progress (0.0 - 1.0)
+    pub progress: f32,
+    /// Milliseconds left until the hold completes
+    pub remaining_ms: u64,
+    /// Hold duration required by the current step
+    pub target_ms: u64,
+}
+
+/// Global listener state, sent as `input-listener-status`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListenerStatus {
+    /// Whether keys are being received
+    pub running: bool,
+    /// Why the listener stopped
+    pub error: Option<String>,
+    /// Milliseconds until the next restart attempt
+    pub retry_in_ms: Option<u64>,
+}
+
+/// Charge stage payload sent to the frontend
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChargeStageInfo {
+    /// Stage reached (1-based)
+    pub stage: u32,
+    /// Number of stages of the step
+    pub stages: u32,
+}
+
+/// Multi-tap progress payload sent to the frontend
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TapCountInfo {
+    /// Presses so far
+    pub presses: u32,
+    /// Presses the step needs
+    pub count: u32,
 }
 
 /// State of a pressed key
@@ -38,6 +176,8 @@ struct KeyState {
     hold_triggered: bool,
     /// Whether this key press has been consumed by a tap command
     consumed: bool,
+    /// Charge stages already reported for this press
+    charge_stage: u32,
 }
 
 /// Input handler for combo navigation
@@ -45,10 +185,159 @@ struct KeyState {
 pub struct InputHandler {
     /// Map of currently pressed keys to their state
     key_states: Arc<RwLock<HashMap<Key, KeyState>>>,
-    /// Current command being waited for
-    current_command: Arc<RwLock<Option<ComboCommand>>>,
-    /// Hold threshold duration
-    hold_threshold: Duration,
+    /// Current command being waited for (shared with the loaded combo file)
+    current_command: Arc<RwLock<Option<Arc<ComboCommand>>>>,
+    /// Step after the current one (lets optional steps be skipped)
+    next_command: Arc<RwLock<Option<Arc<ComboCommand>>>>,
+    /// Hold threshold in milliseconds (shared so runtime changes reach the listener)
+    hold_threshold_ms: Arc<AtomicU64>,
+    /// Normal hold progress update interval in milliseconds
+    progress_interval_ms: Arc<AtomicU64>,
+    /// Hold progress update interval near completion in milliseconds
+    progress_fine_interval_ms: Arc<AtomicU64>,
+    /// Maximum spread between the presses of a chord in milliseconds
+    chord_window_ms: Arc<AtomicU64>,
+    /// Window after a press in which further presses of the same key are
+    /// ignored as switch bounce, in milliseconds
+    debounce_ms: Arc<AtomicU64>,
+    /// When each key last counted as pressed, for debouncing
+    last_press: Arc<RwLock<HashMap<Key, Instant>>>,
+    /// Whether wrong combo keys are reported as misses
+    strict_mode: Arc<AtomicBool>,
+    /// Whether tap steps complete when their key is let go instead of pressed
+    advance_on_release: Arc<AtomicBool>,
+    /// Whether combo matching is paused (e.g. while in a game menu)
+    paused: Arc<AtomicBool>,
+    /// Whether presses only count while the game is the foreground window
+    require_game_focus: Arc<AtomicBool>,
+    /// Whether the game was the foreground window at the last focus poll
+    game_foreground: Arc<AtomicBool>,
+    /// When the current wait step started (`None` when the current step is
+    /// no wait, or its time is already up)
+    wait_started: Arc<RwLock<Option<Instant>>>,
+    /// When the first press of the current double tap step went down
+    first_tap: Arc<RwLock<Option<Instant>>>,
+    /// Progress through the current sequence step
+    sequence: Arc<RwLock<SequenceProgress>>,
+    /// Presses of the current multi-tap step so far, and when the last one
+    /// went down
+    multi_tap: Arc<RwLock<Option<(u32, Instant)>>>,
+    /// Keyboard and mouse keys bound to combo keys
+    keymap: Arc<RwLock<KeyMap>>,
+    /// Physical layout of the player's keyboard
+    layout: Arc<RwLock<KeyboardLayout>>,
+    /// Controller buttons bound to combo keys
+    gamepad: Arc<RwLock<GamepadMapping>>,
+    /// Family of the connected controller (`None` when none is connected)
+    controller: Arc<RwLock<Option<ControllerKind>>>,
+    /// Presses captured while recording a combo (`None` when not recording)
+    recording: Arc<RwLock<Option<Recording>>>,
+    /// Wakes the hold timer thread, which sleeps while no hold or wait step
+    /// is running (the flag records a wakeup that came in while it was busy)
+    hold_wakeup: Arc<(Mutex<bool>, Condvar)>,
+    /// Feeds synthetic events to the listener (`None` until it is started)
+    injector: Arc<RwLock<Option<std::sync::mpsc::Sender<EventType>>>>,
+}
+
+/// Wait before restarting a listener that failed after running `ran_for`
+///
+/// Waits double on each quick failure in a row, up to [`LISTENER_RETRY_MAX`].
+fn retry_delay(previous: Option<Duration>, ran_for: Duration) -> Duration {
+    match previous {
+        Some(previous) if ran_for < LISTENER_STABLE => (previous * 2).min(LISTENER_RETRY_MAX),
+        _ => LISTENER_RETRY_MIN,
+    }
+}
+
+/// Listener restart waiting to be reported as running
+///
+/// `listen` only returns when the hook fails, so a restart counts as running
+/// once the hook delivers an event or survives [`LISTENER_GRACE`].
+#[derive(Debug, Default)]
+struct PendingRestart(AtomicU64);
+
+impl PendingRestart {
+    /// Wait for restart `attempt` (counting from 1) to come up
+    fn arm(&self, attempt: u64) {
+        self.0.store(attempt, Ordering::Relaxed);
+    }
+
+    /// Stop waiting, as the listener failed
+    fn cancel(&self) {
+        self.0.store(0, Ordering::Relaxed);
+    }
+
+    /// The hook delivered an event; whether a restart was still unconfirmed
+    fn confirm(&self) -> bool {
+        self.0.swap(0, Ordering::Relaxed) != 0
+    }
+
+    /// Grace period of restart `attempt` is over; whether it is still
+    /// unconfirmed and hasn't failed
+    fn confirm_attempt(&self, attempt: u64) -> bool {
+        self.0
+            .compare_exchange(attempt, 0, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+    }
+}
+
+/// Report the global listener as running again
+fn send_listener_running(tx: &mpsc::UnboundedSender<TimedKeyEvent>) {
+    let status = ListenerStatus {
+        running: true,
+        error: None,
+        retry_in_ms: None,
+    };
+    let _ = tx.send(KeyEvent::ListenerStatus(status).at(Instant::now()));
+}
+
+// Sentinel keys standing for mouse buttons and wheel notches. Windows reports
+// virtual keys rdev has no name for as `Unknown(vk)`, so they lie above the
+// virtual-key range (Ctrl+Break sends `VK_CANCEL`, 3, through the keyboard hook).
+
+/// Left mouse button
+pub const MOUSE_LEFT_KEY: Key = Key::Unknown(0x1_0001);
+
+/// Right mouse button
+pub const MOUSE_RIGHT_KEY: Key = Key::Unknown(0x1_0002);
+
+/// Wheel notch up
+pub const WHEEL_UP_KEY: Key = Key::Unknown(0x1_0003);
+
+/// Wheel notch down
+pub const WHEEL_DOWN_KEY: Key = Key::Unknown(0x1_0004);
+
+/// Middle mouse button (wheel click)
+pub const MOUSE_MIDDLE_KEY: Key = Key::Unknown(0x1_0005);
+
+/// Back side button
+pub const MOUSE_X1_KEY: Key = Key::Unknown(0x1_0006);
+
+/// Forward side button
+pub const MOUSE_X2_KEY: Key = Key::Unknown(0x1_0007);
+
+/// Sentinel key standing for a mouse button
+///
+/// Side buttons are reported as `Unknown(1)`/`Unknown(2)` on Windows and as
+/// `Unknown(8)`/`Unknown(9)` on X11.
+fn mouse_button_key(button: rdev::Button) -> Option<Key> {
+    match button {
+        rdev::Button::Left => Some(MOUSE_LEFT_KEY),
+        rdev::Button::Right => Some(MOUSE_RIGHT_KEY),
+        rdev::Button::Middle => Some(MOUSE_MIDDLE_KEY),
+        rdev::Button::Unknown(1 | 8) => Some(MOUSE_X1_KEY),
+        rdev::Button::Unknown(2 | 9) => Some(MOUSE_X2_KEY),
+        rdev::Button::Unknown(_) => None,
+    }
+}
+
+/// Stage a charge held for `held` has reached (0 before the first stage)
+fn charge_stage(stages: &[u64], held: Duration) -> ChargeStageInfo {
+    let held_ms = held.as_millis() as u64;
+    ChargeStageInfo {
+        stage: stages.iter().filter(|&&ms| held_ms >= ms).count() as u32,
+        stages: stages.len() as u32,
+    }
 }
 
 impl InputHandler {
@@ -57,87 +346,727 @@ impl InputHandler {
         Self {
             key_states: Arc::new(RwLock::new(HashMap::new())),
             current_command: Arc::new(RwLock::new(None)),
-            hold_threshold: Duration::from_millis(DEFAULT_HOLD_THRESHOLD_MS),
+            next_command: Arc::new(RwLock::new(None)),
+            hold_threshold_ms: Arc::new(AtomicU64::new(DEFAULT_HOLD_THRESHOLD_MS)),
+            progress_interval_ms: Arc::new(AtomicU64::new(DEFAULT_PROGRESS_INTERVAL_MS)),
+            progress_fine_interval_ms: Arc::new(AtomicU64::new(DEFAULT_PROGRESS_FINE_INTERVAL_MS)),
+            chord_window_ms: Arc::new(AtomicU64::new(DEFAULT_CHORD_WINDOW_MS)),
+            debounce_ms: Arc::new(AtomicU64::new(DEFAULT_DEBOUNCE_MS)),
+            last_press: Arc::new(RwLock::new(HashMap::new())),
+            strict_mode: Arc::new(AtomicBool::new(false)),
+            advance_on_release: Arc::new(AtomicBool::new(false)),
+            paused: Arc::new(AtomicBool::new(false)),
+            require_game_focus: Arc::new(AtomicBool::new(false)),
+            game_foreground: Arc::new(AtomicBool::new(true)),
+            wait_started: Arc::new(RwLock::new(None)),
+            first_tap: Arc::new(RwLock::new(None)),
+            sequence: Arc::new(RwLock::new(SequenceProgress::default())),
+            multi_tap: Arc::new(RwLock::new(None)),
+            keymap: Arc::new(RwLock::new(KeyMap::default())),
+            layout: Arc::new(RwLock::new(KeyboardLayout::default())),
+            gamepad: Arc::new(RwLock::new(GamepadMapping::default())),
+            controller: Arc::new(RwLock::new(None)),
+            recording: Arc::new(RwLock::new(None)),
+            hold_wakeup: Arc::new((Mutex::new(false), Condvar::new())),
+            injector: Arc::new(RwLock::new(None)),
         }
     }
 
     /// Create with custom hold threshold
-    pub fn with_hold_threshold(mut self, threshold_ms: u64) -> Self {
-        self.hold_threshold = Duration::from_millis(threshold_ms);
+    pub fn with_hold_threshold(self, threshold_ms: u64) -> Self {
+        self.set_hold_threshold(threshold_ms);
+        self
+    }
+
+    /// Current hold threshold
+    pub fn hold_threshold(&self) -> Duration {
+        Duration::from_millis(self.hold_threshold_ms())
+    }
+
+    /// Current hold threshold in milliseconds
+    pub fn hold_threshold_ms(&self) -> u64 {
+        self.hold_threshold_ms.load(Ordering::Relaxed)
+    }
+
+    /// Set the hold threshold, clamped to the allowed range. Returns the applied value.
+    pub fn set_hold_threshold(&self, threshold_ms: u64) -> u64 {
+        let (min, max) = HOLD_THRESHOLD_RANGE_MS;
+        let threshold_ms = threshold_ms.clamp(min, max);
+        self.hold_threshold_ms
+            .store(threshold_ms, Ordering::Relaxed);
+        threshold_ms
+    }
+
+    /// Nudge the hold threshold by `delta_ms`. Returns the applied value.
+    pub fn adjust_hold_threshold(&self, delta_ms: i64) -> u64 {
+        let current = self.hold_threshold_ms() as i64;
+        self.set_hold_threshold(current.saturating_add(delta_ms).max(0) as u64)
+    }
+
+    /// Create with custom hold progress update intervals
+    pub fn with_progress_intervals(self, interval_ms: u64, fine_interval_ms: u64) -> Self {
+        self.set_progress_intervals(interval_ms, fine_interval_ms);
+        self
+    }
+
+    /// Set the hold progress update intervals, clamped to the allowed range
+    ///
+    /// The fine interval never exceeds the normal one. Returns the applied values.
+    pub fn set_progress_intervals(&self, interval_ms: u64, fine_interval_ms: u64) -> (u64, u64) {
+        let (min, max) = PROGRESS_INTERVAL_RANGE_MS;
+        let interval_ms = interval_ms.clamp(min, max);
+        let fine_interval_ms = fine_interval_ms.clamp(min, interval_ms);
+        self.progress_interval_ms
+            .store(interval_ms, Ordering::Relaxed);
+        self.progress_fine_interval_ms
+            .store(fine_interval_ms, Ordering::Relaxed);
+        (interval_ms, fine_interval_ms)
+    }
+
+    /// Create with a custom chord window
+    pub fn with_chord_window(self, window_ms: u64) -> Self {
+        self.set_chord_window(window_ms);
+        self
+    }
+
+    /// Start capturing combo key presses, dropping any earlier recording
+    pub fn start_recording(&self) {
+        *self.recording.write() = Some(Recording::default());
+    }
+
+    /// Stop capturing and hand over what was recorded (`None` when not
+    /// recording)
+    pub fn stop_recording(&self) -> Option<Recording> {
+        self.recording.write().take()
+    }
+
+    /// Create with strict mode on or off
+    pub fn with_strict_mode(self, strict: bool) -> Self {
+        self.set_strict_mode(strict);
+        self
+    }
+
+    /// Turn strict mode (miss events for wrong keys) on or off
+    pub fn set_strict_mode(&self, strict: bool) {
+        self.strict_mode.store(strict, Ordering::Relaxed);
+    }
+
+    /// Create with tap steps completing on press or on release
+    pub fn with_advance_on_release(self, on_release: bool) -> Self {
+        self.set_advance_on_release(on_release);
+        self
+    }
+
+    /// Complete tap steps when their key is let go (for skills that fire on
+    /// release) instead of when it is pressed
+    pub fn set_advance_on_release(&self, on_release: bool) {
+        self.advance_on_release.store(on_release, Ordering::Relaxed);
+    }
+
+    /// Pause or resume combo matching
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+        self.wake_hold_timer();
+    }
+
+    /// Whether combo matching is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Create with presses only counting while the game is in front
+    pub fn with_require_game_focus(self, require: bool) -> Self {
+        self.set_require_game_focus(require);
+        self
+    }
+
+    /// Only count presses while the game is the foreground window
+    pub fn set_require_game_focus(&self, require: bool) {
+        self.require_game_focus.store(require, Ordering::Relaxed);
+    }
+
+    /// Record whether the game is the foreground window
+    ///
+    /// Set by the focus poll, so key events don't query the window system.
+    pub fn set_game_foreground(&self, foreground: bool) {
+        self.game_foreground.store(foreground, Ordering::Relaxed);
+    }
+
+    /// Whether presses currently count as combo input
+    ///
+    /// Not while paused, nor (if required) while another window such as a
+    /// chat app is in front of the game.
+    fn accepts_input(&self) -> bool {
+        !self.is_paused()
+            && (!self.require_game_focus.load(Ordering::Relaxed)
+                || self.game_foreground.load(Ordering::Relaxed))
+    }
+
+    /// Create with the player's keyboard layout
+    pub fn with_keyboard_layout(self, layout: KeyboardLayout) -> Self {
+        self.set_keyboard_layout(layout);
+        self
+    }
+
+    /// Set the player's keyboard layout
+    pub fn set_keyboard_layout(&self, layout: KeyboardLayout) {
+        *self.layout.write() = layout;
+    }
+
+    /// Key at the same position on a US QWERTY keyboard
+    ///
+    /// Only the Windows hook reports keys by layout; elsewhere keys already
+    /// name their position.
+    fn physical_key(&self, key: Key) -> Key {
+        if cfg!(target_os = "windows") {
+            self.layout.read().physical(key)
+        } else {
+            key
+        }
+    }
+
+    /// Create with keyboard bindings for combo keys
+    pub fn with_key_map(self, keymap: KeyMap) -> Self {
+        self.set_key_map(keymap);
+        self
+    }
+
+    /// Replace the keyboard bindings for combo keys
+    pub fn set_key_map(&self, keymap: KeyMap) {
+        *self.keymap.write() = keymap;
+    }
+
+    /// Create with controller button bindings
+    pub fn with_gamepad_mapping(self, mapping: GamepadMapping) -> Self {
+        self.set_gamepad_mapping(mapping);
+        self
+    }
+
+    /// Replace the controller button bindings
+    pub fn set_gamepad_mapping(&self, mapping: GamepadMapping) {
+        *self.gamepad.write() = mapping;
+    }
+
+    /// Key a controller button stands for
+    fn gamepad_key(&self, button: &str) -> Option<Key> {
+        let id = self.gamepad.read().key_for(button)?;
+        self.keymap.read().key(&id)
+    }
+
+    /// Whether `key` is currently held down
+    pub fn is_key_down(&self, key: &Key) -> bool {
+        self.key_states.read().contains_key(key)
+    }
+
+    /// Family of the connected controller
+    pub fn controller(&self) -> Option<ControllerKind> {
+        *self.controller.read()
+    }
+
+    /// Record the connected controller
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    fn set_controller(&self, controller: Option<ControllerKind>) {
+        *self.controller.write() = controller;
+    }
+
+    /// Glyph of the button bound to `key` on the connected controller
+    pub fn gamepad_glyph(&self, key: &KeyIdentifier) -> Option<String> {
+        self.gamepad_glyphs()
+            .into_iter()
+            .find(|(bound, _)| bound == key)
+            .map(|(_, glyph)| glyph)
+    }
+
+    /// Glyphs of all bound buttons on the connected controller (empty when
+    /// none is connected)
+    pub fn gamepad_glyphs(&self) -> Vec<(KeyIdentifier, String)> {
+        match self.controller() {
+            Some(kind) => self.gamepad.read().glyphs(kind),
+            None => Vec::new(),
+        }
+    }
+
+    /// Set the chord window
+    pub fn set_chord_window(&self, window_ms: u64) {
+        self.chord_window_ms.store(window_ms, Ordering::Relaxed);
+    }
+
+    /// Create with a debounce window
+    pub fn with_debounce(self, debounce_ms: u64) -> Self {
+        self.set_debounce(debounce_ms);
         self
     }
 
+    /// Set the debounce window (0 = off)
+    pub fn set_debounce(&self, debounce_ms: u64) {
+        self.debounce_ms.store(debounce_ms, Ordering::Relaxed);
+    }
+
+    /// Whether a press is a bounce of the key's last press
+    ///
+    /// Worn switches can register one press twice, which would otherwise
+    /// complete two tap steps at once.
+    fn is_bounce(&self, key: Key, now: Instant) -> bool {
+        let window = Duration::from_millis(self.debounce_ms.load(Ordering::Relaxed));
+        let mut last_press = self.last_press.write();
+        let bounce = last_press
+            .get(&key)
+            .is_some_and(|pressed| now.duration_since(*pressed) < window);
+        if !bounce {
+            last_press.insert(key, now);
+        }
+        bounce
+    }
+
+    /// Delay until the next hold progress update (`None` = nothing to time)
+    ///
+    /// `remaining_ms` is the shortest remaining hold time among tracked keys. Ticks
+    /// switch to the fine interval once a hold is about to complete, and never
+    /// overshoot the completion itself.
+    fn progress_tick(&self, remaining_ms: Option<u64>) -> Option<Duration> {
+        let interval = self.progress_interval_ms.load(Ordering::Relaxed);
+        let fine = self.progress_fine_interval_ms.load(Ordering::Relaxed);
+        let tick = match remaining_ms? {
+            remaining if remaining <= PROGRESS_FINE_WINDOW_MS => fine.min(remaining).max(1),
+            // Don't sleep past the start of the fine window
+            remaining => interval.min(remaining - PROGRESS_FINE_WINDOW_MS).max(fine),
+        };
+        Some(Duration::from_millis(tick))
+    }
+
+    /// Let the hold timer check for holds and waits that may have started
+    fn wake_hold_timer(&self) {
+        let (pending, wakeup) = &*self.hold_wakeup;
+        *pending.lock() = true;
+        wakeup.notify_one();
+    }
+
+    /// Sleep for `tick`, or until woken when `None`; a wakeup cuts either short
+    fn sleep_hold_timer(&self, tick: Option<Duration>) {
+        let (pending, wakeup) = &*self.hold_wakeup;
+        let mut pending = pending.lock();
+        if !*pending {
+            match tick {
+                Some(tick) => {
+                    wakeup.wait_for(&mut pending, tick);
+                }
+                None => wakeup.wait(&mut pending),
+            }
+        }
+        *pending = false;
+    }
+
     /// Set the current command to wait for
-    pub fn set_current_command(&self, command: Option<ComboCommand>) {
+    ///
+    /// The timer of a wait step starts over each time it is set.
+    pub fn set_current_command(&self, command: Option<Arc<ComboCommand>>) {
+        let waits = command
+            .as_ref()
+            .is_some_and(|cmd| matches!(cmd.input_type, InputType::Wait { .. }));
+        *self.wait_started.write() = waits.then(Instant::now);
+        *self.first_tap.write() = None;
+        *self.sequence.write() = SequenceProgress::default();
+        *self.multi_tap.write() = None;
         let mut current = self.current_command.write();
         *current = command;
+        drop(current);
+        // A key held into a hold step, or a wait step, needs timing now
+        self.wake_hold_timer();
+    }
+
+    /// Set the step that follows the current one
+    pub fn set_next_command(&self, command: Option<Arc<ComboCommand>>) {
+        *self.next_command.write() = command;
     }
 
     /// Get the current command
-    pub fn get_current_command(&self) -> Option<ComboCommand> {
+    pub fn get_current_command(&self) -> Option<Arc<ComboCommand>> {
         self.current_command.read().clone()
     }
 
-    /// Convert rdev Key to KeyIdentifier
-    fn key_to_identifier(key: &Key) -> Option<KeyIdentifier> {
-        match key {
-            Key::Num1 | Key::Kp1 => Some(KeyIdentifier::Number(1)),
-            Key::Num2 | Key::Kp2 => Some(KeyIdentifier::Number(2)),
-            Key::Num3 | Key::Kp3 => Some(KeyIdentifier::Number(3)),
-            Key::Num4 | Key::Kp4 => Some(KeyIdentifier::Number(4)),
-            Key::Num5 | Key::Kp5 => Some(KeyIdentifier::Number(5)),
-            Key::Num6 | Key::Kp6 => Some(KeyIdentifier::Number(6)),
-            Key::Num7 | Key::Kp7 => Some(KeyIdentifier::Number(7)),
-            Key::Num8 | Key::Kp8 => Some(KeyIdentifier::Number(8)),
-            Key::Num9 | Key::Kp9 => Some(KeyIdentifier::Number(9)),
-            Key::KeyE => Some(KeyIdentifier::Chain),
-            // Map Mouse Left (sentinel) to HeavyAttack (L)
-            Key::Unknown(1) => Some(KeyIdentifier::HeavyAttack),
-            _ => None,
-        }
+    /// Combo key a key press stands for
+    fn key_to_identifier(&self, key: &Key) -> Option<KeyIdentifier> {
+        self.keymap.read().identifier(key)
+    }
+
+    /// Combo key a key press stands for, preferring one of `wanted` (left
+    /// click is both `L` and `LC` by default)
+    fn key_to_identifier_in(&self, key: &Key, wanted: &[KeyIdentifier]) -> Option<KeyIdentifier> {
+        let keymap = self.keymap.read();
+        wanted
+            .iter()
+            .find(|id| keymap.triggers(key, id))
+            .cloned()
+            .or_else(|| keymap.identifier(key))
     }
 
     /// Check if the given key matches the current command
     fn matches_current_command(&self, key: &Key) -> bool {
         let current = self.current_command.read();
-        if let Some(ref cmd) = *current {
-            if let Some(key_id) = Self::key_to_identifier(key) {
-                return cmd.key == key_id;
+        match *current {
+            Some(ref cmd) => self.keymap.read().triggers(key, &cmd.key),
+            None => false,
+        }
+    }
+
+    /// Whether `key` skips the current optional step (it starts the next step instead)
+    fn skips_optional_step(&self, key: &Key) -> bool {
+        let keymap = self.keymap.read();
+        let current = self.current_command.read();
+        let next = self.next_command.read();
+        match (current.as_ref(), next.as_ref()) {
+            (Some(current), Some(next)) => {
+                current.optional
+                    && !keymap.triggers(key, &current.key)
+                    && keymap.triggers(key, &next.key)
             }
+            _ => false,
         }
-        false
+    }
+
+    /// Movement key that must be held for the current command
+    fn current_direction_key(&self) -> Option<Key> {
+        let direction = self.current_command.read().as_ref()?.direction?;
+        Some(match direction {
+            Direction::Forward => Key::KeyW,
+            Direction::Back => Key::KeyS,
+            Direction::Left => Key::KeyA,
+            Direction::Right => Key::KeyD,
+        })
+    }
+
+    /// Whether `key` is a combo key the current step has no use for
+    ///
+    /// Branch steps are picked with their own keys and never miss; on a wait
+    /// step every combo key is early.
+    fn is_miss(&self, key: &Key) -> bool {
+        if self.key_to_identifier(key).is_none() {
+            return false;
+        }
+        let current = self.current_command.read();
+        let Some(cmd) = current.as_ref() else {
+            return false;
+        };
+        let keymap = self.keymap.read();
+        match &cmd.input_type {
+            InputType::Branch { .. } => false,
+            InputType::Wait { .. } => true,
+            InputType::Chord(keys) | InputType::Sequence { keys, .. } => {
+                !keys.iter().any(|id| keymap.triggers(key, id))
+            }
+            _ => !keymap.triggers(key, &cmd.key),
+        }
+    }
+
+    /// Keys of the current command if it is a chord
+    fn current_chord(&self) -> Option<Vec<KeyIdentifier>> {
+        match *self.current_command.read() {
+            Some(ref cmd) => match &cmd.input_type {
+                InputType::Chord(keys) => Some(keys.clone()),
+                _ => None,
+            },
+            None => None,
+        }
+    }
+
+    /// Keys completing `chord` among the keys currently down
+    fn chord_keys(
+        &self,
+        chord: &[KeyIdentifier],
+        states: &HashMap<Key, KeyState>,
+    ) -> Option<Vec<Key>> {
+        let down: Vec<DownKey> = states
+            .iter()
+            .filter(|(_, state)| !state.consumed)
+            .filter_map(|(key, state)| {
+                Some(DownKey {
+                    key: *key,
+                    id: self.key_to_identifier_in(key, chord)?,
+                    press_time: state.press_time,
+                })
+            })
+            .collect();
+        let window = Duration::from_millis(self.chord_window_ms.load(Ordering::Relaxed));
+        chord::complete_chord(chord, &down, window)
+    }
+
+    /// Keys and window of the current command if it is a sequence
+    fn current_sequence(&self) -> Option<(Vec<KeyIdentifier>, Duration)> {
+        match self.current_command.read().as_ref()?.input_type {
+            InputType::Sequence {
+                ref keys,
+                window_ms,
+            } => Some((keys.clone(), Duration::from_millis(window_ms))),
+            _ => None,
+        }
+    }
+
+    /// Window of the current command if it is a double tap
+    fn current_double_tap_window(&self) -> Option<Duration> {
+        match self.current_command.read().as_ref()?.input_type {
+            InputType::DoubleTap { window_ms } => Some(Duration::from_millis(window_ms)),
+            _ => None,
+        }
+    }
+
+    /// Press count and window of the current command if it is a multi-tap
+    fn current_multi_tap(&self) -> Option<(u32, Duration)> {
+        match self.current_command.read().as_ref()?.input_type {
+            InputType::MultiTap { count, window_ms } => {
+                Some((count, Duration::from_millis(window_ms)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the current command completes when its key is let go
+    fn current_command_is_release(&self) -> bool {
+        self.current_command
+            .read()
+            .as_ref()
+            .is_some_and(|cmd| matches!(cmd.input_type, InputType::HoldRelease))
+    }
+
+    /// Whether the current command is a tap that completes on release
+    ///
+    /// Wheel notches have no separate release, so they always complete at once.
+    fn current_tap_is_release(&self) -> bool {
+        self.advance_on_release.load(Ordering::Relaxed)
+            && self
+                .current_command
+                .read()
+                .as_ref()
+                .is_some_and(|cmd| matches!(cmd.input_type, InputType::Tap) && !cmd.key.is_wheel())
     }
 
     /// Check if current command requires hold
     fn current_command_requires_hold(&self) -> bool {
         let current = self.current_command.read();
         if let Some(ref cmd) = *current {
-            matches!(cmd.input_type, InputType::Hold { .. })
+            matches!(
+                cmd.input_type,
+                InputType::Hold { .. } | InputType::ChargedHold { .. }
+            )
         } else {
             false
         }
     }
 
+    /// Stage thresholds of the current command if it is a charged hold
+    fn current_charge_stages(&self) -> Option<Vec<u64>> {
+        match *self.current_command.read() {
+            Some(ref cmd) => match cmd.input_type {
+                InputType::ChargedHold { ref stages } => Some(stages.clone()),
+                _ => None,
+            },
+            None => None,
+        }
+    }
+
+    /// Hold duration required by a step
+    ///
+    /// Holds without a duration of their own follow the configured threshold;
+    /// explicit per-step durations override it.
+    pub fn hold_target(&self, input_type: &InputType) -> Duration {
+        match input_type {
+            InputType::Hold {
+                duration_ms: Some(duration_ms),
+            } => Duration::from_millis(*duration_ms),
+            InputType::ChargedHold { stages } => {
+                Duration::from_millis(stages.last().copied().unwrap_or_default())
+            }
+            _ => self.hold_threshold(),
+        }
+    }
+
+    /// Hold duration required by the current command
+    fn current_hold_target(&self) -> Duration {
+        match *self.current_command.read() {
+            Some(ref cmd) => self.hold_target(&cmd.input_type),
+            None => self.hold_threshold(),
+        }
+    }
+
+    /// Hold progress of the current command after holding for `elapsed`
+    pub fn hold_progress(&self, elapsed: Duration) -> HoldProgressInfo {
+        let target = self.current_hold_target();
+        let progress = if target.is_zero() {
+            1.0
+        } else {
+            (elapsed.as_secs_f32() / target.as_secs_f32()).min(1.0)
+        };
+
+        HoldProgressInfo {
+            progress,
+            remaining_ms: target.saturating_sub(elapsed).as_millis() as u64,
+            target_ms: target.as_millis() as u64,
+        }
+    }
+
+    /// Progress of the current wait step (`None` if there is no running wait)
+    pub fn wait_progress(&self) -> Option<HoldProgressInfo> {
+        let started = (*self.wait_started.read())?;
+        let target = match self.current_command.read().as_ref()?.input_type {
+            InputType::Wait { duration_ms } => Duration::from_millis(duration_ms),
+            _ => return None,
+        };
+        let elapsed = started.elapsed();
+        Some(HoldProgressInfo {
+            progress: (elapsed.as_secs_f32() / target.as_secs_f32()).min(1.0),
+            remaining_ms: target.saturating_sub(elapsed).as_millis() as u64,
+            target_ms: target.as_millis() as u64,
+        })
+    }
+
+    /// Stop the timer of the current wait step; true if it was running
+    fn finish_wait(&self) -> bool {
+        self.wait_started.write().take().is_some()
+    }
+
     /// Handle key press event
     pub fn on_key_press(&self, key: Key) -> Option<KeyEvent> {
+        let now = Instant::now();
+        let ignored = !self.accepts_input();
         // Record press time
         {
             let mut states = self.key_states.write();
-            if states.contains_key(&key) {
+            if states.contains_key(&key) || self.is_bounce(key, now) {
                 return None;
             }
             states.insert(
                 key,
                 KeyState {
-                    press_time: Instant::now(),
+                    press_time: now,
                     hold_triggered: false,
-                    consumed: false,
+                    // Ignored presses never count, even once input is accepted again
+                    consumed: ignored,
+                    charge_stage: 0,
                 },
             );
         }
+        if ignored {
+            return None;
+        }
+        self.wake_hold_timer();
+
+        if let Some(recording) = self.recording.write().as_mut() {
+            if let Some(id) = self.key_to_identifier(&key) {
+                recording.press(key, id, now);
+            }
+        }
+
+        if self.skips_optional_step(&key) {
+            return Some(KeyEvent::StepSkipped(key));
+        }
+        self.evaluate_press(key)
+    }
+
+    /// Check a pressed key against the current command
+    ///
+    /// Called for new presses, and again after an optional step was skipped
+    /// so the same press can complete the step that follows.
+    pub fn evaluate_press(&self, key: Key) -> Option<KeyEvent> {
+        if !self.key_states.read().contains_key(&key) {
+            return None;
+        }
+
+        if self.strict_mode.load(Ordering::Relaxed) && self.is_miss(&key) {
+            if let Some(state) = self.key_states.write().get_mut(&key) {
+                state.consumed = true;
+            }
+            return Some(KeyEvent::Miss(key));
+        }
 
-        // For tap commands, check immediately
-        if self.matches_current_command(&key) && !self.current_command_requires_hold() {
+        // Directional steps only count while the movement key is held
+        if let Some(movement) = self.current_direction_key() {
+            let mut states = self.key_states.write();
+            if key != movement && !states.contains_key(&movement) {
+                if let Some(state) = states.get_mut(&key) {
+                    state.consumed = true;
+                }
+                return Some(KeyEvent::KeyDown(key));
+            }
+        }
+
+        // Chords complete once the last of their keys goes down
+        if let Some(chord) = self.current_chord() {
+            let mut states = self.key_states.write();
+            let Some(keys) = self.chord_keys(&chord, &states) else {
+                return Some(KeyEvent::KeyDown(key));
+            };
+            for pressed in &keys {
+                if let Some(state) = states.get_mut(pressed) {
+                    state.consumed = true;
+                }
+            }
+            return Some(KeyEvent::ChordComplete(keys));
+        }
+
+        // Sequences complete on the press of their last key in order
+        if let Some((keys, window)) = self.current_sequence() {
+            let Some(id) = self.key_to_identifier_in(&key, &keys) else {
+                return Some(KeyEvent::KeyDown(key));
+            };
+            if keys.contains(&id) {
+                if let Some(state) = self.key_states.write().get_mut(&key) {
+                    state.consumed = true;
+                }
+            }
+            if self
+                .sequence
+                .write()
+                .press(&keys, &id, Instant::now(), window)
+            {
+                return Some(KeyEvent::TapComplete(key));
+            }
+            return Some(KeyEvent::KeyDown(key));
+        }
+
+        // Double taps complete on the second press within the window; a late
+        // second press counts as a new first press
+        if let Some(window) = self.current_double_tap_window() {
+            if !self.matches_current_command(&key) {
+                return Some(KeyEvent::KeyDown(key));
+            }
+            if let Some(state) = self.key_states.write().get_mut(&key) {
+                state.consumed = true;
+            }
+            let mut first_tap = self.first_tap.write();
+            if first_tap.is_some_and(|first| first.elapsed() <= window) {
+                *first_tap = None;
+                return Some(KeyEvent::TapComplete(key));
+            }
+            *first_tap = Some(Instant::now());
+            return Some(KeyEvent::KeyDown(key));
+        }
+
+        // Multi-taps count presses; a late press starts the count over
+        if let Some((count, window)) = self.current_multi_tap() {
+            if !self.matches_current_command(&key) {
+                return Some(KeyEvent::KeyDown(key));
+            }
+            if let Some(state) = self.key_states.write().get_mut(&key) {
+                state.consumed = true;
+            }
+            let mut multi_tap = self.multi_tap.write();
+            let presses = match *multi_tap {
+                Some((presses, last)) if last.elapsed() <= window => presses + 1,
+                _ => 1,
+            };
+            if presses >= count {
+                *multi_tap = None;
+                return Some(KeyEvent::TapComplete(key));
+            }
+            *multi_tap = Some((presses, Instant::now()));
+            return Some(KeyEvent::TapProgress(key, TapCountInfo { presses, count }));
+        }
+
+        // For tap commands, check immediately (unless they wait for the release)
+        if self.matches_current_command(&key)
+            && !self.current_command_requires_hold()
+            && !self.current_command_is_release()
+            && !self.current_tap_is_release()
+        {
             // Mark as consumed so release doesn't trigger logic
             if let Some(mut states) = self.key_states.try_write() {
                 if let Some(state) = states.get_mut(&key) {
@@ -156,19 +1085,45 @@ impl InputHandler {
             let mut states = self.key_states.write();
             states.remove(&key)
         };
+        if let Some(recording) = self.recording.write().as_mut() {
+            recording.release(key, Instant::now());
+        }
+        if self.is_paused() {
+            return Some(KeyEvent::KeyUp(key));
+        }
 
         if let Some(state) = state {
+            // Release steps follow a hold start, which consumed the press
+            if self.matches_current_command(&key) && self.current_command_is_release() {
+                return Some(KeyEvent::ReleaseComplete(key));
+            }
+
             // If already consumed by tap, do nothing
             if state.consumed {
                 return Some(KeyEvent::KeyUp(key));
             }
 
+            if self.matches_current_command(&key) && self.current_tap_is_release() {
+                return Some(KeyEvent::ReleaseComplete(key));
+            }
+
+            // Charged holds complete on release at whatever stage they reached
+            if let Some(stages) = self.current_charge_stages() {
+                if self.matches_current_command(&key) {
+                    let info = charge_stage(&stages, state.press_time.elapsed());
+                    if info.stage == 0 {
+                        return Some(KeyEvent::HoldReset(key));
+                    }
+                    return Some(KeyEvent::ChargeComplete(key, info));
+                }
+            }
+
             // Only consider hold completion if it wasn't already triggered
             if !state.hold_triggered {
                 let duration = state.press_time.elapsed();
 
                 if self.matches_current_command(&key) && self.current_command_requires_hold() {
-                    if duration >= self.hold_threshold {
+                    if duration >= self.current_hold_target() {
                         return Some(KeyEvent::HoldComplete(key));
                     }
                     // Key released too early - hold not complete, reset progress
@@ -182,13 +1137,17 @@ impl InputHandler {
 
     /// Check if any pressed key has reached hold threshold
     pub fn check_hold_complete(&self) -> Option<Key> {
+        // Charged holds only complete on release
+        if self.current_charge_stages().is_some() {
+            return None;
+        }
         let mut states = self.key_states.write();
 
         for (key, state) in states.iter_mut() {
             // Check threshold only if not consumed and not triggered
             if !state.consumed
                 && !state.hold_triggered
-                && state.press_time.elapsed() >= self.hold_threshold
+                && state.press_time.elapsed() >= self.current_hold_target()
             {
                 if self.matches_current_command(key) && self.current_command_requires_hold() {
                     state.hold_triggered = true;
@@ -200,10 +1159,37 @@ impl InputHandler {
         None
     }
 
+    /// Check if the held key of a charged hold has crossed a new stage
+    pub fn check_charge_stage(&self) -> Option<(Key, ChargeStageInfo)> {
+        let stages = self.current_charge_stages()?;
+        let mut states = self.key_states.write();
+
+        for (key, state) in states.iter_mut() {
+            if state.consumed || !self.matches_current_command(key) {
+                continue;
+            }
+            let info = charge_stage(&stages, state.press_time.elapsed());
+            if info.stage > state.charge_stage {
+                state.charge_stage = info.stage;
+                return Some((*key, info));
+            }
+        }
+
+        None
+    }
+
+    /// Feed a synthetic event through the listener; false if it isn't running
+    pub fn inject(&self, event_type: EventType) -> bool {
+        self.injector
+            .read()
+            .as_ref()
+            .is_some_and(|injector| injector.send(event_type).is_ok())
+    }
+
     /// Create event channel
     pub fn create_event_channel() -> (
-        mpsc::UnboundedSender<KeyEvent>,
-        mpsc::UnboundedReceiver<KeyEvent>,
+        mpsc::UnboundedSender<TimedKeyEvent>,
+        mpsc::UnboundedReceiver<TimedKeyEvent>,
     ) {
         mpsc::unbounded_channel()
     }
@@ -224,96 +1210,742 @@ impl Default for InputHandler {
 /// # Thread Lifecycle
 /// - **Start**: The thread starts immediately when this function is called
 /// - **Stop**: The thread cannot be manually stopped due to `rdev::listen()`'s blocking nature
+/// - **Failure**: If `rdev::listen()` fails, it is restarted with backoff and the
+///   state is reported as [`KeyEvent::ListenerStatus`]
 /// - **Cleanup**: When the application exits, the OS automatically terminates this thread
 ///
 /// # Returns
-/// Returns a receiver channel that will receive `KeyEvent` notifications
-pub fn start_global_key_listener(handler: InputHandler) -> mpsc::UnboundedReceiver<KeyEvent> {
+/// Returns a receiver channel that will receive timestamped `KeyEvent` notifications
+pub fn start_global_key_listener(handler: InputHandler) -> mpsc::UnboundedReceiver<TimedKeyEvent> {
     let (tx, rx) = mpsc::unbounded_channel();
 
     std::thread::spawn(move || {
         let handler = Arc::new(handler);
         let handler_clone = handler.clone();
+        gamepad::spawn_listener(handler.clone(), tx.clone());
+
+        // Injected events take the same path as hooked ones
+        let (inject_tx, inject_rx) = std::sync::mpsc::channel();
+        *handler.injector.write() = Some(inject_tx);
+        let mut inject_callback = event_callback(handler.clone(), tx.clone());
+        std::thread::spawn(move || {
+            for event_type in inject_rx {
+                inject_callback(Event {
+                    time: SystemTime::now(),
+                    name: None,
+                    event_type,
+                });
+            }
+        });
 
-        // Spawn hold check thread
+        // Spawn hold timer thread; it only runs while a hold or wait is timed
         let tx_hold = tx.clone();
         let handler_hold = handler_clone.clone();
-        std::thread::spawn(move || loop {
-            std::thread::sleep(Duration::from_millis(50));
-
-            // Check for progress on hold keys
-            let states = handler_hold.key_states.read();
-            for (key, state) in states.iter() {
-                if !state.consumed && !state.hold_triggered {
-                    if handler_hold.matches_current_command(key)
-                        && handler_hold.current_command_requires_hold()
-                    {
-                        let elapsed = state.press_time.elapsed();
-                        let progress = (elapsed.as_millis() as f32)
-                            / (handler_hold.hold_threshold.as_millis() as f32);
-
-                        if progress >= 1.0 {
-                            // Will be handled by check_hold_complete
-                        } else {
-                            let _ = tx_hold.send(KeyEvent::HoldProgress(*key, progress.min(1.0)));
+        std::thread::spawn(move || {
+            let mut tick = None;
+            loop {
+                handler_hold.sleep_hold_timer(tick);
+                if handler_hold.is_paused() {
+                    tick = None;
+                    continue;
+                }
+
+                // Check for progress on hold keys
+                let now = Instant::now();
+                let mut remaining_ms: Option<u64> = None;
+                let states = handler_hold.key_states.read();
+                for (key, state) in states.iter() {
+                    if !state.consumed && !state.hold_triggered {
+                        if handler_hold.matches_current_command(key)
+                            && handler_hold.current_command_requires_hold()
+                        {
+                            let info = handler_hold.hold_progress(state.press_time.elapsed());
+
+                            if info.progress >= 1.0 {
+                                // Will be handled by check_hold_complete
+                            } else {
+                                let shortest = remaining_ms.unwrap_or(u64::MAX);
+                                remaining_ms = Some(shortest.min(info.remaining_ms));
+                                let _ = tx_hold.send(KeyEvent::HoldProgress(*key, info).at(now));
+                            }
                         }
                     }
                 }
-            }
-            drop(states);
+                drop(states);
+
+                if let Some(key) = handler_hold.check_hold_complete() {
+                    let _ = tx_hold.send(KeyEvent::HoldComplete(key).at(now));
+                }
+                if let Some((key, info)) = handler_hold.check_charge_stage() {
+                    let _ = tx_hold.send(KeyEvent::ChargeStage(key, info).at(now));
+                }
+
+                // Wait steps run on the same clock
+                if let Some(info) = handler_hold.wait_progress() {
+                    if info.progress < 1.0 {
+                        let shortest = remaining_ms.unwrap_or(u64::MAX);
+                        remaining_ms = Some(shortest.min(info.remaining_ms));
+                        let _ = tx_hold.send(KeyEvent::WaitProgress(info).at(now));
+                    } else if handler_hold.finish_wait() {
+                        let _ = tx_hold.send(KeyEvent::WaitComplete.at(now));
+                    }
+                }
 
-            if let Some(key) = handler_hold.check_hold_complete() {
-                let _ = tx_hold.send(KeyEvent::HoldComplete(key));
+                tick = handler_hold.progress_tick(remaining_ms);
             }
         });
 
-        // Main event callback
-        let callback = move |event: Event| match event.event_type {
+        // Keep the global hook alive: restart it with backoff whenever it fails
+        let pending = Arc::new(PendingRestart::default());
+        let mut attempt = 0;
+        let mut delay = None;
+        loop {
+            let started = Instant::now();
+            let mut callback = event_callback(handler_clone.clone(), tx.clone());
+            let hooked = pending.clone();
+            let tx_hooked = tx.clone();
+            let result = listen(move |event| {
+                if hooked.confirm() {
+                    send_listener_running(&tx_hooked);
+                }
+                callback(event);
+            });
+            pending.cancel();
+            let error = match result {
+                Ok(()) => "listener stopped".to_string(),
+                Err(e) => format!("{:?}", e),
+            };
+            eprintln!("[ERROR] Global input listener failed: {}", error);
+            let wait = retry_delay(delay, started.elapsed());
+            delay = Some(wait);
+            let status = ListenerStatus {
+                running: false,
+                error: Some(error),
+                retry_in_ms: Some(wait.as_millis() as u64),
+            };
+            let _ = tx.send(KeyEvent::ListenerStatus(status).at(Instant::now()));
+            std::thread::sleep(wait);
+
+            // Releases missed while the hook was down would leave keys stuck
+            handler_clone.key_states.write().clear();
+
+            // Only report running once the new hook is known to be up
+            attempt += 1;
+            pending.arm(attempt);
+            let grace = pending.clone();
+            let tx_grace = tx.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(LISTENER_GRACE);
+                if grace.confirm_attempt(attempt) {
+                    send_listener_running(&tx_grace);
+                }
+            });
+        }
+    });
+
+    rx
+}
+
+/// Callback of the global hook, feeding keys and mouse buttons to `handler`
+fn event_callback(
+    handler: Arc<InputHandler>,
+    tx: mpsc::UnboundedSender<TimedKeyEvent>,
+) -> impl FnMut(Event) {
+    let mut repeats = RepeatFilter::default();
+    move |event: Event| {
+        // Stamp before any matching so the time is that of the input itself
+        let at = Instant::now();
+        match event.event_type {
+            // Held keys are repeated by the OS; only the first press counts
+            EventType::KeyPress(key) if !repeats.press(key) => {
+                let _ = tx.send(KeyEvent::KeyRepeat(key).at(at));
+            }
             EventType::KeyPress(key) => {
                 // Always send KeyDown for hotkey processing
-                let _ = tx.send(KeyEvent::KeyDown(key));
+                let _ = tx.send(KeyEvent::KeyDown(key).at(at));
 
                 // Also process through handler for combo detection (if not Alt)
                 if !matches!(key, Key::Alt | Key::AltGr) {
-                    if let Some(evt) = handler_clone.on_key_press(key) {
-                        // Only send if it's a combo event (Tap/Hold complete)
-                        if matches!(evt, KeyEvent::TapComplete(_)) {
-                            let _ = tx.send(evt);
+                    if let Some(evt) = handler.on_key_press(handler.physical_key(key)) {
+                        // Only send if it's a combo event (Tap/chord complete or skip)
+                        if matches!(
+                            evt,
+                            KeyEvent::TapComplete(_)
+                                | KeyEvent::ChordComplete(_)
+                                | KeyEvent::StepSkipped(_)
+                                | KeyEvent::Miss(_)
+                                | KeyEvent::TapProgress(..)
+                        ) {
+                            let _ = tx.send(evt.at(at));
                         }
                     }
                 }
             }
             EventType::KeyRelease(key) => {
+                repeats.release(key);
                 // Always send KeyUp
-                let _ = tx.send(KeyEvent::KeyUp(key));
+                let _ = tx.send(KeyEvent::KeyUp(key).at(at));
 
                 // Also process through handler for combo detection (if not Alt)
                 if !matches!(key, Key::Alt | Key::AltGr) {
-                    if let Some(evt) = handler_clone.on_key_release(key) {
-                        // Only send if it's a combo event (HoldComplete or HoldReset)
-                        if matches!(evt, KeyEvent::HoldComplete(_) | KeyEvent::HoldReset(_)) {
-                            let _ = tx.send(evt);
+                    if let Some(evt) = handler.on_key_release(handler.physical_key(key)) {
+                        // Only send if it's a combo event (hold or release result)
+                        if matches!(
+                            evt,
+                            KeyEvent::HoldComplete(_)
+                                | KeyEvent::HoldReset(_)
+                                | KeyEvent::ReleaseComplete(_)
+                                | KeyEvent::ChargeComplete(..)
+                        ) {
+                            let _ = tx.send(evt.at(at));
                         }
                     }
                 }
             }
-            EventType::ButtonPress(rdev::Button::Left) => {
-                if let Some(evt) = handler_clone.on_key_press(Key::Unknown(1)) {
-                    let _ = tx.send(evt);
+            EventType::ButtonPress(button) => {
+                if let Some(key) = mouse_button_key(button) {
+                    if let Some(evt) = handler.on_key_press(key) {
+                        let _ = tx.send(evt.at(at));
+                    }
                 }
             }
-            EventType::ButtonRelease(rdev::Button::Left) => {
-                if let Some(evt) = handler_clone.on_key_release(Key::Unknown(1)) {
-                    let _ = tx.send(evt);
+            EventType::ButtonRelease(button) => {
+                if let Some(key) = mouse_button_key(button) {
+                    if let Some(evt) = handler.on_key_release(key) {
+                        let _ = tx.send(evt.at(at));
+                    }
                 }
             }
+            // A wheel notch is a press and release in one event
+            EventType::Wheel { delta_y, .. } if delta_y != 0 => {
+                let key = if delta_y > 0 {
+                    WHEEL_UP_KEY
+                } else {
+                    WHEEL_DOWN_KEY
+                };
+                if let Some(evt) = handler.on_key_press(key) {
+                    let _ = tx.send(evt.at(at));
+                }
+                handler.on_key_release(key);
+            }
             _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_are_stamped_at_capture() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut callback = event_callback(Arc::new(InputHandler::new()), tx);
+        let before = Instant::now();
+        callback(Event {
+            time: SystemTime::now(),
+            name: None,
+            event_type: EventType::KeyPress(Key::KeyE),
+        });
+        let after = Instant::now();
+
+        // A late reader still sees when the key went down
+        std::thread::sleep(Duration::from_millis(20));
+        let TimedKeyEvent { event, at } = rx.try_recv().unwrap();
+        assert!(matches!(event, KeyEvent::KeyDown(Key::KeyE)));
+        assert!(before <= at && at <= after);
+    }
+
+    #[test]
+    fn test_listener_retry_backs_off() {
+        let quick = Duration::from_millis(10);
+        assert_eq!(retry_delay(None, quick), LISTENER_RETRY_MIN);
+        assert_eq!(
+            retry_delay(Some(LISTENER_RETRY_MIN), quick),
+            LISTENER_RETRY_MIN * 2
+        );
+        assert_eq!(
+            retry_delay(Some(LISTENER_RETRY_MAX), quick),
+            LISTENER_RETRY_MAX
+        );
+        assert_eq!(
+            retry_delay(Some(LISTENER_RETRY_MAX), LISTENER_STABLE),
+            LISTENER_RETRY_MIN
+        );
+    }
+
+    #[test]
+    fn test_restart_confirmed_once() {
+        let pending = PendingRestart::default();
+        assert!(!pending.confirm());
+
+        // The hook delivered an event before the grace period ended
+        pending.arm(1);
+        assert!(pending.confirm());
+        assert!(!pending.confirm_attempt(1));
+
+        // A failed restart is never reported, not even by a later grace check
+        pending.arm(2);
+        pending.cancel();
+        assert!(!pending.confirm_attempt(2));
+        pending.arm(3);
+        assert!(!pending.confirm_attempt(2));
+        assert!(pending.confirm_attempt(3));
+        assert!(!pending.confirm());
+    }
+
+    #[test]
+    fn test_hold_threshold_adjustment_is_shared_and_clamped() {
+        let handler = InputHandler::new();
+        let listener_copy = handler.clone();
+
+        assert_eq!(handler.adjust_hold_threshold(HOLD_THRESHOLD_STEP_MS), 325);
+        assert_eq!(listener_copy.hold_threshold(), Duration::from_millis(325));
+        assert_eq!(handler.adjust_hold_threshold(-10_000), 50);
+        assert_eq!(handler.set_hold_threshold(60_000), 2000);
+    }
+
+    #[test]
+    fn test_hold_progress_uses_step_duration() {
+        let handler = InputHandler::new();
+        handler.set_current_command(Some(Arc::new(ComboCommand::hold(
+            KeyIdentifier::Number(2),
+            Some(1200),
+            "",
+            "",
+        ))));
+
+        let info = handler.hold_progress(Duration::from_millis(300));
+        assert_eq!(info.target_ms, 1200);
+        assert_eq!(info.remaining_ms, 900);
+        assert!((info.progress - 0.25).abs() < f32::EPSILON);
+
+        // Holds without a duration follow the configured threshold, while an
+        // explicit one equal to the old default doesn't
+        let default_hold = InputType::Hold { duration_ms: None };
+        handler.set_hold_threshold(400);
+        assert_eq!(
+            handler.hold_target(&default_hold),
+            Duration::from_millis(400)
+        );
+        let explicit_hold = InputType::Hold {
+            duration_ms: Some(300),
+        };
+        assert_eq!(
+            handler.hold_target(&explicit_hold),
+            Duration::from_millis(300)
+        );
+    }
+
+    #[test]
+    fn test_hold_completion_uses_step_duration() {
+        let handler = InputHandler::new();
+        let combo =
+            crate::combo::parse_combo_content("U2:150,ポグ,必殺技,|\nU2:1200,ポグ,必殺技,|")
+                .unwrap();
+
+        handler.set_current_command(combo.step(0).cloned());
+        handler.on_key_press(Key::Num2);
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!(handler.check_hold_complete(), Some(Key::Num2));
+        handler.on_key_release(Key::Num2);
+
+        // The same press time falls short of a longer charge
+        handler.set_current_command(combo.step(1).cloned());
+        handler.on_key_press(Key::Num2);
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!(handler.check_hold_complete(), None);
+        assert!(matches!(
+            handler.on_key_release(Key::Num2),
+            Some(KeyEvent::HoldReset(Key::Num2))
+        ));
+    }
+
+    #[test]
+    fn test_charged_hold_reports_stages() {
+        let handler = InputHandler::new();
+        let combo = crate::combo::parse_combo_content("U2:100/250,ポグ,必殺技,|").unwrap();
+        handler.set_current_command(combo.step(0).cloned());
+
+        // Letting go before the first stage resets the charge
+        handler.on_key_press(Key::Num2);
+        assert!(matches!(
+            handler.on_key_release(Key::Num2),
+            Some(KeyEvent::HoldReset(Key::Num2))
+        ));
+
+        assert!(matches!(
+            handler.on_key_press(Key::Num2),
+            Some(KeyEvent::KeyDown(_))
+        ));
+        std::thread::sleep(Duration::from_millis(150));
+        let first = ChargeStageInfo {
+            stage: 1,
+            stages: 2,
+        };
+        assert_eq!(handler.check_charge_stage(), Some((Key::Num2, first)));
+        assert_eq!(handler.check_charge_stage(), None);
+        assert_eq!(handler.check_hold_complete(), None);
+        assert!(matches!(
+            handler.on_key_release(Key::Num2),
+            Some(KeyEvent::ChargeComplete(Key::Num2, info)) if info == first
+        ));
+    }
+
+    #[test]
+    fn test_progress_tick_refines_near_completion() {
+        let handler = InputHandler::new();
+        let tick = |remaining_ms| handler.progress_tick(remaining_ms).map(|t| t.as_millis());
+        assert_eq!(tick(None), None);
+        assert_eq!(tick(Some(500)), Some(50));
+        assert_eq!(tick(Some(120)), Some(20));
+        assert_eq!(tick(Some(80)), Some(10));
+        // The last tick lands on the completion
+        assert_eq!(tick(Some(4)), Some(4));
+
+        assert_eq!(handler.set_progress_intervals(1, 100), (5, 5));
+    }
+
+    #[test]
+    fn test_chord_requires_all_keys_within_window() {
+        let handler = InputHandler::new().with_chord_window(1_000);
+        handler.set_current_command(Some(Arc::new(ComboCommand {
+            input_type: InputType::Chord(vec![KeyIdentifier::Number(1), KeyIdentifier::Chain]),
+            ..ComboCommand::tap(KeyIdentifier::Number(1), "", "")
+        })));
+
+        assert!(matches!(
+            handler.on_key_press(Key::Num1),
+            Some(KeyEvent::KeyDown(_))
+        ));
+        assert!(matches!(
+            handler.on_key_press(Key::KeyE),
+            Some(KeyEvent::ChordComplete(keys)) if keys == [Key::Num1, Key::KeyE]
+        ));
+
+        // Outside the window the chord does not complete
+        handler.on_key_release(Key::Num1);
+        handler.on_key_release(Key::KeyE);
+        handler.set_chord_window(0);
+        handler.on_key_press(Key::Num1);
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(matches!(
+            handler.on_key_press(Key::KeyE),
+            Some(KeyEvent::KeyDown(_))
+        ));
+    }
+
+    #[test]
+    fn test_heavy_attack_follows_binding() {
+        let heavy = Arc::new(ComboCommand::tap(
+            KeyIdentifier::HeavyAttack,
+            "管理人",
+            "重撃",
+        ));
+        let left_click = Arc::new(ComboCommand::tap(
+            KeyIdentifier::MouseLeft,
+            "管理人",
+            "通常攻撃",
+        ));
+
+        // By default left click is both
+        let handler = InputHandler::new();
+        for cmd in [&heavy, &left_click] {
+            handler.set_current_command(Some(cmd.clone()));
+            assert!(matches!(
+                handler.on_key_press(MOUSE_LEFT_KEY),
+                Some(KeyEvent::TapComplete(_))
+            ));
+            handler.on_key_release(MOUSE_LEFT_KEY);
+        }
+
+        let bindings = crate::config::KeyBindings {
+            heavy_attack: "F".to_string(),
+            ..Default::default()
         };
+        let handler = InputHandler::new().with_key_map(KeyMap::from_bindings(&bindings));
+        handler.set_current_command(Some(heavy));
+        assert!(matches!(
+            handler.on_key_press(MOUSE_LEFT_KEY),
+            Some(KeyEvent::KeyDown(_))
+        ));
+        assert!(matches!(
+            handler.on_key_press(Key::KeyF),
+            Some(KeyEvent::TapComplete(_))
+        ));
+        handler.set_current_command(Some(left_click));
+        handler.on_key_release(MOUSE_LEFT_KEY);
+        assert!(matches!(
+            handler.on_key_press(MOUSE_LEFT_KEY),
+            Some(KeyEvent::TapComplete(_))
+        ));
+    }
+
+    #[test]
+    fn test_direction_requires_movement_key() {
+        let handler = InputHandler::new();
+        let mut cmd = ComboCommand::tap(KeyIdentifier::HeavyAttack, "管理人", "重撃");
+        cmd.direction = Some(Direction::Forward);
+        handler.set_current_command(Some(Arc::new(cmd)));
+
+        assert!(matches!(
+            handler.on_key_press(MOUSE_LEFT_KEY),
+            Some(KeyEvent::KeyDown(_))
+        ));
+        handler.on_key_release(MOUSE_LEFT_KEY);
+
+        handler.on_key_press(Key::KeyW);
+        assert!(matches!(
+            handler.on_key_press(MOUSE_LEFT_KEY),
+            Some(KeyEvent::TapComplete(_))
+        ));
+    }
+
+    #[test]
+    fn test_release_step_completes_on_key_up() {
+        let handler = InputHandler::new();
+        let mut start = ComboCommand::tap(KeyIdentifier::Number(2), "リーフォン", "必殺技");
+        start.input_type = InputType::HoldStart;
+        let mut release = start.clone();
+        release.input_type = InputType::HoldRelease;
+
+        handler.set_current_command(Some(Arc::new(start)));
+        assert!(matches!(
+            handler.on_key_press(Key::Num2),
+            Some(KeyEvent::TapComplete(Key::Num2))
+        ));
+
+        handler.set_current_command(Some(Arc::new(release)));
+        assert!(matches!(
+            handler.on_key_release(Key::Num2),
+            Some(KeyEvent::ReleaseComplete(Key::Num2))
+        ));
+
+        // Pressing the key again does not complete the release
+        assert!(matches!(
+            handler.on_key_press(Key::Num2),
+            Some(KeyEvent::KeyDown(_))
+        ));
+    }
+
+    #[test]
+    fn test_wait_step_runs_on_its_own() {
+        let handler = InputHandler::new();
+        let combo = crate::combo::parse_combo_content("WAIT:1500,,,アニメーション待ち|").unwrap();
+        handler.set_current_command(combo.step(0).cloned());
+
+        let info = handler.wait_progress().unwrap();
+        assert_eq!(info.target_ms, 1500);
+        assert!(info.progress < 1.0);
+        // Keys do nothing on a wait step
+        assert!(matches!(
+            handler.on_key_press(Key::Num1),
+            Some(KeyEvent::KeyDown(_))
+        ));
+
+        assert!(handler.finish_wait());
+        assert!(!handler.finish_wait());
+        assert!(handler.wait_progress().is_none());
+
+        handler.set_current_command(Some(Arc::new(ComboCommand::tap(
+            KeyIdentifier::Chain,
+            "チェン",
+            "連携",
+        ))));
+        assert!(handler.wait_progress().is_none());
+    }
+
+    #[test]
+    fn test_double_tap_needs_two_quick_presses() {
+        let handler = InputHandler::new();
+        let combo = crate::combo::parse_combo_content("DD:200,管理人,回避,|").unwrap();
+        handler.set_current_command(combo.step(0).cloned());
+
+        assert!(matches!(
+            handler.on_key_press(Key::Space),
+            Some(KeyEvent::KeyDown(_))
+        ));
+        handler.on_key_release(Key::Space);
+        // Other keys in between are ignored
+        handler.on_key_press(Key::KeyE);
+        handler.on_key_release(Key::KeyE);
+        assert!(matches!(
+            handler.on_key_press(Key::Space),
+            Some(KeyEvent::TapComplete(Key::Space))
+        ));
+        handler.on_key_release(Key::Space);
 
-        if let Err(e) = listen(callback) {
-            eprintln!("Error listening to events: {:?}", e);
+        // Too slow: the second press starts over
+        handler.set_current_command(combo.step(0).cloned());
+        handler.on_key_press(Key::Space);
+        handler.on_key_release(Key::Space);
+        std::thread::sleep(Duration::from_millis(250));
+        assert!(matches!(
+            handler.on_key_press(Key::Space),
+            Some(KeyEvent::KeyDown(_))
+        ));
+    }
+
+    #[test]
+    fn test_strict_mode_reports_wrong_keys() {
+        let handler = InputHandler::new();
+        handler.set_current_command(Some(Arc::new(ComboCommand::tap(
+            KeyIdentifier::Chain,
+            "チェン",
+            "連携",
+        ))));
+        assert!(matches!(
+            handler.on_key_press(Key::Num2),
+            Some(KeyEvent::KeyDown(_))
+        ));
+        handler.on_key_release(Key::Num2);
+
+        handler.set_strict_mode(true);
+        assert!(matches!(
+            handler.on_key_press(Key::Num2),
+            Some(KeyEvent::Miss(Key::Num2))
+        ));
+        // Keys that aren't combo keys never miss
+        assert!(matches!(
+            handler.on_key_press(Key::KeyW),
+            Some(KeyEvent::KeyDown(_))
+        ));
+        assert!(matches!(
+            handler.on_key_press(Key::KeyE),
+            Some(KeyEvent::TapComplete(Key::KeyE))
+        ));
+    }
+
+    #[test]
+    fn test_paused_handler_ignores_presses() {
+        let handler = InputHandler::new();
+        handler.set_current_command(Some(Arc::new(ComboCommand::tap(
+            KeyIdentifier::Chain,
+            "チェン",
+            "連携",
+        ))));
+        handler.set_paused(true);
+        assert!(handler.on_key_press(Key::KeyE).is_none());
+
+        // Letting go of a key pressed during the pause does nothing
+        handler.set_paused(false);
+        assert!(matches!(
+            handler.on_key_release(Key::KeyE),
+            Some(KeyEvent::KeyUp(Key::KeyE))
+        ));
+        assert!(matches!(
+            handler.on_key_press(Key::KeyE),
+            Some(KeyEvent::TapComplete(Key::KeyE))
+        ));
+    }
+
+    #[test]
+    fn test_presses_need_game_in_front_when_required() {
+        let handler = InputHandler::new().with_require_game_focus(true);
+        handler.set_current_command(Some(Arc::new(ComboCommand::tap(
+            KeyIdentifier::Chain,
+            "チェン",
+            "連携",
+        ))));
+        handler.set_game_foreground(false);
+        assert!(handler.on_key_press(Key::KeyE).is_none());
+        handler.on_key_release(Key::KeyE);
+
+        handler.set_game_foreground(true);
+        assert!(matches!(
+            handler.on_key_press(Key::KeyE),
+            Some(KeyEvent::TapComplete(Key::KeyE))
+        ));
+    }
+
+    #[test]
+    fn test_multi_tap_counts_presses() {
+        let handler = InputHandler::new();
+        let mut step = ComboCommand::tap(KeyIdentifier::Number(3), "ポグ", "戦技");
+        step.input_type = InputType::MultiTap {
+            count: 3,
+            window_ms: 1_000,
+        };
+        handler.set_current_command(Some(Arc::new(step)));
+
+        for presses in 1..=2 {
+            let event = handler.on_key_press(Key::Num3);
+            handler.on_key_release(Key::Num3);
+            let Some(KeyEvent::TapProgress(_, info)) = event else {
+                panic!("expected tap progress, got {:?}", event);
+            };
+            assert_eq!(info, TapCountInfo { presses, count: 3 });
         }
-    });
+        assert!(matches!(
+            handler.on_key_press(Key::Num3),
+            Some(KeyEvent::TapComplete(_))
+        ));
+    }
 
-    rx
+    #[test]
+    fn test_tap_completes_on_release() {
+        let handler = InputHandler::new().with_advance_on_release(true);
+        handler.set_current_command(Some(Arc::new(ComboCommand::tap(
+            KeyIdentifier::Number(2),
+            "ポグ",
+            "戦技",
+        ))));
+        assert!(matches!(
+            handler.on_key_press(Key::Num2),
+            Some(KeyEvent::KeyDown(Key::Num2))
+        ));
+        assert!(matches!(
+            handler.on_key_release(Key::Num2),
+            Some(KeyEvent::ReleaseComplete(Key::Num2))
+        ));
+    }
+
+    #[test]
+    fn test_debounce_ignores_quick_second_press() {
+        let handler = InputHandler::new().with_debounce(1_000);
+        handler.set_current_command(Some(Arc::new(ComboCommand::tap(
+            KeyIdentifier::Chain,
+            "チェン",
+            "連携",
+        ))));
+        assert!(matches!(
+            handler.on_key_press(Key::KeyE),
+            Some(KeyEvent::TapComplete(Key::KeyE))
+        ));
+        handler.on_key_release(Key::KeyE);
+
+        // The bounce neither counts nor leaves the key stuck down
+        assert!(handler.on_key_press(Key::KeyE).is_none());
+        handler.on_key_release(Key::KeyE);
+        handler.set_debounce(0);
+        assert!(matches!(
+            handler.on_key_press(Key::KeyE),
+            Some(KeyEvent::TapComplete(Key::KeyE))
+        ));
+    }
+
+    #[test]
+    fn test_next_step_key_skips_optional_step() {
+        let handler = InputHandler::new();
+        let mut filler = ComboCommand::tap(KeyIdentifier::HeavyAttack, "管理人", "重撃");
+        filler.optional = true;
+        let next = Arc::new(ComboCommand::tap(KeyIdentifier::Chain, "チェン", "連携"));
+        handler.set_current_command(Some(Arc::new(filler)));
+        handler.set_next_command(Some(next.clone()));
+
+        assert!(matches!(
+            handler.on_key_press(Key::KeyE),
+            Some(KeyEvent::StepSkipped(Key::KeyE))
+        ));
+
+        // Once the next step is current, the same press completes it
+        handler.set_current_command(Some(next));
+        handler.set_next_command(None);
+        assert!(matches!(
+            handler.evaluate_press(Key::KeyE),
+            Some(KeyEvent::TapComplete(Key::KeyE))
+        ));
+        handler.on_key_release(Key::KeyE);
+        assert!(handler.evaluate_press(Key::KeyE).is_none());
+    }
 }
@@ -0,0 +1,144 @@
+//! Combo recording
+//!
+//! While recording, every combo key press is captured with its timing and
+//! turned into a draft combo: presses held past the hold threshold become
+//! hold steps, and the time between presses becomes the step's gap. Operator
+//! names are left for the author to fill in.
+
+use crate::combo::{ComboCommand, ComboFile, KeyIdentifier};
+use rdev::Key;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Recorded durations and gaps are rounded to this many milliseconds
+const ROUND_MS: u64 = 50;
+
+/// One recorded key press
+#[derive(Debug, Clone)]
+struct RecordedPress {
+    key: Key,
+    id: KeyIdentifier,
+    pressed_at: Instant,
+    /// How long the key was held (`None` while it is still down)
+    held: Option<Duration>,
+}
+
+/// Key presses captured since recording started
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    presses: Vec<RecordedPress>,
+}
+
+/// Round to the nearest multiple of [`ROUND_MS`]
+fn round_ms(duration: Duration) -> u64 {
+    let ms = duration.as_millis() as u64;
+    (ms + ROUND_MS / 2) / ROUND_MS * ROUND_MS
+}
+
+/// Skill type a recorded input most likely is
+fn skill_type(id: &KeyIdentifier, hold: bool) -> &'static str {
+    match id {
+        KeyIdentifier::Number(_) if hold => "必殺技",
+        KeyIdentifier::Number(_) => "戦技",
+        KeyIdentifier::Chain => "連携",
+        KeyIdentifier::HeavyAttack | KeyIdentifier::MouseLeft => "重撃",
+        KeyIdentifier::Dodge => "回避",
+        KeyIdentifier::Swap => "交代",
+        _ => "",
+    }
+}
+
+impl Recording {
+    /// Record a press of combo key `id`
+    pub fn press(&mut self, key: Key, id: KeyIdentifier, at: Instant) {
+        self.presses.push(RecordedPress {
+            key,
+            id,
+            pressed_at: at,
+            held: None,
+        });
+    }
+
+    /// Record the release of `key`
+    pub fn release(&mut self, key: Key, at: Instant) {
+        if let Some(press) = self
+            .presses
+            .iter_mut()
+            .rev()
+            .find(|press| press.key == key && press.held.is_none())
+        {
+            press.held = Some(at.saturating_duration_since(press.pressed_at));
+        }
+    }
+
+    /// Number of presses recorded so far
+    pub fn len(&self) -> usize {
+        self.presses.len()
+    }
+
+    /// Whether nothing was recorded
+    pub fn is_empty(&self) -> bool {
+        self.presses.is_empty()
+    }
+
+    /// Draft combo of the recorded presses
+    ///
+    /// Presses held at least `hold_threshold` become hold steps with their
+    /// measured duration; keys still down count as taps.
+    pub fn into_combo(self, title: &str, hold_threshold: Duration) -> ComboFile {
+        let mut combo = ComboFile::new(title);
+        let mut previous: Option<Instant> = None;
+        for press in self.presses {
+            let hold = press.held.filter(|held| *held >= hold_threshold);
+            let skill = skill_type(&press.id, hold.is_some());
+            let mut step = match hold {
+                Some(held) => ComboCommand::hold(press.id, Some(round_ms(held)), "", skill),
+                None => ComboCommand::tap(press.id, "", skill),
+            };
+            step.gap_ms =
+                previous.map(|at| round_ms(press.pressed_at.saturating_duration_since(at)));
+            previous = Some(press.pressed_at);
+            combo.commands.push(Arc::new(step));
+        }
+        combo
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combo::{serialize_combo_file, InputType};
+
+    #[test]
+    fn test_recording_becomes_combo() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut recording = Recording::default();
+        recording.press(Key::Num2, KeyIdentifier::Number(2), at(0));
+        recording.release(Key::Num2, at(80));
+        recording.press(Key::Num2, KeyIdentifier::Number(2), at(620));
+        recording.press(Key::KeyE, KeyIdentifier::Chain, at(1_010));
+        recording.release(Key::Num2, at(1_430));
+        assert_eq!(recording.len(), 3);
+
+        let combo = recording.into_combo("録画", Duration::from_millis(300));
+        assert_eq!(combo.title, "録画");
+        let steps: Vec<_> = combo.steps().collect();
+        assert_eq!(steps[0].input_type, InputType::Tap);
+        assert_eq!(steps[0].skill_type, "戦技");
+        assert_eq!(steps[0].gap_ms, None);
+        assert_eq!(
+            steps[1].input_type,
+            InputType::Hold {
+                duration_ms: Some(800)
+            }
+        );
+        assert_eq!(steps[1].skill_type, "必殺技");
+        assert_eq!(steps[1].gap_ms, Some(600));
+        assert_eq!(steps[2].key, KeyIdentifier::Chain);
+        assert_eq!(steps[2].gap_ms, Some(400));
+
+        let output = serialize_combo_file(&combo);
+        assert!(output.contains("\nU2:800,,必殺技,,600|"));
+    }
+}
@@ -0,0 +1,42 @@
+//! Auto-repeat filtering
+//!
+//! While a key is held, the OS keeps sending presses for it. Only the first
+//! press of a key counts; the rest are reported as repeats until the key is
+//! released.
+
+use rdev::Key;
+use std::collections::HashSet;
+
+/// Keys currently held, as seen by the listener
+#[derive(Debug, Clone, Default)]
+pub struct RepeatFilter {
+    down: HashSet<Key>,
+}
+
+impl RepeatFilter {
+    /// Record a press; false if it is an auto-repeat of a held key
+    pub fn press(&mut self, key: Key) -> bool {
+        self.down.insert(key)
+    }
+
+    /// Record a release
+    pub fn release(&mut self, key: Key) {
+        self.down.remove(&key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeats_until_release() {
+        let mut filter = RepeatFilter::default();
+        assert!(filter.press(Key::F5));
+        assert!(!filter.press(Key::F5));
+        assert!(filter.press(Key::Num2));
+
+        filter.release(Key::F5);
+        assert!(filter.press(Key::F5));
+    }
+}
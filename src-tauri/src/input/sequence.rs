@@ -0,0 +1,74 @@
+//! In-step sequence detection
+//!
+//! A sequence step (`2>E`) completes once its keys are pressed in order, each
+//! within the window of the press before it. A wrong combo key or a late press
+//! starts the sequence over.
+
+use crate::combo::KeyIdentifier;
+use std::time::{Duration, Instant};
+
+/// Progress through the current sequence step
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SequenceProgress {
+    /// Number of keys already pressed in order
+    matched: usize,
+    /// When the last of them was pressed
+    last_press: Option<Instant>,
+}
+
+impl SequenceProgress {
+    /// Feed a press of `id`; true once it completes the sequence
+    pub fn press(
+        &mut self,
+        keys: &[KeyIdentifier],
+        id: &KeyIdentifier,
+        now: Instant,
+        window: Duration,
+    ) -> bool {
+        let in_time = self
+            .last_press
+            .is_some_and(|last| now.duration_since(last) <= window);
+        if !in_time {
+            self.matched = 0;
+        }
+
+        if keys.get(self.matched) == Some(id) {
+            self.matched += 1;
+        } else {
+            // A press of the first key still starts a new attempt
+            self.matched = usize::from(keys.first() == Some(id));
+        }
+
+        if self.matched == keys.len() {
+            *self = Self::default();
+            return true;
+        }
+        self.last_press = (self.matched > 0).then_some(now);
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sequence_in_order_within_window() {
+        let keys = [KeyIdentifier::Number(2), KeyIdentifier::Chain];
+        let window = Duration::from_millis(500);
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut progress = SequenceProgress::default();
+
+        // Wrong order
+        assert!(!progress.press(&keys, &KeyIdentifier::Chain, at(0), window));
+        assert!(!progress.press(&keys, &KeyIdentifier::Number(2), at(100), window));
+        assert!(progress.press(&keys, &KeyIdentifier::Chain, at(400), window));
+
+        // Too slow, then a fresh attempt
+        assert!(!progress.press(&keys, &KeyIdentifier::Number(2), at(1_000), window));
+        assert!(!progress.press(&keys, &KeyIdentifier::Chain, at(1_600), window));
+        assert!(!progress.press(&keys, &KeyIdentifier::Number(2), at(1_700), window));
+        assert!(progress.press(&keys, &KeyIdentifier::Chain, at(1_800), window));
+    }
+}
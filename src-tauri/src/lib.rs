@@ -2,217 +2,1770 @@
 
 pub mod combo;
 pub mod config;
+pub mod editor;
 pub mod input;
+pub mod library;
+pub mod navigation;
+pub mod playlist;
 pub mod process;
+pub mod session;
+pub mod watcher;
+pub mod window_tracker;
 
 use combo::ComboFile;
 use config::Config;
-use input::{InputHandler, KeyEvent};
+use editor::{DocumentSnapshot, EditError, EditorDocument};
+use input::gamepad::GamepadMapping;
+use input::keymap::{key_name, KeyMap};
+use input::{ChargeStageInfo, InputHandler, KeyEvent, TimedKeyEvent, WHEEL_DOWN_KEY, WHEEL_UP_KEY};
+use library::index::LibraryIndex;
+use library::search::{SearchFilters, SearchResult};
+use navigation::{Navigator, Step, UpdateSink};
+use playlist::{Playlist, PlaylistStatus};
+use process::focus::{self, FocusMonitor};
+use process::launch::{self, LaunchTarget};
 use process::ProcessMonitor;
 use rdev::Key;
+use session::metrics::{InputLatency, InputMetrics};
+use session::timing::{self, TimingResult};
+use session::{SessionStats, SessionTracker};
+use window_tracker::{DisplayMode, WindowRect};
 
-use parking_lot::RwLock;
+use notify::RecommendedWatcher;
+use parking_lot::{Mutex, RwLock};
 use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tauri::{Emitter, Manager, State};
 
 pub struct AppState {
     pub combo_file: RwLock<Option<ComboFile>>,
-    pub current_index: RwLock<usize>,
+    pub combo_path: RwLock<Option<String>>,
+    /// Selected combo when the loaded file holds several `##` combos
+    pub combo_name: RwLock<Option<String>>,
+    /// Playlist being played, if any
+    pub playlist: RwLock<Option<Playlist>>,
+    /// Watches the loaded combo file for hot reload (a `Mutex` because
+    /// watchers are not `Sync` on every platform)
+    pub combo_watcher: Mutex<Option<RecommendedWatcher>>,
+    /// Current step of the loaded combo
+    pub navigator: RwLock<Navigator>,
     pub config: RwLock<Config>,
     pub process_monitor: RwLock<ProcessMonitor>,
     pub input_handler: InputHandler,
     pub overlay_visible: RwLock<bool>,
+    pub editor: RwLock<Option<EditorDocument>>,
+    /// Cached index of the combo directory
+    pub library_index: RwLock<Option<LibraryIndex>>,
+    /// Known operators and skill types for checking combo files
+    pub roster: combo::Roster,
+    pub session: RwLock<SessionTracker>,
+    /// Whether the game window is in the foreground (updated by the monitor thread)
+    pub game_focused: RwLock<bool>,
+    /// Do-not-disturb mode: suppresses non-essential events
+    pub dnd: RwLock<bool>,
+    /// Whether the next key press is captured for a key binding (and does
+    /// nothing else)
+    pub key_capture: RwLock<bool>,
+    /// Game window the overlay was last anchored to
+    pub game_window: RwLock<Option<WindowRect>>,
+    /// How the game was last seen displayed
+    pub display_mode: RwLock<Option<DisplayMode>>,
+    /// When the game was last started from the app; the overlay comes up
+    /// if its process appears soon after
+    pub launch_pending: RwLock<Option<Instant>>,
+}
+
+/// Key label shown in the overlay
+fn key_display_label(key: &combo::KeyIdentifier) -> String {
+    match key {
+        combo::KeyIdentifier::Number(n) => n.to_string(),
+        combo::KeyIdentifier::Chain => "E".to_string(),
+        combo::KeyIdentifier::HeavyAttack => "L".to_string(),
+        combo::KeyIdentifier::MouseLeft => "LC".to_string(),
+        combo::KeyIdentifier::Dodge => "D".to_string(),
+        combo::KeyIdentifier::Swap => "Q".to_string(),
+        combo::KeyIdentifier::MouseRight => "RC".to_string(),
+        combo::KeyIdentifier::MouseMiddle => "MC".to_string(),
+        combo::KeyIdentifier::MouseX1 => "X1".to_string(),
+        combo::KeyIdentifier::MouseX2 => "X2".to_string(),
+        combo::KeyIdentifier::Wheel(combo::WheelDirection::Up) => "WU".to_string(),
+        combo::KeyIdentifier::Wheel(combo::WheelDirection::Down) => "WD".to_string(),
+    }
+}
+
+/// Read the combo called `name` (or the first one) from a combo file
+fn read_combo(path: &str, name: Option<&str>) -> Result<(ComboFile, Option<String>), String> {
+    let library = combo::parse_combo_library_file(path).map_err(|e| e.to_string())?;
+    let named = library
+        .take(name)
+        .ok_or_else(|| format!("Combo not found: {}", name.unwrap_or_default()))?;
+    Ok((named.combo, named.name))
+}
+
+/// Read a single-combo file, skipping invalid lines
+///
+/// YAML files are parsed strictly. An include that fails to load is reported
+/// and left unresolved.
+fn read_combo_lenient(path: &str) -> Result<(ComboFile, Vec<combo::ParseWarning>), String> {
+    if combo::ComboFormat::from_path(path) == combo::ComboFormat::Yaml {
+        return read_combo(path, None).map(|(combo, _)| (combo, Vec::new()));
+    }
+    let content = combo::encoding::read_text(path).map_err(|e| e.to_string())?;
+    let (mut combo, mut errors) =
+        combo::parse_combo_content_lenient(&content).map_err(|e| e.to_string())?;
+    if let Err(e) = combo::include::resolve_includes(&mut combo, Path::new(path)) {
+        errors.push(e);
+    }
+    Ok((combo, errors.iter().map(Into::into).collect()))
 }
 
 impl AppState {
     pub fn new() -> Self {
+        let config = Config::load_or_default();
+        let session = SessionTracker::new(config.session.idle_timeout_secs);
+        let input_handler = InputHandler::new()
+            .with_hold_threshold(config.input.hold_threshold_ms)
+            .with_progress_intervals(
+                config.input.progress_interval_ms,
+                config.input.progress_fine_interval_ms,
+            )
+            .with_chord_window(config.input.chord_window_ms)
+            .with_debounce(config.input.debounce_ms)
+            .with_strict_mode(config.input.strict_mode)
+            .with_advance_on_release(config.input.advance_on_release)
+            .with_require_game_focus(config.input.require_game_focus)
+            .with_keyboard_layout(config.input.keyboard_layout)
+            .with_key_map(KeyMap::from_bindings(&config.key_bindings))
+            .with_gamepad_mapping(GamepadMapping::from_bindings(&config.gamepad));
+        let process_monitor = ProcessMonitor::new();
+        process_monitor.set_polling(config.polling.process_interval_ms, config.polling.low_power);
+
         Self {
             combo_file: RwLock::new(None),
-            current_index: RwLock::new(0),
-            config: RwLock::new(Config::load_or_default()),
-            process_monitor: RwLock::new(ProcessMonitor::new()),
-            input_handler: InputHandler::new(),
+            combo_path: RwLock::new(None),
+            combo_name: RwLock::new(None),
+            playlist: RwLock::new(None),
+            combo_watcher: Mutex::new(None),
+            navigator: RwLock::new(Navigator::default()),
+            config: RwLock::new(config),
+            process_monitor: RwLock::new(process_monitor),
+            input_handler,
             overlay_visible: RwLock::new(true),
+            editor: RwLock::new(None),
+            library_index: RwLock::new(None),
+            roster: combo::Roster::load_or_builtin(
+                Config::default_path().with_file_name("roster.json"),
+            ),
+            session: RwLock::new(session),
+            game_focused: RwLock::new(false),
+            dnd: RwLock::new(false),
+            key_capture: RwLock::new(false),
+            game_window: RwLock::new(None),
+            display_mode: RwLock::new(None),
+            launch_pending: RwLock::new(None),
+        }
+    }
+
+    pub fn sync_input_handler(&self) {
+        let combo = self.combo_file.read();
+        let navigator = self.navigator.read();
+
+        let command = combo
+            .as_ref()
+            .and_then(|file| file.step(navigator.index()).cloned());
+        let next = combo
+            .as_ref()
+            .and_then(|file| file.step(navigator.peek_next(file)).cloned());
+        drop(navigator);
+
+        self.input_handler.set_current_command(command);
+        self.input_handler.set_next_command(next);
+    }
+
+    fn get_current_command_internal(&self) -> Option<CurrentCommandInfo> {
+        let combo = self.combo_file.read();
+        let navigator = self.navigator.read();
+        let index = navigator.index();
+
+        if let Some(ref file) = *combo {
+            if let Some(cmd) = file.step(index) {
+                let key_display = match &cmd.input_type {
+                    combo::InputType::Branch { .. } => "?".to_string(),
+                    combo::InputType::Wait { .. } => "WAIT".to_string(),
+                    combo::InputType::Chord(keys) => keys
+                        .iter()
+                        .map(key_display_label)
+                        .collect::<Vec<_>>()
+                        .join("+"),
+                    combo::InputType::Sequence { keys, .. } => keys
+                        .iter()
+                        .map(key_display_label)
+                        .collect::<Vec<_>>()
+                        .join("→"),
+                    combo::InputType::Hold { .. } | combo::InputType::ChargedHold { .. } => {
+                        match cmd.key {
+                            combo::KeyIdentifier::Number(n) => format!("Hold {}", n),
+                            ref key => key_display_label(key),
+                        }
+                    }
+                    combo::InputType::DoubleTap { .. } => {
+                        format!("{}×2", key_display_label(&cmd.key))
+                    }
+                    combo::InputType::MultiTap { count, .. } => {
+                        format!("{}×{}", key_display_label(&cmd.key), count)
+                    }
+                    combo::InputType::HoldStart => format!("+{}", key_display_label(&cmd.key)),
+                    combo::InputType::HoldRelease => format!("-{}", key_display_label(&cmd.key)),
+                    _ => key_display_label(&cmd.key),
+                };
+                let key_display = match cmd.direction {
+                    Some(direction) => format!("{}+{}", direction.label(), key_display),
+                    None => key_display,
+                };
+
+                let language = self.config.read().language.code();
+                return Some(CurrentCommandInfo {
+                    index,
+                    total: file.step_count(),
+                    title: file.title.clone(),
+                    key_display,
+                    character: cmd.character.clone(),
+                    skill_type: cmd.skill_type.clone(),
+                    memo: combo::locale::localize(&cmd.memo, language).to_string(),
+                    notes: file
+                        .notes_before(index)
+                        .into_iter()
+                        .map(|note| combo::locale::localize(note, language).to_string())
+                        .collect(),
+                    is_hold: matches!(
+                        cmd.input_type,
+                        combo::InputType::Hold { .. } | combo::InputType::ChargedHold { .. }
+                    ),
+                    direction: cmd.direction,
+                    gap_ms: cmd.gap_ms,
+                    optional: cmd.optional,
+                    group: file.step_group(index),
+                    tags: cmd.tags.clone(),
+                    icon: cmd.icon.clone(),
+                    gamepad_glyph: self.input_handler.gamepad_glyph(&cmd.key),
+                    combo_tags: file.metadata.tags.clone(),
+                    branch_options: match &cmd.input_type {
+                        combo::InputType::Branch { options } => options.clone(),
+                        _ => Vec::new(),
+                    },
+                    loop_remaining: navigator.loop_remaining(file),
+                    phase: file.phase_at(index).map(|p| file.phases()[p].name.clone()),
+                    dnd: *self.dnd.read(),
+                    timing: None,
+                    charge: None,
+                    input_delay_ms: None,
+                });
+            }
+        }
+
+        None
+    }
+
+    /// Emit an event that is suppressed in do-not-disturb mode
+    fn emit_optional<S: Serialize + Clone>(
+        &self,
+        app_handle: &tauri::AppHandle,
+        event: &str,
+        payload: S,
+    ) {
+        if !*self.dnd.read() {
+            let _ = app_handle.emit(event, payload);
+        }
+    }
+
+    /// Switch do-not-disturb mode and notify the frontend
+    fn set_dnd_internal(&self, app_handle: &tauri::AppHandle, enabled: bool) {
+        *self.dnd.write() = enabled;
+        let _ = app_handle.emit("dnd-changed", enabled);
+        if let Some(cmd) = self.get_current_command_internal() {
+            app_handle.send_update(&cmd);
+        }
+    }
+
+    /// Pause or resume combo tracking and notify the frontend
+    fn set_tracking_paused_internal(&self, app_handle: &tauri::AppHandle, paused: bool) {
+        self.input_handler.set_paused(paused);
+        let _ = app_handle.emit("tracking-paused", paused);
+    }
+
+    /// Nudge the hold threshold, persist it and notify the frontend
+    fn adjust_hold_threshold_internal(&self, app_handle: &tauri::AppHandle, delta_ms: i64) -> u64 {
+        let threshold_ms = self.input_handler.adjust_hold_threshold(delta_ms);
+        {
+            let mut config = self.config.write();
+            config.input.hold_threshold_ms = threshold_ms;
+            let _ = config.save(Config::default_path());
+        }
+        let _ = app_handle.emit("hold-threshold-changed", threshold_ms);
+        threshold_ms
+    }
+
+    /// Apply a change to the open editor document and emit `document-changed`
+    fn edit_document<F>(
+        &self,
+        app_handle: &tauri::AppHandle,
+        f: F,
+    ) -> Result<DocumentSnapshot, String>
+    where
+        F: FnOnce(&mut EditorDocument) -> Result<(), EditError>,
+    {
+        let mut editor = self.editor.write();
+        let doc = editor
+            .as_mut()
+            .ok_or_else(|| EditError::NoDocument.to_string())?;
+        f(doc).map_err(|e| e.to_string())?;
+
+        let snapshot = doc.snapshot();
+        let _ = app_handle.emit("document-changed", &snapshot);
+        Ok(snapshot)
+    }
+
+    /// Refuse to save a combo with validation errors (warnings are fine)
+    fn check_savable(&self, combo: &ComboFile) -> Result<(), String> {
+        let errors =
+            combo::save_errors(combo, self.input_handler.hold_threshold_ms(), &self.roster);
+        if errors.is_empty() {
+            return Ok(());
+        }
+        Err(errors
+            .iter()
+            .map(|d| format!("Line {}: {} ({})", d.line, d.message, d.code))
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+
+    /// Undo (or redo) the last edit of the open document; `document-changed`
+    /// is only emitted when there was one
+    fn step_edit_history(
+        &self,
+        app_handle: &tauri::AppHandle,
+        redo: bool,
+    ) -> Result<DocumentSnapshot, String> {
+        let mut editor = self.editor.write();
+        let doc = editor
+            .as_mut()
+            .ok_or_else(|| EditError::NoDocument.to_string())?;
+        let changed = if redo { doc.redo() } else { doc.undo() };
+
+        let snapshot = doc.snapshot();
+        if changed {
+            let _ = app_handle.emit("document-changed", &snapshot);
+        }
+        Ok(snapshot)
+    }
+
+    /// Update stored references after a combo file was moved or deleted
+    ///
+    /// `new_path` is `None` when the file was deleted.
+    fn relocate_combo_references(
+        &self,
+        app_handle: &tauri::AppHandle,
+        old_path: &str,
+        new_path: Option<&str>,
+    ) {
+        let old = Path::new(old_path);
+
+        {
+            let mut config = self.config.write();
+            if config.last_combo_file.as_deref().map(Path::new) == Some(old) {
+                config.last_combo_file = new_path.map(str::to_string);
+                let _ = config.save(Config::default_path());
+            }
+        }
+
+        if let Some(doc) = self.editor.write().as_mut() {
+            if doc.path.as_deref().map(Path::new) == Some(old) {
+                doc.path = new_path.map(str::to_string);
+            }
+        }
+
+        if let (Some(playlist), Some(new_path)) = (self.playlist.write().as_mut(), new_path) {
+            playlist.relocate(old, new_path);
+        }
+
+        // A deleted file stays loaded in memory; only reloading becomes impossible
+        {
+            let mut combo_path = self.combo_path.write();
+            if combo_path.as_deref().map(Path::new) != Some(old) {
+                return;
+            }
+            *combo_path = new_path.map(str::to_string);
+        }
+        // Hot reload follows the file to its new name
+        if new_path.is_some() {
+            self.watch_loaded_combo(app_handle);
+        } else {
+            *self.combo_watcher.lock() = None;
+        }
+    }
+
+    /// Load a combo file (picking `name` from multi-combo files) and start at its first step
+    ///
+    /// Files in an older format version are rewritten in the current one and
+    /// the file is watched for hot reload.
+    fn load_combo_internal(
+        &self,
+        app_handle: &tauri::AppHandle,
+        path: &str,
+        name: Option<&str>,
+    ) -> Result<String, String> {
+        let (combo, name) = read_combo(path, name)?;
+        Ok(self.install_combo(app_handle, path, combo, name))
+    }
+
+    /// Make `combo`, read from `path`, the active combo and watch its file
+    fn install_combo(
+        &self,
+        app_handle: &tauri::AppHandle,
+        path: &str,
+        combo: ComboFile,
+        name: Option<String>,
+    ) -> String {
+        let title = combo.title.clone();
+        self.report_unknown_names(app_handle, &combo);
+
+        let retention = self.config.read().backups.retention;
+        match library::upgrade_combo_file(path, retention) {
+            Ok(Some(report)) => {
+                let _ = app_handle.emit("combo-migrated", report);
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("[ERROR] Failed to upgrade combo file: {}", e),
+        }
+
+        *self.combo_file.write() = Some(combo);
+        *self.combo_path.write() = Some(path.to_string());
+        *self.combo_name.write() = name;
+        self.navigate(Step::First);
+
+        {
+            let mut config = self.config.write();
+            config.last_combo_file = Some(path.to_string());
+            let _ = config.save(Config::default_path());
+        }
+
+        self.watch_loaded_combo(app_handle);
+        title
+    }
+
+    /// Tell the UI about operator names and skill types missing from the roster
+    fn report_unknown_names(&self, app_handle: &tauri::AppHandle, combo: &ComboFile) {
+        let diagnostics = combo::check_roster(combo, &self.roster);
+        if !diagnostics.is_empty() {
+            let _ = app_handle.emit("combo-unknown-names", &diagnostics);
+        }
+    }
+
+    /// Load a combo from pasted text; it has no file to save or watch
+    fn load_combo_text_internal(&self, content: &str) -> Result<String, String> {
+        let combo = combo::parse_combo_content(content).map_err(|e| e.to_string())?;
+        let title = combo.title.clone();
+
+        *self.combo_watcher.lock() = None;
+        *self.combo_file.write() = Some(combo);
+        *self.combo_path.write() = None;
+        *self.combo_name.write() = None;
+        *self.playlist.write() = None;
+        self.navigate(Step::First);
+        Ok(title)
+    }
+
+    /// Index of the combo directory
+    ///
+    /// Scanned on first use, when the directory setting changes, or when
+    /// `refresh` is set (reusing unchanged entries).
+    fn library_index_internal(&self, refresh: bool) -> Result<LibraryIndex, String> {
+        let dir = self.config.read().combo_directory();
+        let mut cached = self.library_index.write();
+        if let Some(index) = cached.as_ref() {
+            if !refresh && Path::new(&index.dir) == dir {
+                return Ok(index.clone());
+            }
+        }
+
+        // On the first scan, unchanged files come from the on-disk cache
+        let stored = match cached.as_ref() {
+            Some(_) => None,
+            None => library::cache::load_index(&dir),
+        };
+        let previous = cached.as_ref().or(stored.as_ref());
+        let index = library::index::scan_library(&dir, previous).map_err(|e| e.to_string())?;
+        if let Err(e) = library::cache::save_index(&dir, &index) {
+            eprintln!("[ERROR] Failed to save library cache: {}", e);
+        }
+        *cached = Some(index.clone());
+        Ok(index)
+    }
+
+    /// Reload the loaded combo whenever its file changes on disk
+    fn watch_loaded_combo(&self, app_handle: &tauri::AppHandle) {
+        let Some(path) = self.combo_path.read().clone() else {
+            return;
+        };
+
+        let app_handle = app_handle.clone();
+        let watcher = watcher::watch_file(Path::new(&path), move || {
+            let state = app_handle.state::<AppState>();
+            match state.reload_combo_internal() {
+                Ok(Some(cmd)) => {
+                    app_handle.send_update(&cmd);
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("[ERROR] Failed to hot reload combo file: {}", e),
+            }
+        });
+
+        // Replacing the old watcher stops watching the previous file
+        let mut combo_watcher = self.combo_watcher.lock();
+        match watcher {
+            Ok(watcher) => *combo_watcher = Some(watcher),
+            Err(e) => {
+                *combo_watcher = None;
+                eprintln!("[ERROR] Failed to watch combo file: {}", e);
+            }
+        }
+    }
+
+    /// Re-parse the loaded combo file, keeping the current index when possible
+    ///
+    /// On parse errors the currently loaded combo is left untouched.
+    fn reload_combo_internal(&self) -> Result<Option<CurrentCommandInfo>, String> {
+        let path = self
+            .combo_path
+            .read()
+            .clone()
+            .ok_or_else(|| "No combo file loaded".to_string())?;
+        let name = self.combo_name.read().clone();
+        let (combo, _) = read_combo(&path, name.as_deref())?;
+
+        *self.combo_file.write() = Some(combo);
+        // Stay on the same step, with the loop passes of the old file forgotten
+        let index = self.navigator.read().index();
+        self.navigator.write().restart();
+        self.move_to(Step::To(index));
+
+        Ok(self.get_current_command_internal())
+    }
+
+    /// Follow an option of the current branch step
+    fn choose_branch_internal(&self, option: usize) -> Result<CurrentCommandInfo, String> {
+        let target = {
+            let combo = self.combo_file.read();
+            let file = combo
+                .as_ref()
+                .ok_or_else(|| "No combo file loaded".to_string())?;
+            let index = self.navigator.read().index();
+            let options = match file.step(index).map(|cmd| &cmd.input_type) {
+                Some(combo::InputType::Branch { options }) => options,
+                _ => return Err("Current step is not a branch".to_string()),
+            };
+            let choice = options
+                .get(option)
+                .ok_or_else(|| format!("Branch option {} does not exist", option + 1))?;
+            choice.target.unwrap_or((index + 1) % file.step_count())
+        };
+
+        self.navigate(Step::To(target))
+            .ok_or_else(|| "No combo file loaded".to_string())
+    }
+
+    /// Edit the rows of the loaded combo, keeping the current step in place
+    ///
+    /// `edit` gets the row of the current step and returns where that row is
+    /// afterwards. Edits that leave a branch pointing past the end are undone.
+    fn edit_loaded_combo(
+        &self,
+        edit: impl FnOnce(&mut ComboFile, usize) -> Result<usize, String>,
+    ) -> Result<Option<CurrentCommandInfo>, String> {
+        {
+            let mut combo = self.combo_file.write();
+            let file = combo
+                .as_mut()
+                .ok_or_else(|| "No combo file loaded".to_string())?;
+            let mut navigator = self.navigator.write();
+
+            let previous = file.clone();
+            let row = file
+                .row_of_step(navigator.index())
+                .unwrap_or(file.commands.len());
+            let row = edit(file, row)?;
+            if let Err(e) = file.validate_branches() {
+                *file = previous;
+                return Err(e.to_string());
+            }
+
+            let step = Step::To(file.steps_before_row(row));
+            if !navigator.apply(file, step) {
+                navigator.restart();
+            }
+        }
+        self.sync_input_handler();
+        Ok(self.get_current_command_internal())
+    }
+
+    /// Record an input captured `at` that completed the current step
+    ///
+    /// Sends its latency as `input-latency` and returns its timing grade.
+    fn record_advance(&self, app_handle: &tauri::AppHandle, at: Instant) -> Option<TimingResult> {
+        let advance = self.session.write().record_advance(at);
+        if let Some(latency) = advance.latency {
+            let latency = InputLatency {
+                index: self.navigator.read().index(),
+                latency_ms: latency.as_millis() as u64,
+            };
+            self.emit_optional(app_handle, "input-latency", latency);
+        }
+        self.step_timing(advance.gap)
+    }
+
+    /// Timing grade of completing the current step `gap` after the previous
+    /// input (`None` when the step has no gap annotation)
+    fn step_timing(&self, gap: Option<Duration>) -> Option<TimingResult> {
+        let index = self.navigator.read().index();
+        let expected_ms = self.combo_file.read().as_ref()?.step(index)?.gap_ms?;
+        Some(timing::grade(expected_ms, gap?))
+    }
+
+    /// Start the combo over if no step was completed for the auto-reset time
+    ///
+    /// Returns the first step when it reset.
+    fn check_auto_reset(&self) -> Option<CurrentCommandInfo> {
+        let secs = self.config.read().session.auto_reset_secs;
+        if secs == 0 || self.navigator.read().index() == 0 {
+            return None;
+        }
+        if !self
+            .session
+            .read()
+            .is_stalled(Instant::now(), Duration::from_secs(secs))
+        {
+            return None;
+        }
+        self.navigate(Step::First)
+    }
+
+    /// Whether the overlay should be up, given the user's toggle and whether
+    /// the game is in front
+    fn overlay_shown(&self, wanted: bool, focused: bool) -> bool {
+        let running = self.process_monitor.read().is_target_running();
+        focus::overlay_shown(wanted, focused, running, &self.config.read().overlay)
+    }
+
+    /// Get ready for a session when the game starts: the last combo from its
+    /// first step, with the overlay shown (or as it was left, when it hides
+    /// with the game and the game wasn't started from the app)
+    fn on_game_started(&self, app_handle: &tauri::AppHandle) {
+        let last = self.config.read().last_combo_file.clone();
+        let loaded = self.combo_path.read().clone();
+        match last {
+            // Already loaded (possibly from a playlist or a multi-combo file)
+            Some(path) if loaded.as_deref() == Some(path.as_str()) => {
+                self.navigate(Step::First);
+            }
+            Some(path) => match self.load_combo_internal(app_handle, &path, None) {
+                Ok(_) => *self.playlist.write() = None,
+                Err(e) => eprintln!("[ERROR] Failed to load last combo file: {}", e),
+            },
+            None => {}
+        }
+        if let Some(cmd) = self.get_current_command_internal() {
+            app_handle.send_update(&cmd);
+        }
+
+        let launched = launch::is_recent_launch(self.launch_pending.write().take(), Instant::now());
+        if launched || !self.config.read().overlay.hide_when_game_closed {
+            *self.overlay_visible.write() = true;
+        }
+        let visible = *self.overlay_visible.read();
+        let _ = app_handle.emit("overlay-visibility-changed", visible);
+    }
+
+    /// Emit "display-mode" when the game switches between windowed,
+    /// borderless and exclusive fullscreen
+    fn check_display_mode(&self, app_handle: &tauri::AppHandle) {
+        let pid = self.process_monitor.read().game_pid();
+        let mode = pid.and_then(window_tracker::game_display_mode);
+        if mode.is_some() && std::mem::replace(&mut *self.display_mode.write(), mode) != mode {
+            let _ = app_handle.emit("display-mode", mode);
+        }
+    }
+
+    /// Move the overlay along with the game window when it is anchored to it
+    fn follow_game_window(&self, app_handle: &tauri::AppHandle) {
+        let overlay = self.config.read().overlay.clone();
+        let rect = if overlay.anchor_to_game {
+            let pid = self.process_monitor.read().game_pid();
+            pid.and_then(window_tracker::game_window_rect)
+        } else {
+            None
+        };
+        if std::mem::replace(&mut *self.game_window.write(), rect) == rect {
+            return;
+        }
+        let (Some(rect), Some(main)) = (rect, app_handle.get_webview_window("main")) else {
+            return;
+        };
+        let Ok(size) = main.outer_size() else {
+            return;
+        };
+        let (x, y) = overlay.anchor_corner.position(
+            rect,
+            (size.width, size.height),
+            (overlay.anchor_offset_x, overlay.anchor_offset_y),
+        );
+        let _ = main.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }));
+    }
+
+    /// Whether a wheel notch should move through the combo
+    ///
+    /// Needs wheel navigation enabled and its modifier (if any) held.
+    fn wheel_navigates(&self) -> bool {
+        let config = self.config.read();
+        if !config.input.wheel_navigation {
+            return false;
         }
+        let modifier = config.input.wheel_navigation_modifier.trim();
+        if modifier.is_empty() {
+            return true;
+        }
+        match input::keymap::parse_key_name(modifier) {
+            Some(key) => self.input_handler.is_key_down(&key),
+            None => {
+                eprintln!("[ERROR] Unknown wheel navigation modifier '{}'", modifier);
+                false
+            }
+        }
+    }
+
+    /// Move through the loaded combo and keep the input handler in sync;
+    /// false if there was nowhere to go
+    ///
+    /// Leaves the session alone: completed steps are recorded by
+    /// [`Self::record_advance`], manual moves by [`Self::navigate`].
+    fn move_to(&self, step: Step) -> bool {
+        let moved = {
+            let combo = self.combo_file.read();
+            combo
+                .as_ref()
+                .is_some_and(|file| self.navigator.write().apply(file, step))
+        };
+        if moved {
+            self.sync_input_handler();
+        }
+        moved
     }
 
-    pub fn sync_input_handler(&self) {
-        let combo = self.combo_file.read();
-        let index = *self.current_index.read();
+    /// Move through the combo by hand (hotkey, command, branch choice, ...),
+    /// with the new current step if it moved
+    fn navigate(&self, step: Step) -> Option<CurrentCommandInfo> {
+        if !self.move_to(step) {
+            return None;
+        }
+        self.session.write().record_step_shown(Instant::now());
+        self.get_current_command_internal()
+    }
+
+    /// [`Self::navigate`] and send the new current step as `combo-update`
+    fn navigate_and_emit(
+        &self,
+        app_handle: &tauri::AppHandle,
+        step: Step,
+    ) -> Option<CurrentCommandInfo> {
+        let cmd = self.navigate(step)?;
+        app_handle.send_update(&cmd);
+        Some(cmd)
+    }
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UpdateSink for tauri::AppHandle {
+    fn send_update<T: Serialize + Clone>(&self, current: &T) {
+        let _ = self.emit(navigation::UPDATE_EVENT, current);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurrentCommandInfo {
+    pub index: usize,
+    pub total: usize,
+    pub title: String,
+    pub key_display: String,
+    pub character: String,
+    pub skill_type: String,
+    pub memo: String,
+    /// Info rows (`!` lines) just before the step
+    pub notes: Vec<String>,
+    pub is_hold: bool,
+    /// Movement key to hold while pressing the step's key
+    pub direction: Option<combo::Direction>,
+    /// Expected wait after the previous input in milliseconds
+    pub gap_ms: Option<u64>,
+    /// Whether the step may be skipped by pressing the next step's key
+    pub optional: bool,
+    /// Cancel window the step belongs to
+    pub group: Option<combo::StepGroup>,
+    /// Tags of the step
+    pub tags: Vec<String>,
+    /// Icon reference of the step (the picture comes from `get_step_icon`)
+    pub icon: Option<String>,
+    /// Glyph of the controller button for the step's key (`None` without a
+    /// connected controller)
+    pub gamepad_glyph: Option<String>,
+    /// Header tags of the combo
+    pub combo_tags: Vec<String>,
+    /// Options to pick from when the current step is a branch
+    pub branch_options: Vec<combo::BranchOption>,
+    /// Passes left (including the current one) of the innermost loop around this step
+    pub loop_remaining: Option<u32>,
+    /// Name of the phase (`#` section) the current step belongs to
+    pub phase: Option<String>,
+    /// Whether do-not-disturb mode is active
+    pub dnd: bool,
+    /// Timing grade of the input that completed the previous step (only
+    /// sent with `combo-update` after an input)
+    pub timing: Option<TimingResult>,
+    /// Stage reached by the charged hold that completed the previous step
+    /// (only sent with `combo-update` after a charged hold)
+    pub charge: Option<ChargeStageInfo>,
+    /// Milliseconds from capturing the input that completed the previous step
+    /// to sending this update (only sent with `combo-update` after an input)
+    pub input_delay_ms: Option<u64>,
+}
+
+// ============= Tauri Commands =============
+
+#[tauri::command]
+fn load_combo_file(
+    path: String,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<String, String> {
+    let title = state.load_combo_internal(&app_handle, &path, None)?;
+    // Picking a file by hand leaves the playlist
+    *state.playlist.write() = None;
+
+    if let Some(cmd) = state.get_current_command_internal() {
+        app_handle.send_update(&cmd);
+    }
+
+    Ok(title)
+}
+
+/// Wrong key pressed in strict mode (`combo-miss` payload)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComboMiss {
+    /// Step that was current when the key was pressed
+    pub index: usize,
+    /// Key that was pressed
+    pub key: String,
+    /// Whether the combo went back to its first step
+    pub reset: bool,
+}
+
+/// Combo loaded leniently, with the lines that were skipped
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LenientLoadResult {
+    pub title: String,
+    pub warnings: Vec<combo::ParseWarning>,
+}
+
+#[tauri::command]
+fn load_combo_file_lenient(
+    path: String,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<LenientLoadResult, String> {
+    let (combo, warnings) = read_combo_lenient(&path)?;
+    let title = state.install_combo(&app_handle, &path, combo, None);
+    *state.playlist.write() = None;
+
+    if !warnings.is_empty() {
+        let _ = app_handle.emit("combo-warnings", &warnings);
+    }
+    if let Some(cmd) = state.get_current_command_internal() {
+        app_handle.send_update(&cmd);
+    }
+
+    Ok(LenientLoadResult { title, warnings })
+}
+
+#[tauri::command]
+fn load_combo_from_text(
+    content: String,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<String, String> {
+    let title = state.load_combo_text_internal(&content)?;
+    if let Some(combo) = state.combo_file.read().as_ref() {
+        state.report_unknown_names(&app_handle, combo);
+    }
+
+    if let Some(cmd) = state.get_current_command_internal() {
+        app_handle.send_update(&cmd);
+    }
+
+    Ok(title)
+}
+
+#[tauri::command]
+fn load_playlist(
+    path: String,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<PlaylistStatus, String> {
+    let playlist = playlist::load_playlist(&path).map_err(|e| e.to_string())?;
+    let entry = playlist.current().clone();
+    state.load_combo_internal(&app_handle, &entry.path, entry.combo.as_deref())?;
+
+    let status = playlist.status();
+    *state.playlist.write() = Some(playlist);
+
+    let _ = app_handle.emit("playlist-advanced", &status);
+    if let Some(cmd) = state.get_current_command_internal() {
+        app_handle.send_update(&cmd);
+    }
+    Ok(status)
+}
+
+#[tauri::command]
+fn next_combo_in_playlist(
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<Option<PlaylistStatus>, String> {
+    let mut guard = state.playlist.write();
+    let playlist = guard
+        .as_mut()
+        .ok_or_else(|| "No playlist loaded".to_string())?;
+    let Some(entry) = playlist.advance().cloned() else {
+        return Ok(None);
+    };
+    let status = playlist.status();
+    drop(guard);
+
+    state.load_combo_internal(&app_handle, &entry.path, entry.combo.as_deref())?;
+
+    let _ = app_handle.emit("playlist-advanced", &status);
+    if let Some(cmd) = state.get_current_command_internal() {
+        app_handle.send_update(&cmd);
+    }
+    Ok(Some(status))
+}
+
+#[tauri::command]
+fn get_playlist_status(state: State<AppState>) -> Option<PlaylistStatus> {
+    state.playlist.read().as_ref().map(|p| p.status())
+}
+
+#[tauri::command]
+fn reload_current_combo(
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<Option<CurrentCommandInfo>, String> {
+    let cmd = state.reload_combo_internal()?;
+    if let Some(ref cmd) = cmd {
+        app_handle.send_update(&cmd);
+    }
+    Ok(cmd)
+}
+
+#[tauri::command]
+fn get_sp_projection(
+    start_sp: Option<u32>,
+    max_sp: Option<u32>,
+    state: State<AppState>,
+) -> Result<combo::SpProjection, String> {
+    state
+        .combo_file
+        .read()
+        .as_ref()
+        .map(|file| combo::project_sp(file, start_sp.unwrap_or(0), max_sp))
+        .ok_or_else(|| "No combo file loaded".to_string())
+}
+
+#[tauri::command]
+fn get_combo_stats(path: String, name: Option<String>) -> Result<combo::ComboStats, String> {
+    let (file, _) = read_combo(&path, name.as_deref())?;
+    Ok(combo::stats(&file))
+}
+
+#[tauri::command]
+fn export_combo(
+    path: String,
+    format: combo::ExportFormat,
+    state: State<AppState>,
+) -> Result<(), String> {
+    let sheet = state
+        .combo_file
+        .read()
+        .as_ref()
+        .map(|file| combo::export_combo(file, format))
+        .ok_or_else(|| "No combo file loaded".to_string())?;
+    std::fs::write(&path, sheet).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn export_share_code(state: State<AppState>) -> Result<String, String> {
+    state
+        .combo_file
+        .read()
+        .as_ref()
+        .map(combo::encode_share_code)
+        .ok_or_else(|| "No combo file loaded".to_string())
+}
+
+#[tauri::command]
+fn generate_combo_qr(path: String, state: State<AppState>) -> Result<(), String> {
+    let png = state
+        .combo_file
+        .read()
+        .as_ref()
+        .map(combo::encode_share_qr)
+        .ok_or_else(|| "No combo file loaded".to_string())?
+        .map_err(|e| e.to_string())?;
+    std::fs::write(&path, png).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn import_share_code(
+    code: String,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<DocumentSnapshot, String> {
+    let combo = combo::decode_share_code(&code).map_err(|e| e.to_string())?;
+
+    let doc = EditorDocument::new(combo, None);
+    let snapshot = doc.snapshot();
+    *state.editor.write() = Some(doc);
+
+    let _ = app_handle.emit("document-changed", &snapshot);
+    Ok(snapshot)
+}
+
+#[tauri::command]
+fn list_combos_in_file(path: String) -> Result<Vec<String>, String> {
+    combo::parse_combo_library_file(&path)
+        .map(|library| library.names())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn select_combo(
+    name: String,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<Option<CurrentCommandInfo>, String> {
+    let path = state
+        .combo_path
+        .read()
+        .clone()
+        .ok_or_else(|| "No combo file loaded".to_string())?;
+    let (combo, name) = read_combo(&path, Some(&name))?;
+
+    *state.combo_file.write() = Some(combo);
+    *state.combo_name.write() = name;
+    Ok(state.navigate_and_emit(&app_handle, Step::First))
+}
+
+#[tauri::command]
+fn insert_command(
+    index: usize,
+    command: combo::ComboCommand,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<Option<CurrentCommandInfo>, String> {
+    let info = state.edit_loaded_combo(|file, row| {
+        if index > file.commands.len() {
+            return Err(format!("Row {} is out of range", index));
+        }
+        file.insert(index, command);
+        Ok(if index <= row { row + 1 } else { row })
+    })?;
+    if let Some(cmd) = &info {
+        app_handle.send_update(&cmd);
+    }
+    Ok(info)
+}
+
+#[tauri::command]
+fn delete_command(
+    index: usize,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<Option<CurrentCommandInfo>, String> {
+    let info = state.edit_loaded_combo(|file, row| {
+        if let Some(branch) = file.branch_into_row(index) {
+            return Err(format!(
+                "Row {} is the target of the branch at step {}",
+                index,
+                branch + 1
+            ));
+        }
+        file.remove(index)
+            .ok_or_else(|| format!("Row {} is out of range", index))?;
+        // Deleting the current step moves on to the one after it
+        Ok(if index < row { row - 1 } else { row })
+    })?;
+    if let Some(cmd) = &info {
+        app_handle.send_update(&cmd);
+    }
+    Ok(info)
+}
+
+#[tauri::command]
+fn move_command(
+    from: usize,
+    to: usize,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<Option<CurrentCommandInfo>, String> {
+    let info = state.edit_loaded_combo(|file, row| {
+        if !file.reorder(from, to) {
+            return Err(format!("Row {} or {} is out of range", from, to));
+        }
+        Ok(if row == from {
+            to
+        } else if from < row && row <= to {
+            row - 1
+        } else if to <= row && row < from {
+            row + 1
+        } else {
+            row
+        })
+    })?;
+    if let Some(cmd) = &info {
+        app_handle.send_update(&cmd);
+    }
+    Ok(info)
+}
+
+#[tauri::command]
+fn update_command(
+    index: usize,
+    command: combo::ComboCommand,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<Option<CurrentCommandInfo>, String> {
+    let info = state.edit_loaded_combo(|file, row| {
+        let slot = file
+            .commands
+            .get_mut(index)
+            .ok_or_else(|| format!("Row {} is out of range", index))?;
+        *slot = Arc::new(command);
+        file.refresh_title();
+        Ok(row)
+    })?;
+    if let Some(cmd) = &info {
+        app_handle.send_update(&cmd);
+    }
+    Ok(info)
+}
+
+#[tauri::command]
+fn get_current_command(state: State<AppState>) -> Option<CurrentCommandInfo> {
+    state.get_current_command_internal()
+}
+
+#[tauri::command]
+fn advance_command(state: State<AppState>) -> Option<CurrentCommandInfo> {
+    state.navigate(Step::Next)
+}
+
+#[tauri::command]
+fn previous_command(state: State<AppState>) -> Option<CurrentCommandInfo> {
+    // At the first step this stays put and still reports it
+    state
+        .navigate(Step::Previous)
+        .or_else(|| state.get_current_command_internal())
+}
+
+#[tauri::command]
+fn reset_combo(state: State<AppState>) -> Option<CurrentCommandInfo> {
+    state.navigate(Step::First)
+}
+
+#[tauri::command]
+fn choose_branch(
+    option: usize,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<CurrentCommandInfo, String> {
+    let cmd = state.choose_branch_internal(option)?;
+    app_handle.send_update(&cmd);
+    Ok(cmd)
+}
+
+#[tauri::command]
+fn next_phase(state: State<AppState>) -> Option<CurrentCommandInfo> {
+    state.navigate(Step::Phase { forward: true })
+}
+
+#[tauri::command]
+fn previous_phase(state: State<AppState>) -> Option<CurrentCommandInfo> {
+    state.navigate(Step::Phase { forward: false })
+}
+
+#[tauri::command]
+fn get_combo_metadata(state: State<AppState>) -> Option<combo::ComboMetadata> {
+    state
+        .combo_file
+        .read()
+        .as_ref()
+        .map(|file| file.metadata.clone())
+}
+
+#[tauri::command]
+fn get_config(state: State<AppState>) -> config::Config {
+    state.config.read().clone()
+}
+
+#[tauri::command]
+fn inject_input_event(
+    event: input::inject::InjectedInput,
+    state: State<AppState>,
+) -> Result<(), String> {
+    if !cfg!(debug_assertions) {
+        return Err("Input injection is only available in debug builds".to_string());
+    }
+    let event_type = event
+        .event_type()
+        .ok_or_else(|| format!("Unknown key in {:?}", event))?;
+    if !state.input_handler.inject(event_type) {
+        return Err("Input listener is not running".to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn check_binding_conflicts(
+    key_bindings: config::KeyBindings,
+) -> Vec<config::conflicts::BindingConflict> {
+    config::conflicts::binding_conflicts(&key_bindings)
+}
+
+#[tauri::command]
+fn save_config(new_config: config::Config, state: State<AppState>) -> Result<(), String> {
+    let mut config = state.config.write();
+    *config = new_config;
+    state
+        .session
+        .write()
+        .set_idle_timeout(config.session.idle_timeout_secs);
+    config.input.hold_threshold_ms = state
+        .input_handler
+        .set_hold_threshold(config.input.hold_threshold_ms);
+    (
+        config.input.progress_interval_ms,
+        config.input.progress_fine_interval_ms,
+    ) = state.input_handler.set_progress_intervals(
+        config.input.progress_interval_ms,
+        config.input.progress_fine_interval_ms,
+    );
+    state
+        .input_handler
+        .set_chord_window(config.input.chord_window_ms);
+    state.input_handler.set_debounce(config.input.debounce_ms);
+    state
+        .process_monitor
+        .read()
+        .set_polling(config.polling.process_interval_ms, config.polling.low_power);
+    state
+        .input_handler
+        .set_strict_mode(config.input.strict_mode);
+    state
+        .input_handler
+        .set_advance_on_release(config.input.advance_on_release);
+    state
+        .input_handler
+        .set_require_game_focus(config.input.require_game_focus);
+    state
+        .input_handler
+        .set_keyboard_layout(config.input.keyboard_layout);
+    state
+        .input_handler
+        .set_key_map(KeyMap::from_bindings(&config.key_bindings));
+    state
+        .input_handler
+        .set_gamepad_mapping(GamepadMapping::from_bindings(&config.gamepad));
+    config
+        .save(Config::default_path())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn validate_combo_file(
+    path: String,
+    state: State<AppState>,
+) -> Result<Vec<combo::Diagnostic>, String> {
+    let content = combo::encoding::read_text(&path).map_err(|e| e.to_string())?;
+    let hold_threshold_ms = state.input_handler.hold_threshold_ms();
+    Ok(combo::lint(
+        &content,
+        combo::ComboFormat::from_path(&path),
+        hold_threshold_ms,
+        &state.roster,
+    ))
+}
+
+#[tauri::command]
+fn lint_combo(
+    content: String,
+    format: Option<combo::ComboFormat>,
+    state: State<AppState>,
+) -> Vec<combo::Diagnostic> {
+    let hold_threshold_ms = state.input_handler.hold_threshold_ms();
+    combo::lint(
+        &content,
+        format.unwrap_or(combo::ComboFormat::Text),
+        hold_threshold_ms,
+        &state.roster,
+    )
+}
+
+#[tauri::command]
+fn get_step_icon(index: usize, state: State<AppState>) -> Result<Option<String>, String> {
+    let icon = match state.combo_file.read().as_ref().and_then(|f| f.step(index)) {
+        Some(step) => step.icon.clone(),
+        None => return Err(format!("No step {}", index)),
+    };
+    let Some(icon) = icon else {
+        return Ok(None);
+    };
+
+    let library = state.config.read().combo_directory();
+    // Pasted combos have no file; their image paths start at the library
+    let combo_dir = state
+        .combo_path
+        .read()
+        .as_ref()
+        .and_then(|path| Path::new(path).parent().map(Path::to_path_buf))
+        .unwrap_or_else(|| library.clone());
+    let path = combo::icon::resolve_icon(&icon, &combo_dir, &library.join("icons"));
+    combo::icon::icon_data_url(&path)
+        .map(Some)
+        .map_err(|e| format!("Failed to read icon {}: {}", path.display(), e))
+}
+
+#[tauri::command]
+fn get_gamepad_glyphs(state: State<AppState>) -> Vec<(String, String)> {
+    state
+        .input_handler
+        .gamepad_glyphs()
+        .iter()
+        .map(|(key, glyph)| (key_display_label(key), glyph.clone()))
+        .collect()
+}
+
+#[tauri::command]
+fn start_key_capture(state: State<AppState>) {
+    *state.key_capture.write() = true;
+}
+
+#[tauri::command]
+fn cancel_key_capture(state: State<AppState>) {
+    *state.key_capture.write() = false;
+}
+
+#[tauri::command]
+fn start_recording(state: State<AppState>) {
+    state.input_handler.start_recording();
+}
+
+#[tauri::command]
+fn stop_recording(path: String, state: State<AppState>) -> Result<usize, String> {
+    let recording = state
+        .input_handler
+        .stop_recording()
+        .ok_or_else(|| "Not recording".to_string())?;
+    if recording.is_empty() {
+        return Err("Nothing was recorded".to_string());
+    }
+
+    let title = Path::new(&path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let steps = recording.len();
+    let combo = recording.into_combo(&title, state.input_handler.hold_threshold());
+    let retention = state.config.read().backups.retention;
+    library::save_combo_file(&path, &combo, retention).map_err(|e| e.to_string())?;
+    Ok(steps)
+}
+
+#[tauri::command]
+fn get_hold_threshold(state: State<AppState>) -> u64 {
+    state.input_handler.hold_threshold_ms()
+}
+
+#[tauri::command]
+fn adjust_hold_threshold(
+    delta_ms: i64,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> u64 {
+    state.adjust_hold_threshold_internal(&app_handle, delta_ms)
+}
+
+#[tauri::command]
+fn get_session_stats(state: State<AppState>) -> SessionStats {
+    state.session.read().stats(Instant::now())
+}
 
-        let command = if let Some(ref file) = *combo {
-            let commands: Vec<_> = file.commands.iter().filter(|c| !c.is_title).collect();
-            if index < commands.len() {
-                Some(commands[index].clone())
-            } else {
-                None
-            }
-        } else {
-            None
-        };
+#[tauri::command]
+fn get_input_metrics(state: State<AppState>) -> InputMetrics {
+    state.session.read().input_metrics()
+}
 
-        self.input_handler.set_current_command(command);
-    }
+#[tauri::command]
+fn reset_session_stats(state: State<AppState>) -> SessionStats {
+    let mut session = state.session.write();
+    session.reset();
+    session.stats(Instant::now())
+}
 
-    fn get_current_command_internal(&self) -> Option<CurrentCommandInfo> {
-        let combo = self.combo_file.read();
-        let index = *self.current_index.read();
+#[tauri::command]
+fn install_sample_combos(state: State<AppState>) -> Result<Vec<String>, String> {
+    let dir = state.config.read().combo_directory();
+    library::install_sample_combos(dir).map_err(|e| e.to_string())
+}
 
-        if let Some(ref file) = *combo {
-            let commands: Vec<_> = file.commands.iter().filter(|c| !c.is_title).collect();
-
-            if index < commands.len() {
-                let cmd = &commands[index];
-                let key_display = match &cmd.key {
-                    combo::KeyIdentifier::Number(n) => {
-                        if matches!(cmd.input_type, combo::InputType::Hold { .. }) {
-                            format!("Hold {}", n)
-                        } else {
-                            n.to_string()
-                        }
-                    }
-                    combo::KeyIdentifier::Chain => "E".to_string(),
-                    combo::KeyIdentifier::HeavyAttack | combo::KeyIdentifier::MouseLeft => {
-                        "L".to_string()
-                    }
-                };
+#[tauri::command]
+fn list_combo_library(state: State<AppState>) -> Result<LibraryIndex, String> {
+    state.library_index_internal(false)
+}
 
-                return Some(CurrentCommandInfo {
-                    index,
-                    total: commands.len(),
-                    title: file.title.clone(),
-                    key_display,
-                    character: cmd.character.clone(),
-                    skill_type: cmd.skill_type.clone(),
-                    memo: cmd.memo.clone(),
-                    is_hold: matches!(cmd.input_type, combo::InputType::Hold { .. }),
-                });
-            }
-        }
+#[tauri::command]
+fn refresh_combo_library(state: State<AppState>) -> Result<LibraryIndex, String> {
+    state.library_index_internal(true)
+}
 
-        None
-    }
+#[tauri::command]
+fn search_combos(
+    query: String,
+    filters: Option<SearchFilters>,
+    state: State<AppState>,
+) -> Result<Vec<SearchResult>, String> {
+    let index = state.library_index_internal(false)?;
+    Ok(library::search::search_library(
+        &index,
+        &query,
+        &filters.unwrap_or_default(),
+    ))
+}
 
-    /// Helper function to advance command index
-    fn advance_command_internal(&self) -> bool {
-        let combo = self.combo_file.read();
-        if let Some(ref file) = *combo {
-            let commands: Vec<_> = file.commands.iter().filter(|c| !c.is_title).collect();
-            if commands.is_empty() {
-                return false;
-            }
-            let mut index = self.current_index.write();
-            *index = (*index + 1) % commands.len();
-            true
-        } else {
-            false
-        }
-    }
+#[tauri::command]
+fn save_combo_file(
+    path: String,
+    mut combo: ComboFile,
+    state: State<AppState>,
+) -> Result<(), String> {
+    // The title comes from the rows; don't trust the one sent by the frontend
+    combo.refresh_title();
+    state.check_savable(&combo)?;
+
+    let retention = state.config.read().backups.retention;
+    library::save_combo_file(&path, &combo, retention).map_err(|e| e.to_string())
 }
 
-impl Default for AppState {
-    fn default() -> Self {
-        Self::new()
-    }
+#[tauri::command]
+fn list_combo_backups(path: String) -> Result<Vec<library::backup::BackupEntry>, String> {
+    library::backup::list_backups(Path::new(&path)).map_err(|e| e.to_string())
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CurrentCommandInfo {
-    pub index: usize,
-    pub total: usize,
-    pub title: String,
-    pub key_display: String,
-    pub character: String,
-    pub skill_type: String,
-    pub memo: String,
-    pub is_hold: bool,
+#[tauri::command]
+fn restore_combo_backup(
+    path: String,
+    backup_name: String,
+    state: State<AppState>,
+) -> Result<(), String> {
+    let retention = state.config.read().backups.retention;
+    library::backup::restore_backup(Path::new(&path), &backup_name, retention)
+        .map_err(|e| e.to_string())
 }
 
-// ============= Tauri Commands =============
+#[tauri::command]
+fn rename_combo_file(
+    path: String,
+    new_name: String,
+    overwrite: Option<bool>,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<String, String> {
+    let retention = state.config.read().backups.retention;
+    let new_path = library::rename_combo_file(
+        Path::new(&path),
+        &new_name,
+        overwrite.unwrap_or(false),
+        retention,
+    )
+    .map_err(|e| e.to_string())?;
+    let new_path = new_path.to_string_lossy().to_string();
+    state.relocate_combo_references(&app_handle, &path, Some(&new_path));
+    Ok(new_path)
+}
 
 #[tauri::command]
-fn load_combo_file(
+fn move_combo_file(
     path: String,
+    dest_dir: String,
+    overwrite: Option<bool>,
     state: State<AppState>,
     app_handle: tauri::AppHandle,
 ) -> Result<String, String> {
-    let combo_result = combo::parse_combo_file(&path).map_err(|e| e.to_string())?;
-    let title = combo_result.title.clone();
+    let retention = state.config.read().backups.retention;
+    let new_path = library::move_combo_file(
+        Path::new(&path),
+        Path::new(&dest_dir),
+        overwrite.unwrap_or(false),
+        retention,
+    )
+    .map_err(|e| e.to_string())?;
+    let new_path = new_path.to_string_lossy().to_string();
+    state.relocate_combo_references(&app_handle, &path, Some(&new_path));
+    Ok(new_path)
+}
 
-    *state.combo_file.write() = Some(combo_result);
-    *state.current_index.write() = 0;
-    state.sync_input_handler();
+#[tauri::command]
+fn duplicate_combo_file(
+    path: String,
+    new_name: String,
+    overwrite: Option<bool>,
+    state: State<AppState>,
+) -> Result<String, String> {
+    let retention = state.config.read().backups.retention;
+    library::duplicate_combo_file(
+        Path::new(&path),
+        &new_name,
+        overwrite.unwrap_or(false),
+        retention,
+    )
+    .map(|p| p.to_string_lossy().to_string())
+    .map_err(|e| e.to_string())
+}
 
-    {
-        let mut config = state.config.write();
-        config.last_combo_file = Some(path);
-        let _ = config.save(Config::default_path());
-    }
+#[tauri::command]
+fn create_combo_file(
+    path: String,
+    title: String,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<String, String> {
+    library::create_combo_file(&path, &title).map_err(|e| e.to_string())?;
+    let title = state.load_combo_internal(&app_handle, &path, None)?;
+    *state.playlist.write() = None;
 
     if let Some(cmd) = state.get_current_command_internal() {
-        let _ = app_handle.emit("combo-update", cmd);
+        app_handle.send_update(&cmd);
     }
-
     Ok(title)
 }
 
 #[tauri::command]
-fn get_current_command(state: State<AppState>) -> Option<CurrentCommandInfo> {
-    state.get_current_command_internal()
+fn delete_combo_file(
+    path: String,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let retention = state.config.read().backups.retention;
+    library::delete_combo_file(Path::new(&path), retention).map_err(|e| e.to_string())?;
+    state.relocate_combo_references(&app_handle, &path, None);
+    Ok(())
 }
 
 #[tauri::command]
-fn advance_command(state: State<AppState>) -> Option<CurrentCommandInfo> {
-    if state.advance_command_internal() {
-        state.sync_input_handler();
-        state.get_current_command_internal()
-    } else {
-        None
-    }
+fn open_combo_document(
+    path: String,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<DocumentSnapshot, String> {
+    let combo = combo::parse_combo_file(&path).map_err(|e| e.to_string())?;
+    let doc = EditorDocument::new(combo, Some(path));
+    let snapshot = doc.snapshot();
+    *state.editor.write() = Some(doc);
+
+    let _ = app_handle.emit("document-changed", &snapshot);
+    Ok(snapshot)
 }
 
 #[tauri::command]
-fn previous_command(state: State<AppState>) -> Option<CurrentCommandInfo> {
-    {
-        let combo = state.combo_file.read();
-        if let Some(ref file) = *combo {
-            let commands: Vec<_> = file.commands.iter().filter(|c| !c.is_title).collect();
-            let mut index = state.current_index.write();
-            if !commands.is_empty() {
-                if *index > 0 {
-                    *index -= 1;
-                }
-            }
-        }
+fn merge_combo_files(
+    base: String,
+    other: String,
+    strategy: Option<combo::MergeStrategy>,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<DocumentSnapshot, String> {
+    let base = combo::parse_combo_file(&base).map_err(|e| e.to_string())?;
+    let other = combo::parse_combo_file(&other).map_err(|e| e.to_string())?;
+    let combo = combo::merge_combos(&base, &other, strategy.unwrap_or_default())
+        .map_err(|e| e.to_string())?;
+
+    let doc = EditorDocument::new(combo, None);
+    let snapshot = doc.snapshot();
+    *state.editor.write() = Some(doc);
+
+    let _ = app_handle.emit("document-changed", &snapshot);
+    Ok(snapshot)
+}
+
+#[tauri::command]
+fn import_combo_csv(
+    path: String,
+    columns: Option<combo::CsvColumns>,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<DocumentSnapshot, String> {
+    let content = combo::encoding::read_text(&path).map_err(|e| e.to_string())?;
+    let mut combo =
+        combo::import_csv(&content, &columns.unwrap_or_default()).map_err(|e| e.to_string())?;
+
+    // Sheets without a `#` row are named after the file
+    if combo.title.is_empty() {
+        let name = Path::new(&path)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        combo.insert(0, combo::ComboCommand::title(&name));
     }
-    state.sync_input_handler();
-    state.get_current_command_internal()
+
+    let doc = EditorDocument::new(combo, None);
+    let snapshot = doc.snapshot();
+    *state.editor.write() = Some(doc);
+
+    let _ = app_handle.emit("document-changed", &snapshot);
+    Ok(snapshot)
 }
 
 #[tauri::command]
-fn reset_combo(state: State<AppState>) -> Option<CurrentCommandInfo> {
-    *state.current_index.write() = 0;
-    state.sync_input_handler();
-    state.get_current_command_internal()
+fn get_combo_document(state: State<AppState>) -> Option<DocumentSnapshot> {
+    state.editor.read().as_ref().map(|doc| doc.snapshot())
 }
 
 #[tauri::command]
-fn get_config(state: State<AppState>) -> config::Config {
-    state.config.read().clone()
+fn insert_step(
+    index: usize,
+    command: combo::ComboCommand,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<DocumentSnapshot, String> {
+    state.edit_document(&app_handle, |doc| doc.insert_step(index, command))
 }
 
 #[tauri::command]
-fn save_config(new_config: config::Config, state: State<AppState>) -> Result<(), String> {
-    let mut config = state.config.write();
-    *config = new_config;
-    config
-        .save(Config::default_path())
-        .map_err(|e| e.to_string())
+fn delete_step(
+    index: usize,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<DocumentSnapshot, String> {
+    state.edit_document(&app_handle, |doc| doc.delete_step(index))
+}
+
+#[tauri::command]
+fn move_step(
+    from: usize,
+    to: usize,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<DocumentSnapshot, String> {
+    state.edit_document(&app_handle, |doc| doc.move_step(from, to))
+}
+
+#[tauri::command]
+fn update_step(
+    index: usize,
+    command: combo::ComboCommand,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<DocumentSnapshot, String> {
+    state.edit_document(&app_handle, |doc| doc.update_step(index, command))
+}
+
+#[tauri::command]
+fn undo_edit(
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<DocumentSnapshot, String> {
+    state.step_edit_history(&app_handle, false)
+}
+
+#[tauri::command]
+fn redo_edit(
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<DocumentSnapshot, String> {
+    state.step_edit_history(&app_handle, true)
+}
+
+#[tauri::command]
+fn save_combo_document(
+    path: Option<String>,
+    state: State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<DocumentSnapshot, String> {
+    let retention = state.config.read().backups.retention;
+    let mut editor = state.editor.write();
+    let doc = editor
+        .as_mut()
+        .ok_or_else(|| EditError::NoDocument.to_string())?;
+
+    let path = path
+        .or_else(|| doc.path.clone())
+        .ok_or_else(|| "No save path specified".to_string())?;
+    state.check_savable(&doc.combo)?;
+    library::save_combo_file(&path, &doc.combo, retention).map_err(|e| e.to_string())?;
+    doc.path = Some(path);
+    doc.mark_saved();
+
+    let snapshot = doc.snapshot();
+    let _ = app_handle.emit("document-changed", &snapshot);
+    Ok(snapshot)
+}
+
+#[tauri::command]
+fn is_game_running(state: State<AppState>) -> bool {
+    state.process_monitor.read().is_target_running()
+}
+
+#[tauri::command]
+fn launch_game(state: State<AppState>, app_handle: tauri::AppHandle) -> Result<(), String> {
+    use tauri_plugin_opener::OpenerExt;
+
+    let launcher = state
+        .config
+        .read()
+        .game_launcher
+        .clone()
+        .unwrap_or_default();
+    let target =
+        LaunchTarget::parse(&launcher).ok_or_else(|| "No game launcher configured".to_string())?;
+    if state.process_monitor.read().is_target_running() {
+        return Err("The game is already running".to_string());
+    }
+    let opened = match target {
+        LaunchTarget::Path(path) => app_handle
+            .opener()
+            .open_path(path.to_string_lossy(), None::<&str>),
+        LaunchTarget::Uri(uri) => app_handle.opener().open_url(uri, None::<&str>),
+    };
+    opened.map_err(|e| format!("Failed to launch the game: {}", e))?;
+    *state.launch_pending.write() = Some(Instant::now());
+    Ok(())
 }
 
 #[tauri::command]
-fn is_game_running() -> bool {
-    ProcessMonitor::check_once()
+fn get_display_mode(state: State<AppState>) -> Option<DisplayMode> {
+    *state.display_mode.read()
 }
 
 #[tauri::command]
@@ -220,10 +1773,10 @@ fn toggle_overlay(state: State<AppState>, app_handle: tauri::AppHandle) -> bool
     let mut visible = state.overlay_visible.write();
     *visible = !*visible;
 
-    let active = ProcessMonitor::is_game_active();
+    let shown = state.overlay_shown(*visible, ProcessMonitor::is_game_active());
 
     if let Some(window) = app_handle.get_webview_window("main") {
-        if *visible && active {
+        if shown {
             let _ = window.show();
         } else {
             let _ = window.hide();
@@ -235,6 +1788,40 @@ fn toggle_overlay(state: State<AppState>, app_handle: tauri::AppHandle) -> bool
     *visible
 }
 
+#[tauri::command]
+fn set_dnd_mode(enabled: bool, state: State<AppState>, app_handle: tauri::AppHandle) {
+    state.set_dnd_internal(&app_handle, enabled);
+}
+
+#[tauri::command]
+fn toggle_dnd_mode(state: State<AppState>, app_handle: tauri::AppHandle) -> bool {
+    let enabled = !*state.dnd.read();
+    state.set_dnd_internal(&app_handle, enabled);
+    enabled
+}
+
+#[tauri::command]
+fn get_dnd_mode(state: State<AppState>) -> bool {
+    *state.dnd.read()
+}
+
+#[tauri::command]
+fn set_tracking_paused(paused: bool, state: State<AppState>, app_handle: tauri::AppHandle) {
+    state.set_tracking_paused_internal(&app_handle, paused);
+}
+
+#[tauri::command]
+fn toggle_tracking_paused(state: State<AppState>, app_handle: tauri::AppHandle) -> bool {
+    let paused = !state.input_handler.is_paused();
+    state.set_tracking_paused_internal(&app_handle, paused);
+    paused
+}
+
+#[tauri::command]
+fn get_tracking_paused(state: State<AppState>) -> bool {
+    state.input_handler.is_paused()
+}
+
 #[tauri::command]
 fn set_overlay_visible(visible: bool, state: State<AppState>) {
     *state.overlay_visible.write() = visible;
@@ -268,54 +1855,6 @@ fn app_exit(state: State<AppState>, app_handle: tauri::AppHandle) {
     app_handle.exit(0);
 }
 
-
-
-fn key_to_string(key: Key) -> String {
-    match key {
-        Key::KeyA => "A".to_string(),
-        Key::KeyB => "B".to_string(),
-        Key::KeyC => "C".to_string(),
-        Key::KeyD => "D".to_string(),
-        Key::KeyE => "E".to_string(),
-        Key::KeyF => "F".to_string(),
-        Key::KeyG => "G".to_string(),
-        Key::KeyH => "H".to_string(),
-        Key::KeyI => "I".to_string(),
-        Key::KeyJ => "J".to_string(),
-        Key::KeyK => "K".to_string(),
-        Key::KeyL => "L".to_string(),
-        Key::KeyM => "M".to_string(),
-        Key::KeyN => "N".to_string(),
-        Key::KeyO => "O".to_string(),
-        Key::KeyP => "P".to_string(),
-        Key::KeyQ => "Q".to_string(),
-        Key::KeyR => "R".to_string(),
-        Key::KeyS => "S".to_string(),
-        Key::KeyT => "T".to_string(),
-        Key::KeyU => "U".to_string(),
-        Key::KeyV => "V".to_string(),
-        Key::KeyW => "W".to_string(),
-        Key::KeyX => "X".to_string(),
-        Key::KeyY => "Y".to_string(),
-        Key::KeyZ => "Z".to_string(),
-        Key::Num1 => "1".to_string(),
-        Key::Num2 => "2".to_string(),
-        Key::Num3 => "3".to_string(),
-        Key::Num4 => "4".to_string(),
-        Key::Num5 => "5".to_string(),
-        Key::Num6 => "6".to_string(),
-        Key::Num7 => "7".to_string(),
-        Key::Num8 => "8".to_string(),
-        Key::Num9 => "9".to_string(),
-        Key::Num0 => "0".to_string(),
-        Key::Space => "Space".to_string(),
-        Key::Return => "Enter".to_string(),
-        Key::Escape => "Escape".to_string(),
-        Key::Tab => "Tab".to_string(),
-        _ => format!("{:?}", key),
-    }
-}
-
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -325,14 +1864,82 @@ pub fn run() {
         .manage(AppState::new())
         .invoke_handler(tauri::generate_handler![
             load_combo_file,
+            load_combo_file_lenient,
+            load_combo_from_text,
+            reload_current_combo,
+            list_combos_in_file,
+            select_combo,
+            load_playlist,
+            next_combo_in_playlist,
+            get_playlist_status,
             get_current_command,
+            insert_command,
+            delete_command,
+            move_command,
+            update_command,
             advance_command,
             previous_command,
             reset_combo,
+            choose_branch,
+            next_phase,
+            previous_phase,
+            get_combo_metadata,
+            get_combo_stats,
+            export_combo,
+            export_share_code,
+            generate_combo_qr,
+            import_share_code,
+            get_sp_projection,
             get_config,
+            check_binding_conflicts,
+            inject_input_event,
             save_config,
+            validate_combo_file,
+            lint_combo,
+            get_step_icon,
+            get_gamepad_glyphs,
+            start_key_capture,
+            cancel_key_capture,
+            start_recording,
+            stop_recording,
+            get_hold_threshold,
+            adjust_hold_threshold,
+            get_session_stats,
+            get_input_metrics,
+            reset_session_stats,
+            install_sample_combos,
+            list_combo_library,
+            refresh_combo_library,
+            search_combos,
+            save_combo_file,
+            list_combo_backups,
+            restore_combo_backup,
+            rename_combo_file,
+            move_combo_file,
+            duplicate_combo_file,
+            create_combo_file,
+            delete_combo_file,
+            open_combo_document,
+            merge_combo_files,
+            import_combo_csv,
+            get_combo_document,
+            insert_step,
+            delete_step,
+            move_step,
+            update_step,
+            undo_edit,
+            redo_edit,
+            save_combo_document,
             is_game_running,
+            get_display_mode,
+            launch_game,
             toggle_overlay,
+            set_dnd_mode,
+            toggle_dnd_mode,
+            get_dnd_mode,
+            set_tracking_paused,
+            toggle_tracking_paused,
+            get_tracking_paused,
             set_overlay_visible,
             get_overlay_visible,
             open_settings_window,
@@ -364,14 +1971,29 @@ pub fn run() {
                 // Listen for window move events to update config in memory
                 if let Some(main_window) = app.get_webview_window("main") {
                     let app_handle_move = app.handle().clone();
+                    let main_window_move = main_window.clone();
                     main_window.on_window_event(move |event| {
                         if let tauri::WindowEvent::Moved(pos) = event {
                              let state = app_handle_move.state::<AppState>();
+                             let game = *state.game_window.read();
+                             let size = main_window_move.outer_size().ok();
                              // Use try_write to avoid potential deadlocks in event loop, mostly safe though
                              let mut maybe_config = state.config.try_write();
                              if let Some(ref mut config) = maybe_config {
                                  config.overlay.x = pos.x;
                                  config.overlay.y = pos.y;
+                                 // Dragging an anchored overlay moves it relative to the game
+                                 if let (true, Some(game), Some(size)) =
+                                     (config.overlay.anchor_to_game, game, size)
+                                 {
+                                     let (x, y) = config.overlay.anchor_corner.offset(
+                                         game,
+                                         (size.width, size.height),
+                                         (pos.x, pos.y),
+                                     );
+                                     config.overlay.anchor_offset_x = x;
+                                     config.overlay.anchor_offset_y = y;
+                                 }
                              }
                         }
                     });
@@ -444,16 +2066,48 @@ pub fn run() {
             // Note: This thread runs indefinitely. When the application exits,
             // the OS will automatically terminate this background thread.
             let app_handle = app.handle().clone();
+            let process_handle = app.handle().clone();
+            app.state::<AppState>()
+                .process_monitor
+                .write()
+                .start(move |running| {
+                    let _ = process_handle.emit("game-process-changed", running);
+                    if running {
+                        process_handle
+                            .state::<AppState>()
+                            .on_game_started(&process_handle);
+                    }
+                });
             std::thread::spawn(move || {
                 let mut last_visible = false;
+                let mut focus = FocusMonitor::default();
                 loop {
-                    let active = ProcessMonitor::is_game_active();
+                    if let Some(focused) = focus.poll() {
+                        let _ = app_handle.emit("game-focus-changed", focused);
+                    }
+                    let active = focus.is_focused();
+
+                    // Pause session stats while unfocused or inactive
+                    {
+                        let state = app_handle.state::<AppState>();
+                        *state.game_focused.write() = active;
+                        state
+                            .input_handler
+                            .set_game_foreground(focus.is_game_in_front());
+                        if state.session.write().check_idle(Instant::now(), active) {
+                            state.emit_optional(&app_handle, "session-idle-changed", true);
+                        }
+                        // A stalled attempt starts over
+                        if let Some(cmd) = state.check_auto_reset() {
+                            app_handle.send_update(&cmd);
+                        }
+                    }
                     
                     // Check user preference
                     let should_be_visible = {
                         let state = app_handle.state::<AppState>();
                         let user_wants_visible = *state.overlay_visible.read();
-                        active && user_wants_visible
+                        state.overlay_shown(user_wants_visible, active)
                     };
 
                     if should_be_visible != last_visible {
@@ -473,12 +2127,28 @@ pub fn run() {
                         }
                     }
 
+                    {
+                        let state = app_handle.state::<AppState>();
+                        state.follow_game_window(&app_handle);
+                        state.check_display_mode(&app_handle);
+                    }
+
                     // Emit game status for frontend (e.g., to stop animations if paused?)
                     // Even if hidden, the frontend might want to know.
                     let _ = app_handle.emit("game-status-changed", active);
                     
-                    // Check more frequently for responsiveness (500ms)
-                    std::thread::sleep(std::time::Duration::from_millis(500));
+                    // Check more often than the process list for responsiveness
+                    let interval = {
+                        let state = app_handle.state::<AppState>();
+                        let polling = state.config.read().polling.clone();
+                        let running = state.process_monitor.read().is_target_running();
+                        process::poll_interval(
+                            polling.focus_interval_ms,
+                            polling.low_power,
+                            running,
+                        )
+                    };
+                    std::thread::sleep(interval);
                 }
             });
 
@@ -488,27 +2158,104 @@ pub fn run() {
             std::thread::spawn(move || {
                 let mut rx = input::start_global_key_listener(input_handler);
 
-                while let Some(event) = rx.blocking_recv() {
+                while let Some(TimedKeyEvent { event, at }) = rx.blocking_recv() {
                     let state = app_handle_input.state::<AppState>();
 
                     match event {
-                        KeyEvent::TapComplete(_) | KeyEvent::HoldComplete(_) => {
-                            if state.advance_command_internal() {
-                                state.sync_input_handler();
-                                if let Some(cmd) = state.get_current_command_internal() {
-                                    let _ = app_handle_input.emit("combo-update", cmd);
+                        KeyEvent::TapComplete(_)
+                        | KeyEvent::ChordComplete(_)
+                        | KeyEvent::HoldComplete(_)
+                        | KeyEvent::ReleaseComplete(_)
+                        | KeyEvent::ChargeComplete(..)
+                        | KeyEvent::WaitComplete => {
+                            let charge = match event {
+                                KeyEvent::ChargeComplete(_, info) => Some(info),
+                                _ => None,
+                            };
+                            let timing = state.record_advance(&app_handle_input, at);
+                            if state.move_to(Step::Next) {
+                                if let Some(mut cmd) = state.get_current_command_internal() {
+                                    cmd.timing = timing;
+                                    cmd.charge = charge;
+                                    cmd.input_delay_ms = Some(at.elapsed().as_millis() as u64);
+                                    app_handle_input.send_update(&cmd);
+                                }
+                            }
+                        }
+                        KeyEvent::StepSkipped(key) => {
+                            if state.move_to(Step::Next) {
+                                // The press that skipped the optional step may complete the next one
+                                let mut timing = None;
+                                if let Some(KeyEvent::TapComplete(_) | KeyEvent::ChordComplete(_)) =
+                                    state.input_handler.evaluate_press(key)
+                                {
+                                    timing = state.record_advance(&app_handle_input, at);
+                                    state.move_to(Step::Next);
                                 }
+                                if let Some(mut cmd) = state.get_current_command_internal() {
+                                    cmd.timing = timing;
+                                    cmd.input_delay_ms = Some(at.elapsed().as_millis() as u64);
+                                    app_handle_input.send_update(&cmd);
+                                }
+                            }
+                        }
+                        // Wheel navigation wins over strict-mode misses
+                        KeyEvent::Miss(key @ (WHEEL_UP_KEY | WHEEL_DOWN_KEY))
+                            if state.wheel_navigates() =>
+                        {
+                            let step = Step::one(key == WHEEL_DOWN_KEY);
+                            state.navigate_and_emit(&app_handle_input, step);
+                        }
+                        KeyEvent::Miss(key) => {
+                            let index = state.navigator.read().index();
+                            let reset = state.config.read().input.reset_on_miss;
+                            if reset {
+                                state.navigate_and_emit(&app_handle_input, Step::First);
                             }
+                            let miss = ComboMiss {
+                                index,
+                                key: key_name(key),
+                                reset,
+                            };
+                            let _ = app_handle_input.emit("combo-miss", miss);
                         }
-                        KeyEvent::HoldProgress(_, progress) => {
+                        KeyEvent::HoldProgress(_, info) => {
                             // Emit hold progress to frontend
-                            let _ = app_handle_input.emit("hold-progress", progress);
+                            state.emit_optional(&app_handle_input, "hold-progress", info);
+                        }
+                        KeyEvent::WaitProgress(info) => {
+                            state.emit_optional(&app_handle_input, "wait-progress", info);
+                        }
+                        KeyEvent::ChargeStage(_, info) => {
+                            state.emit_optional(&app_handle_input, "charge-stage", info);
+                        }
+                        KeyEvent::TapProgress(_, info) => {
+                            state.emit_optional(&app_handle_input, "tap-progress", info);
                         }
                         KeyEvent::HoldReset(_) => {
                             // Reset hold progress on frontend
-                            let _ = app_handle_input.emit("hold-progress", 0.0);
+                            let info = state.input_handler.hold_progress(Duration::ZERO);
+                            state.emit_optional(&app_handle_input, "hold-progress", info);
+                        }
+                        // Captured keys are named as hotkey matching sees them
+                        KeyEvent::KeyDown(key)
+                            if std::mem::take(&mut *state.key_capture.write()) =>
+                        {
+                            let _ = app_handle_input.emit("key-captured", key_name(key));
                         }
                         KeyEvent::KeyDown(key) => {
+                            // Any non-Alt input while the game is focused resumes an idle session
+                            if !matches!(key, Key::Alt | Key::AltGr)
+                                && *state.game_focused.read()
+                                && state.session.write().record_input(at)
+                            {
+                                state.emit_optional(
+                                    &app_handle_input,
+                                    "session-idle-changed",
+                                    false,
+                                );
+                            }
+
                             if matches!(key, Key::Alt | Key::AltGr) {
                                 #[cfg(debug_assertions)]
                                 println!("[DEBUG] lib.rs received Alt KeyDown: {:?}", key);
@@ -526,45 +2273,17 @@ pub fn run() {
                                 }
                             }
 
-                            match key {
-                                Key::RightArrow => {
-                                    if state.advance_command_internal() {
-                                        state.sync_input_handler();
-                                        if let Some(cmd) = state.get_current_command_internal() {
-                                            let _ = app_handle_input.emit("combo-update", cmd);
-                                        }
-                                    }
-                                }
-                                Key::LeftArrow => {
-                                    let mut changed = false;
-                                    {
-                                        let combo = state.combo_file.read();
-                                        if let Some(ref file) = *combo {
-                                            let commands: Vec<_> = file
-                                                .commands
-                                                .iter()
-                                                .filter(|c| !c.is_title)
-                                                .collect();
-                                            let mut index = state.current_index.write();
-                                            if !commands.is_empty() && *index > 0 {
-                                                *index -= 1;
-                                                changed = true;
-                                            }
-                                        }
-                                    }
-                                    if changed {
-                                        state.sync_input_handler();
-                                        if let Some(cmd) = state.get_current_command_internal() {
-                                            let _ = app_handle_input.emit("combo-update", cmd);
-                                        }
-                                    }
-                                }
-                                _ => {}
+                            // Wheel notches that didn't complete a wheel step (down = next)
+                            if matches!(key, WHEEL_UP_KEY | WHEEL_DOWN_KEY)
+                                && state.wheel_navigates()
+                            {
+                                let step = Step::one(key == WHEEL_DOWN_KEY);
+                                state.navigate_and_emit(&app_handle_input, step);
                             }
 
                             // Hotkey Check
                             let config = state.config.read();
-                            let key_str = key_to_string(key);
+                            let key_str = key_name(key);
 
                             #[cfg(debug_assertions)]
                             {
@@ -604,8 +2323,62 @@ pub fn run() {
 
                                 let _ =
                                     app_handle_input.emit("overlay-visibility-changed", *visible);
+                            } else if key_str == config.key_bindings.next_step
+                                || key_str == config.key_bindings.previous_step
+                            {
+                                let step = Step::one(key_str == config.key_bindings.next_step);
+                                drop(config);
+                                state.navigate_and_emit(&app_handle_input, step);
+                            } else if key_str == config.key_bindings.reset_combo {
+                                drop(config);
+                                state.navigate_and_emit(&app_handle_input, Step::First);
+                            } else if key_str == config.key_bindings.reload_combo {
+                                drop(config);
+                                match state.reload_combo_internal() {
+                                    Ok(Some(cmd)) => {
+                                        app_handle_input.send_update(&cmd);
+                                    }
+                                    Ok(None) => {}
+                                    Err(e) => {
+                                        let _ = app_handle_input.emit("combo-reload-failed", e);
+                                    }
+                                }
+                            } else if key_str == config.key_bindings.toggle_dnd {
+                                drop(config);
+                                let enabled = !*state.dnd.read();
+                                state.set_dnd_internal(&app_handle_input, enabled);
+                            } else if key_str == config.key_bindings.pause_tracking {
+                                drop(config);
+                                let paused = !state.input_handler.is_paused();
+                                state.set_tracking_paused_internal(&app_handle_input, paused);
+                            } else if key_str == config.key_bindings.decrease_hold_threshold {
+                                drop(config);
+                                state.adjust_hold_threshold_internal(
+                                    &app_handle_input,
+                                    -input::HOLD_THRESHOLD_STEP_MS,
+                                );
+                            } else if key_str == config.key_bindings.increase_hold_threshold {
+                                drop(config);
+                                state.adjust_hold_threshold_internal(
+                                    &app_handle_input,
+                                    input::HOLD_THRESHOLD_STEP_MS,
+                                );
+                            } else if key_str == config.key_bindings.branch_option_1
+                                || key_str == config.key_bindings.branch_option_2
+                            {
+                                let option =
+                                    usize::from(key_str == config.key_bindings.branch_option_2);
+                                drop(config);
+                                if let Ok(cmd) = state.choose_branch_internal(option) {
+                                    app_handle_input.send_update(&cmd);
+                                }
                             }
                         }
+                        // Held keys don't retrigger steps or hotkeys
+                        KeyEvent::KeyRepeat(_) => {}
+                        KeyEvent::ListenerStatus(status) => {
+                            let _ = app_handle_input.emit("input-listener-status", status);
+                        }
                         KeyEvent::KeyUp(key) => {
                             if matches!(key, Key::Alt | Key::AltGr) {
                                 #[cfg(debug_assertions)]
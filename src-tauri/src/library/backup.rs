@@ -0,0 +1,199 @@
+//! Timestamped combo file backups
+//!
+//! Before a combo file is overwritten, a copy is written to a `.backups`
+//! folder next to it as `<file name>.<unix ms>.bak`.
+
+use super::LibraryError;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Backup folder name (created next to the backed-up file)
+const BACKUP_DIR: &str = ".backups";
+
+/// Backup file extension
+const BACKUP_EXT: &str = "bak";
+
+/// A single backup of a combo file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupEntry {
+    /// Backup file name (used to restore)
+    pub file_name: String,
+    /// Creation time in milliseconds since the Unix epoch
+    pub created_ms: u64,
+    /// Size in bytes
+    pub size: u64,
+}
+
+fn backup_dir(path: &Path) -> PathBuf {
+    path.parent()
+        .map(|p| p.join(BACKUP_DIR))
+        .unwrap_or_else(|| PathBuf::from(BACKUP_DIR))
+}
+
+fn file_name(path: &Path) -> Result<String, LibraryError> {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| LibraryError::InvalidPath(path.display().to_string()))
+}
+
+/// Parse the timestamp out of a backup name belonging to `original`
+fn parse_backup_name(name: &str, original: &str) -> Option<u64> {
+    let rest = name.strip_prefix(original)?.strip_prefix('.')?;
+    let stamp = rest.strip_suffix(BACKUP_EXT)?.strip_suffix('.')?;
+    stamp.parse().ok()
+}
+
+/// Back up an existing file before it gets overwritten
+///
+/// Does nothing if the file does not exist yet or `retention` is 0 (backups
+/// turned off). Old backups beyond `retention` are removed afterwards.
+pub fn backup_file(path: &Path, retention: usize) -> Result<Option<BackupEntry>, LibraryError> {
+    if retention == 0 {
+        return Ok(None);
+    }
+    let entry = write_backup(path)?;
+    prune_backups(path, retention, None)?;
+    Ok(entry)
+}
+
+/// Copy an existing file to a new backup, without pruning
+fn write_backup(path: &Path) -> Result<Option<BackupEntry>, LibraryError> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let name = file_name(path)?;
+    let dir = backup_dir(path);
+    fs::create_dir_all(&dir).map_err(|e| LibraryError::IoError(e.to_string()))?;
+
+    // Stamps must stay strictly increasing even when saving several times
+    // within the same millisecond, otherwise pruning could drop the newest copy
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let created_ms = match list_backups(path)?.first() {
+        Some(newest) => now_ms.max(newest.created_ms + 1),
+        None => now_ms,
+    };
+    let backup_path = dir.join(format!("{}.{}.{}", name, created_ms, BACKUP_EXT));
+
+    let size = fs::copy(path, &backup_path).map_err(|e| LibraryError::IoError(e.to_string()))?;
+
+    Ok(Some(BackupEntry {
+        file_name: file_name(&backup_path)?,
+        created_ms,
+        size,
+    }))
+}
+
+/// List backups of a file, newest first
+pub fn list_backups(path: &Path) -> Result<Vec<BackupEntry>, LibraryError> {
+    let name = file_name(path)?;
+    let dir = backup_dir(path);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(&dir).map_err(|e| LibraryError::IoError(e.to_string()))? {
+        let entry = entry.map_err(|e| LibraryError::IoError(e.to_string()))?;
+        let backup_name = entry.file_name().to_string_lossy().to_string();
+        if let Some(created_ms) = parse_backup_name(&backup_name, &name) {
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            entries.push(BackupEntry {
+                file_name: backup_name,
+                created_ms,
+                size,
+            });
+        }
+    }
+
+    entries.sort_by_key(|e| std::cmp::Reverse(e.created_ms));
+    Ok(entries)
+}
+
+/// Remove the oldest backups of a file so that at most `retention` remain,
+/// never removing the backup named `keep`
+fn prune_backups(path: &Path, retention: usize, keep: Option<&str>) -> Result<(), LibraryError> {
+    let dir = backup_dir(path);
+    for old in list_backups(path)?.into_iter().skip(retention) {
+        if keep == Some(old.file_name.as_str()) {
+            continue;
+        }
+        fs::remove_file(dir.join(old.file_name))
+            .map_err(|e| LibraryError::IoError(e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Restore a backup over the original file
+///
+/// The current file content is backed up first (unless backups are turned
+/// off) so a restore can itself be undone. The restored backup is kept.
+pub fn restore_backup(
+    path: &Path,
+    backup_name: &str,
+    retention: usize,
+) -> Result<(), LibraryError> {
+    let name = file_name(path)?;
+    if parse_backup_name(backup_name, &name).is_none() {
+        return Err(LibraryError::InvalidPath(backup_name.to_string()));
+    }
+
+    let backup_path = backup_dir(path).join(backup_name);
+    if !backup_path.is_file() {
+        return Err(LibraryError::NotFound(backup_name.to_string()));
+    }
+
+    let content = fs::read(&backup_path).map_err(|e| LibraryError::IoError(e.to_string()))?;
+    if retention > 0 {
+        write_backup(path)?;
+    }
+    fs::write(path, content).map_err(|e| LibraryError::IoError(e.to_string()))?;
+    if retention > 0 {
+        prune_backups(path, retention, Some(backup_name))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_retention_and_restore() {
+        let dir = std::env::temp_dir().join("akef_combonavi_backup_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("combo.txt");
+
+        for i in 0..4 {
+            fs::write(&path, format!("v{}", i)).unwrap();
+            backup_file(&path, 2).unwrap();
+        }
+
+        let backups = list_backups(&path).unwrap();
+        assert_eq!(backups.len(), 2);
+
+        // Newest backup holds "v3"; oldest retained holds "v2"
+        restore_backup(&path, &backups[1].file_name, 2).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "v2");
+        assert!(restore_backup(&path, "../combo.txt", 2).is_err());
+
+        // The restored-from backup survives pruning
+        let after = list_backups(&path).unwrap();
+        assert!(after.iter().any(|b| b.file_name == backups[1].file_name));
+        assert_eq!(after.len(), 3);
+
+        // Retention 0 turns backups off and leaves the existing ones alone
+        assert!(backup_file(&path, 0).unwrap().is_none());
+        restore_backup(&path, &backups[0].file_name, 0).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "v3");
+        assert_eq!(list_backups(&path).unwrap().len(), 3);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
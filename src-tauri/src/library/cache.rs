@@ -0,0 +1,65 @@
+//! Library index cache
+//!
+//! The index of the last scan is written in the binary format next to the
+//! combos, so the first scan after startup only parses files that changed.
+//! A missing or unreadable cache just means a full scan.
+
+use super::index::LibraryIndex;
+use super::LibraryError;
+use crate::combo::binary::{from_bytes, to_bytes};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Cache folder inside the combo directory (hidden, so scans skip it)
+const CACHE_DIR: &str = ".cache";
+
+/// Index cache file name
+const INDEX_FILE: &str = "library.bin";
+
+fn index_path(dir: &Path) -> PathBuf {
+    dir.join(CACHE_DIR).join(INDEX_FILE)
+}
+
+/// Index saved by the last scan of `dir`, if it is still readable
+pub fn load_index(dir: &Path) -> Option<LibraryIndex> {
+    let bytes = fs::read(index_path(dir)).ok()?;
+    from_bytes::<LibraryIndex>(&bytes).filter(|index| Path::new(&index.dir) == dir)
+}
+
+/// Save `index` for the next startup
+pub fn save_index(dir: &Path, index: &LibraryIndex) -> Result<(), LibraryError> {
+    let path = index_path(dir);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| LibraryError::IoError(e.to_string()))?;
+    }
+    fs::write(&path, to_bytes(index)).map_err(|e| LibraryError::IoError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::index::scan_library;
+
+    #[test]
+    fn test_index_cache() {
+        let dir = std::env::temp_dir().join("akef_combonavi_cache_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.txt"), "#,物理,,|\n2,リーフォン,戦技,|").unwrap();
+        assert!(load_index(&dir).is_none());
+
+        let index = scan_library(&dir, None).unwrap();
+        save_index(&dir, &index).unwrap();
+        let loaded = load_index(&dir).unwrap();
+        assert_eq!(loaded.entries.len(), 1);
+        assert_eq!(loaded.entries[0].title, "物理");
+
+        // The cache folder is not part of the library
+        assert_eq!(scan_library(&dir, Some(&loaded)).unwrap().entries.len(), 1);
+
+        fs::write(index_path(&dir), "garbage").unwrap();
+        assert!(load_index(&dir).is_none());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,232 @@
+//! Combo library index
+//!
+//! Scans the combo directory and summarizes every combo file (title,
+//! metadata, step count) for the library browser. Rescans reuse the entries
+//! of files that have not changed since the previous scan.
+
+use super::LibraryError;
+use crate::combo::{parse_combo_library_file, ComboMetadata, InputType};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// File extensions treated as combo files
+const COMBO_EXTENSIONS: [&str; 3] = ["txt", "yaml", "yml"];
+
+/// A combo file in the library
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryEntry {
+    pub path: String,
+    /// Path relative to the library directory (for display and grouping)
+    pub relative_path: String,
+    /// Title of the first combo
+    pub title: String,
+    pub metadata: ComboMetadata,
+    /// Combo names of a multi-combo (`##`) file; empty for single combos
+    pub combos: Vec<String>,
+    /// Operators used by the steps (and the metadata team), in first-use order
+    pub characters: Vec<String>,
+    /// Distinct skill types of the steps
+    pub skill_types: Vec<String>,
+    /// Header tags of every combo and the tags of their steps
+    pub tags: Vec<String>,
+    pub step_count: usize,
+    /// Last modification time in milliseconds since the Unix epoch
+    pub modified_ms: u64,
+    /// Parse error, if the file does not load
+    pub error: Option<String>,
+}
+
+/// Cached summary of the combo directory
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LibraryIndex {
+    pub dir: String,
+    /// Entries sorted by relative path
+    pub entries: Vec<LibraryEntry>,
+}
+
+/// Whether `path` looks like a combo file
+fn is_combo_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| COMBO_EXTENSIONS.iter().any(|c| e.eq_ignore_ascii_case(c)))
+}
+
+/// Collect combo files under `dir`, skipping hidden folders such as `.backups`
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), LibraryError> {
+    let entries = fs::read_dir(dir).map_err(|e| LibraryError::IoError(e.to_string()))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if path.is_dir() {
+            if !hidden {
+                collect_files(&path, files)?;
+            }
+        } else if !hidden && is_combo_file(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Append `value` unless it is empty or already listed
+fn push_unique(list: &mut Vec<String>, value: &str) {
+    let value = value.trim();
+    if !value.is_empty() && !list.iter().any(|v| v == value) {
+        list.push(value.to_string());
+    }
+}
+
+/// Summarize one combo file
+fn index_file(path: &Path, dir: &Path, modified_ms: u64) -> LibraryEntry {
+    let relative_path = path
+        .strip_prefix(dir)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/");
+    let mut entry = LibraryEntry {
+        path: path.to_string_lossy().to_string(),
+        relative_path,
+        title: String::new(),
+        metadata: ComboMetadata::default(),
+        combos: Vec::new(),
+        characters: Vec::new(),
+        skill_types: Vec::new(),
+        tags: Vec::new(),
+        step_count: 0,
+        modified_ms,
+        error: None,
+    };
+
+    match parse_combo_library_file(path) {
+        Ok(library) => {
+            if library.combos.len() > 1 {
+                entry.combos = library.names();
+            }
+            if let Some(first) = library.combos.first() {
+                entry.title = first.combo.title.clone();
+                entry.metadata = first.combo.metadata.clone();
+                entry.step_count = first.combo.step_count();
+            }
+            for named in &library.combos {
+                let combo = &named.combo;
+                for name in &combo.metadata.team {
+                    push_unique(&mut entry.characters, name);
+                }
+                for tag in &combo.metadata.tags {
+                    push_unique(&mut entry.tags, tag);
+                }
+                // Branch rows hold a prompt and options instead
+                let steps = combo
+                    .steps()
+                    .filter(|step| !matches!(step.input_type, InputType::Branch { .. }));
+                for step in steps {
+                    push_unique(&mut entry.characters, &step.character);
+                    push_unique(&mut entry.skill_types, &step.skill_type);
+                    for tag in &step.tags {
+                        push_unique(&mut entry.tags, tag);
+                    }
+                }
+            }
+        }
+        Err(e) => entry.error = Some(e.to_string()),
+    }
+    entry
+}
+
+/// Scan the combo directory
+///
+/// Entries from `previous` are reused for files whose modification time is
+/// unchanged. A missing directory yields an empty index.
+pub fn scan_library(
+    dir: &Path,
+    previous: Option<&LibraryIndex>,
+) -> Result<LibraryIndex, LibraryError> {
+    let mut files = Vec::new();
+    if dir.is_dir() {
+        collect_files(dir, &mut files)?;
+    }
+
+    let cached: HashMap<&str, &LibraryEntry> = previous
+        .map(|index| index.entries.iter().map(|e| (e.path.as_str(), e)).collect())
+        .unwrap_or_default();
+
+    let mut entries: Vec<LibraryEntry> = files
+        .iter()
+        .map(|path| {
+            let modified_ms = fs::metadata(path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default();
+            match cached.get(path.to_string_lossy().as_ref()) {
+                Some(entry) if entry.modified_ms == modified_ms => (*entry).clone(),
+                _ => index_file(path, dir, modified_ms),
+            }
+        })
+        .collect();
+    entries.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+
+    Ok(LibraryIndex {
+        dir: dir.to_string_lossy().to_string(),
+        entries,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_library() {
+        let dir = std::env::temp_dir().join("akef_combonavi_index_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("raid")).unwrap();
+        fs::create_dir_all(dir.join(".backups")).unwrap();
+        fs::write(
+            dir.join("a.txt"),
+            "@author: saica\n@tags: ボス\n#,物理,,|\n2,リーフォン,戦技,,,,バースト/ボス|\nE,チェン,連携,|",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("raid/boss.txt"),
+            "## 第1形態\n#,前半,,|\n2,リーフォン,戦技,|\n## 第2形態\n#,後半,,|\nE,チェン,連携,|",
+        )
+        .unwrap();
+        fs::write(dir.join("broken.txt"), "X,?,?,|").unwrap();
+        fs::write(dir.join("notes.md"), "not a combo").unwrap();
+        fs::write(dir.join(".backups/a.txt.1.bak"), "old").unwrap();
+
+        let index = scan_library(&dir, None).unwrap();
+        let paths: Vec<&str> = index
+            .entries
+            .iter()
+            .map(|e| e.relative_path.as_str())
+            .collect();
+        assert_eq!(paths, ["a.txt", "broken.txt", "raid/boss.txt"]);
+
+        assert_eq!(index.entries[0].title, "物理");
+        assert_eq!(index.entries[0].metadata.author.as_deref(), Some("saica"));
+        assert_eq!(index.entries[0].step_count, 2);
+        assert_eq!(index.entries[0].tags, ["ボス", "バースト"]);
+        assert!(index.entries[1].error.is_some());
+        assert_eq!(index.entries[2].combos, ["第1形態", "第2形態"]);
+        assert_eq!(index.entries[2].characters, ["リーフォン", "チェン"]);
+
+        // Unchanged files come from the previous index
+        let mut previous = index.clone();
+        previous.entries[0].title = "cached".to_string();
+        let rescanned = scan_library(&dir, Some(&previous)).unwrap();
+        assert_eq!(rescanned.entries[0].title, "cached");
+
+        assert!(scan_library(&dir.join("missing"), None)
+            .unwrap()
+            .entries
+            .is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,344 @@
+//! Combo library module
+//!
+//! Manages the user's combo directory and the sample combos bundled with the app.
+
+pub mod backup;
+pub mod cache;
+pub mod index;
+pub mod search;
+
+use crate::combo::encoding::read_text;
+use crate::combo::migrate::upgrade_content;
+use crate::combo::{
+    parse_combo_library_content, serialize_combo_file, serialize_combo_yaml, ComboFile,
+    ComboFormat, MigrationReport,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A sample combo embedded into the executable
+pub struct SampleCombo {
+    /// File name used when installing into the library
+    pub file_name: &'static str,
+    /// Combo file content
+    pub content: &'static str,
+}
+
+/// Curated sample rotations shipped with the application
+pub const SAMPLE_COMBOS: &[SampleCombo] = &[
+    SampleCombo {
+        file_name: "physical_burst.txt",
+        content: include_str!("../../resources/samples/physical_burst.txt"),
+    },
+    SampleCombo {
+        file_name: "electric_opener.txt",
+        content: include_str!("../../resources/samples/electric_opener.txt"),
+    },
+    SampleCombo {
+        file_name: "chain_loop.txt",
+        content: include_str!("../../resources/samples/chain_loop.txt"),
+    },
+];
+
+/// Copy the bundled sample combos into the library directory
+///
+/// Existing files are never overwritten. Returns the file names that were installed.
+pub fn install_sample_combos<P: AsRef<Path>>(dir: P) -> Result<Vec<String>, LibraryError> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir).map_err(|e| LibraryError::IoError(e.to_string()))?;
+
+    let mut installed = Vec::new();
+    for sample in SAMPLE_COMBOS {
+        let path = dir.join(sample.file_name);
+        if path.exists() {
+            continue;
+        }
+        fs::write(&path, sample.content).map_err(|e| LibraryError::IoError(e.to_string()))?;
+        installed.push(sample.file_name.to_string());
+    }
+
+    Ok(installed)
+}
+
+/// Write a combo file, backing up any existing file first
+///
+/// The format follows the file extension (see [`ComboFormat`]).
+///
+/// `retention` is the number of backups kept per file; `0` disables backups.
+pub fn save_combo_file<P: AsRef<Path>>(
+    path: P,
+    combo: &ComboFile,
+    retention: usize,
+) -> Result<(), LibraryError> {
+    let path = path.as_ref();
+    if retention > 0 {
+        backup::backup_file(path, retention)?;
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| LibraryError::IoError(e.to_string()))?;
+    }
+    let content = match ComboFormat::from_path(path) {
+        ComboFormat::Text => serialize_combo_file(combo),
+        ComboFormat::Yaml => {
+            serialize_combo_yaml(combo).map_err(|e| LibraryError::IoError(e.to_string()))?
+        }
+    };
+    fs::write(path, content).map_err(|e| LibraryError::IoError(e.to_string()))
+}
+
+/// Write a new combo file holding only a title row
+///
+/// Existing files are never overwritten.
+pub fn create_combo_file<P: AsRef<Path>>(path: P, title: &str) -> Result<(), LibraryError> {
+    let path = path.as_ref();
+    ensure_writable(path, false, 0)?;
+    let title = title.trim();
+    if title.is_empty() || title.contains([',', '|']) {
+        return Err(LibraryError::InvalidPath(title.to_string()));
+    }
+    save_combo_file(path, &ComboFile::new(title), 0)
+}
+
+/// Rewrite a text combo file written in an older format version
+///
+/// The old file is backed up first. Returns `None` when the file is already
+/// current; YAML files always are.
+pub fn upgrade_combo_file<P: AsRef<Path>>(
+    path: P,
+    retention: usize,
+) -> Result<Option<MigrationReport>, LibraryError> {
+    let path = path.as_ref();
+    if ComboFormat::from_path(path) != ComboFormat::Text {
+        return Ok(None);
+    }
+
+    let content = read_text(path).map_err(|e| LibraryError::IoError(e.to_string()))?;
+    let Some((upgraded, report)) =
+        upgrade_content(&content).map_err(|e| LibraryError::IoError(e.to_string()))?
+    else {
+        return Ok(None);
+    };
+    // Never replace a file with one that no longer loads
+    parse_combo_library_content(&upgraded).map_err(|e| LibraryError::IoError(e.to_string()))?;
+
+    if retention > 0 {
+        backup::backup_file(path, retention)?;
+    }
+    fs::write(path, upgraded).map_err(|e| LibraryError::IoError(e.to_string()))?;
+    Ok(Some(report))
+}
+
+/// Validate a bare file name (no directory components)
+fn validate_file_name(name: &str) -> Result<(), LibraryError> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() || trimmed == "." || trimmed == ".." || trimmed.contains(['/', '\\', ':'])
+    {
+        return Err(LibraryError::InvalidPath(name.to_string()));
+    }
+    Ok(())
+}
+
+/// Ensure `path` is an existing file
+fn ensure_exists(path: &Path) -> Result<(), LibraryError> {
+    if !path.is_file() {
+        return Err(LibraryError::NotFound(path.display().to_string()));
+    }
+    Ok(())
+}
+
+/// Ensure `dest` can be written to, honoring the overwrite flag
+///
+/// A file about to be overwritten is backed up first (when `retention > 0`),
+/// as for deletes.
+fn ensure_writable(dest: &Path, overwrite: bool, retention: usize) -> Result<(), LibraryError> {
+    if !dest.exists() {
+        return Ok(());
+    }
+    if !overwrite {
+        return Err(LibraryError::AlreadyExists(dest.display().to_string()));
+    }
+    if retention > 0 {
+        backup::backup_file(dest, retention)?;
+    }
+    Ok(())
+}
+
+/// Move a file, falling back to copy + delete across volumes
+fn relocate(
+    from: &Path,
+    to: &Path,
+    overwrite: bool,
+    retention: usize,
+) -> Result<PathBuf, LibraryError> {
+    ensure_exists(from)?;
+    if from == to {
+        return Ok(to.to_path_buf());
+    }
+    ensure_writable(to, overwrite, retention)?;
+
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).map_err(|e| LibraryError::IoError(e.to_string()))?;
+    }
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to).map_err(|e| LibraryError::IoError(e.to_string()))?;
+        fs::remove_file(from).map_err(|e| LibraryError::IoError(e.to_string()))?;
+    }
+    Ok(to.to_path_buf())
+}
+
+/// Rename a combo file within its directory. Returns the new path.
+pub fn rename_combo_file(
+    path: &Path,
+    new_name: &str,
+    overwrite: bool,
+    retention: usize,
+) -> Result<PathBuf, LibraryError> {
+    validate_file_name(new_name)?;
+    let dest = path
+        .parent()
+        .map(|p| p.join(new_name.trim()))
+        .unwrap_or_else(|| PathBuf::from(new_name.trim()));
+    relocate(path, &dest, overwrite, retention)
+}
+
+/// Move a combo file into another directory. Returns the new path.
+pub fn move_combo_file(
+    path: &Path,
+    dest_dir: &Path,
+    overwrite: bool,
+    retention: usize,
+) -> Result<PathBuf, LibraryError> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| LibraryError::InvalidPath(path.display().to_string()))?;
+    relocate(path, &dest_dir.join(name), overwrite, retention)
+}
+
+/// Copy a combo file next to the original under a new name. Returns the new path.
+pub fn duplicate_combo_file(
+    path: &Path,
+    new_name: &str,
+    overwrite: bool,
+    retention: usize,
+) -> Result<PathBuf, LibraryError> {
+    validate_file_name(new_name)?;
+    ensure_exists(path)?;
+    let dest = path
+        .parent()
+        .map(|p| p.join(new_name.trim()))
+        .unwrap_or_else(|| PathBuf::from(new_name.trim()));
+    if dest == path {
+        return Err(LibraryError::AlreadyExists(dest.display().to_string()));
+    }
+    ensure_writable(&dest, overwrite, retention)?;
+
+    fs::copy(path, &dest).map_err(|e| LibraryError::IoError(e.to_string()))?;
+    Ok(dest)
+}
+
+/// Delete a combo file
+///
+/// A backup is kept (when `retention > 0`) so accidental deletes can be restored.
+pub fn delete_combo_file(path: &Path, retention: usize) -> Result<(), LibraryError> {
+    ensure_exists(path)?;
+    if retention > 0 {
+        backup::backup_file(path, retention)?;
+    }
+    fs::remove_file(path).map_err(|e| LibraryError::IoError(e.to_string()))
+}
+
+/// Library errors
+#[derive(Debug, Clone)]
+pub enum LibraryError {
+    IoError(String),
+    InvalidPath(String),
+    NotFound(String),
+    AlreadyExists(String),
+}
+
+impl std::fmt::Display for LibraryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LibraryError::IoError(msg) => write!(f, "IO error: {}", msg),
+            LibraryError::InvalidPath(path) => write!(f, "Invalid path: {}", path),
+            LibraryError::NotFound(path) => write!(f, "Not found: {}", path),
+            LibraryError::AlreadyExists(path) => write!(f, "Already exists: {}", path),
+        }
+    }
+}
+
+impl std::error::Error for LibraryError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combo::parse_combo_content;
+
+    #[test]
+    fn test_sample_combos_parse() {
+        for sample in SAMPLE_COMBOS {
+            let combo = parse_combo_content(sample.content).unwrap();
+            assert!(!combo.title.is_empty(), "{} has no title", sample.file_name);
+        }
+    }
+
+    #[test]
+    fn test_install_does_not_overwrite() {
+        let dir = std::env::temp_dir().join("akef_combonavi_install_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("chain_loop.txt"), "custom").unwrap();
+
+        let installed = install_sample_combos(&dir).unwrap();
+        assert_eq!(installed.len(), SAMPLE_COMBOS.len() - 1);
+        assert_eq!(
+            fs::read_to_string(dir.join("chain_loop.txt")).unwrap(),
+            "custom"
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_file_management() {
+        let dir = std::env::temp_dir().join("akef_combonavi_manage_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.txt");
+        fs::write(&path, "a").unwrap();
+        fs::write(dir.join("b.txt"), "b").unwrap();
+
+        assert!(matches!(
+            rename_combo_file(&path, "b.txt", false, 1),
+            Err(LibraryError::AlreadyExists(_))
+        ));
+        assert!(rename_combo_file(&path, "../c.txt", false, 1).is_err());
+
+        let copy = duplicate_combo_file(&path, "c.txt", false, 1).unwrap();
+        let moved = move_combo_file(&copy, &dir.join("sub"), false, 1).unwrap();
+        assert_eq!(fs::read_to_string(&moved).unwrap(), "a");
+        assert!(!copy.exists());
+
+        // The overwritten file is backed up
+        let renamed = rename_combo_file(&path, "b.txt", true, 1).unwrap();
+        assert_eq!(fs::read_to_string(&renamed).unwrap(), "a");
+        assert_eq!(backup::list_backups(&renamed).unwrap().len(), 1);
+
+        let created = dir.join("new.txt");
+        create_combo_file(&created, "新規").unwrap();
+        let combo = parse_combo_content(&fs::read_to_string(&created).unwrap()).unwrap();
+        assert_eq!(combo.title, "新規");
+        assert_eq!(combo.step_count(), 0);
+        assert!(matches!(
+            create_combo_file(&created, "新規"),
+            Err(LibraryError::AlreadyExists(_))
+        ));
+
+        delete_combo_file(&renamed, 1).unwrap();
+        assert!(!renamed.exists());
+        assert_eq!(backup::list_backups(&renamed).unwrap().len(), 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,203 @@
+//! Combo library search
+//!
+//! Fuzzy matching over the library index for the quick switcher. Query
+//! characters must appear in order but not necessarily next to each other,
+//! so `pb` finds `physical_burst.txt`.
+
+use super::index::{LibraryEntry, LibraryIndex};
+use serde::{Deserialize, Serialize};
+
+/// Narrow the results down; unset filters match everything
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchFilters {
+    /// Operator used in the combo
+    pub character: Option<String>,
+    pub tag: Option<String>,
+    pub skill_type: Option<String>,
+}
+
+/// A matching library entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub entry: LibraryEntry,
+    /// Higher is a better match
+    pub score: u32,
+}
+
+/// Fuzzy match `query` against `text`, case-insensitively
+///
+/// Returns `None` unless every query character appears in order. Consecutive
+/// runs, matches at the start and whole-substring matches score higher.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let query: Vec<char> = query
+        .to_lowercase()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+    let lower = text.to_lowercase();
+    let text: Vec<char> = lower.chars().collect();
+
+    let mut score = 0;
+    let mut matched = 0;
+    let mut previous = None;
+    for (i, &c) in text.iter().enumerate() {
+        if matched == query.len() {
+            break;
+        }
+        if c != query[matched] {
+            continue;
+        }
+        score += 10;
+        if i == 0 {
+            score += 10;
+        }
+        if previous.is_some_and(|p| p + 1 == i) {
+            score += 5;
+        }
+        previous = Some(i);
+        matched += 1;
+    }
+    if matched < query.len() {
+        return None;
+    }
+
+    let needle: String = query.iter().collect();
+    if lower == needle {
+        score += 50;
+    } else if lower.contains(&needle) {
+        score += 20;
+    }
+    Some(score)
+}
+
+/// Best score of `query` over several fields
+fn best_score<'a>(query: &str, fields: impl IntoIterator<Item = &'a String>) -> Option<u32> {
+    fields
+        .into_iter()
+        .filter_map(|field| fuzzy_score(query, field))
+        .max()
+}
+
+/// Whether an optional filter matches any of `fields`
+fn filter_matches(filter: &Option<String>, fields: &[String]) -> bool {
+    match filter.as_deref().map(str::trim) {
+        None | Some("") => true,
+        Some(filter) => best_score(filter, fields).is_some(),
+    }
+}
+
+/// Search the library, best matches first
+///
+/// The query is matched against titles (weighted highest), combo names, file
+/// paths, operators, tags and descriptions. An empty query lists every entry
+/// that passes the filters. Files that failed to parse are left out.
+pub fn search_library(
+    index: &LibraryIndex,
+    query: &str,
+    filters: &SearchFilters,
+) -> Vec<SearchResult> {
+    let mut results: Vec<SearchResult> = index
+        .entries
+        .iter()
+        .filter(|entry| entry.error.is_none())
+        .filter(|entry| {
+            filter_matches(&filters.character, &entry.characters)
+                && filter_matches(&filters.tag, &entry.tags)
+                && filter_matches(&filters.skill_type, &entry.skill_types)
+        })
+        .filter_map(|entry| {
+            let title = fuzzy_score(query, &entry.title).map(|s| s * 2);
+            let names = best_score(query, entry.combos.iter().chain([&entry.relative_path]));
+            let details = best_score(
+                query,
+                entry
+                    .characters
+                    .iter()
+                    .chain(&entry.tags)
+                    .chain(&entry.metadata.description),
+            );
+            let score = [title, names, details].into_iter().flatten().max()?;
+            Some(SearchResult {
+                entry: entry.clone(),
+                score,
+            })
+        })
+        .collect();
+
+    results.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.entry.relative_path.cmp(&b.entry.relative_path))
+    });
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combo::ComboMetadata;
+
+    fn entry(path: &str, title: &str, characters: &[&str], tags: &[&str]) -> LibraryEntry {
+        LibraryEntry {
+            path: path.to_string(),
+            relative_path: path.to_string(),
+            title: title.to_string(),
+            metadata: ComboMetadata {
+                tags: tags.iter().map(|t| t.to_string()).collect(),
+                ..ComboMetadata::default()
+            },
+            combos: Vec::new(),
+            characters: characters.iter().map(|c| c.to_string()).collect(),
+            skill_types: vec!["戦技".to_string()],
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            step_count: 1,
+            modified_ms: 0,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_search_library() {
+        assert!(fuzzy_score("pb", "Physical Burst").is_some());
+        assert!(fuzzy_score("bp", "Physical Burst").is_none());
+        assert!(fuzzy_score("phys", "physical").unwrap() > fuzzy_score("phys", "p_h_y_s").unwrap());
+
+        let index = LibraryIndex {
+            dir: String::new(),
+            entries: vec![
+                entry("chain_loop.txt", "チェンループ", &["チェン"], &["loop"]),
+                entry(
+                    "physical_burst.txt",
+                    "物理バースト",
+                    &["リーフォン", "チェン"],
+                    &["boss"],
+                ),
+                entry("electric.txt", "電気", &["アークライト"], &["boss", "aoe"]),
+            ],
+        };
+
+        let results = search_library(&index, "チェン", &SearchFilters::default());
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].entry.path, "chain_loop.txt");
+
+        let filters = SearchFilters {
+            tag: Some("boss".to_string()),
+            ..SearchFilters::default()
+        };
+        let results = search_library(&index, "", &filters);
+        assert_eq!(results.len(), 2);
+
+        let filters = SearchFilters {
+            character: Some("リーフォン".to_string()),
+            tag: Some("boss".to_string()),
+            skill_type: Some("戦技".to_string()),
+        };
+        let results = search_library(&index, "phys", &filters);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].entry.path, "physical_burst.txt");
+    }
+}
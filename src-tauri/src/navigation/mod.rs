@@ -0,0 +1,146 @@
+//! Step navigation
+//!
+//! Every move through a combo, whether from input, hotkeys, branch choices or
+//! edits, goes through [`Navigator`], which keeps the current step index and
+//! the loop pass counters in step. Moving forward past the last step starts
+//! over at the first (after loop blocks have repeated); moving back stops at
+//! the first step; phase jumps wrap around both ways. The step reached is
+//! sent through an [`UpdateSink`] as [`UPDATE_EVENT`].
+
+use crate::combo::{ComboFile, LoopState};
+use serde::Serialize;
+
+/// Event the current step is sent as whenever it changes
+pub const UPDATE_EVENT: &str = "combo-update";
+
+/// Receiver of the current step whenever it changes (the frontend in the app)
+pub trait UpdateSink {
+    /// Send the new current step as [`UPDATE_EVENT`]
+    fn send_update<T: Serialize + Clone>(&self, current: &T);
+}
+
+/// A move through the combo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// The step after the current one, repeating loop blocks
+    Next,
+    /// The step before the current one
+    Previous,
+    /// The first step, with all loop passes forgotten
+    First,
+    /// A given step (branch targets, edits), clamped to the last one
+    To(usize),
+    /// First step of the next or previous phase
+    Phase { forward: bool },
+}
+
+impl Step {
+    /// One step forward or back
+    pub fn one(forward: bool) -> Self {
+        if forward {
+            Self::Next
+        } else {
+            Self::Previous
+        }
+    }
+}
+
+/// Position in the loaded combo
+#[derive(Debug, Clone, Default)]
+pub struct Navigator {
+    index: usize,
+    /// Pass counters of the loop blocks in the combo
+    loops: LoopState,
+}
+
+impl Navigator {
+    /// Index of the current step
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Start over at the first step (e.g. for a newly loaded combo)
+    pub fn restart(&mut self) {
+        self.index = 0;
+        self.loops.reset();
+    }
+
+    /// Apply `step` to the position in `combo`; false if there was nowhere to go
+    pub fn apply(&mut self, combo: &ComboFile, step: Step) -> bool {
+        let total = combo.step_count();
+        if total == 0 {
+            return false;
+        }
+        let target = match step {
+            Step::Next => {
+                self.index = self.loops.next_index(combo, self.index);
+                return true;
+            }
+            Step::First => {
+                self.restart();
+                return true;
+            }
+            Step::Previous if self.index == 0 => return false,
+            Step::Previous => self.index - 1,
+            Step::To(index) => index.min(total - 1),
+            Step::Phase { forward } => {
+                let phases = combo.phases();
+                if phases.is_empty() {
+                    return false;
+                }
+                let phase = match (combo.phase_at(self.index), forward) {
+                    (Some(p), true) => (p + 1) % phases.len(),
+                    (Some(p), false) => (p + phases.len() - 1) % phases.len(),
+                    // Steps before the first header
+                    (None, true) => 0,
+                    (None, false) => phases.len() - 1,
+                };
+                phases[phase].start
+            }
+        };
+        self.index = target;
+        self.loops.jump(combo, target);
+        true
+    }
+
+    /// Index of the step that would follow the current one, leaving the
+    /// loop counters untouched
+    pub fn peek_next(&self, combo: &ComboFile) -> usize {
+        self.loops.clone().next_index(combo, self.index)
+    }
+
+    /// Passes left of the innermost loop containing the current step
+    pub fn loop_remaining(&self, combo: &ComboFile) -> Option<u32> {
+        self.loops.remaining(combo, self.index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combo::parse_combo_content;
+
+    #[test]
+    fn test_navigation_policy() {
+        let combo =
+            parse_combo_content("LOOP 2\n1,ポグ,戦技,|\nENDLOOP\n2,チェン,戦技,|\n3,管理人,戦技,|")
+                .unwrap();
+        let mut nav = Navigator::default();
+        assert!(!nav.apply(&combo, Step::Previous));
+
+        // The loop block repeats before moving on, then the end wraps around
+        let mut visited = Vec::new();
+        for _ in 0..4 {
+            assert!(nav.apply(&combo, Step::Next));
+            visited.push(nav.index());
+        }
+        assert_eq!(visited, vec![0, 1, 2, 0]);
+
+        assert!(nav.apply(&combo, Step::To(99)));
+        assert_eq!(nav.index(), 2);
+        assert!(nav.apply(&combo, Step::Previous));
+        assert_eq!(nav.index(), 1);
+        // No phases to jump between
+        assert!(!nav.apply(&combo, Step::Phase { forward: true }));
+    }
+}
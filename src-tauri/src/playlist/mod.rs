@@ -0,0 +1,184 @@
+//! Combo playlists
+//!
+//! A playlist file lists combo files to play one after another, so a full
+//! raid's rotations can be chained without picking each file by hand.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Comment line prefixes (same as combo files)
+const COMMENT_MARKERS: [&str; 2] = ["//", ";"];
+
+/// A combo file referenced by a playlist
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlaylistEntry {
+    /// Combo file path (relative entries are resolved against the playlist)
+    pub path: String,
+    /// Combo to select in a multi-combo (`##`) file
+    pub combo: Option<String>,
+}
+
+/// A loaded playlist and the entry being played
+#[derive(Debug, Clone)]
+pub struct Playlist {
+    /// Name shown to the player (the playlist file stem)
+    pub name: String,
+    pub entries: Vec<PlaylistEntry>,
+    position: usize,
+}
+
+/// Playlist progress sent to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaylistStatus {
+    pub name: String,
+    /// 0-based index of the current entry
+    pub position: usize,
+    pub total: usize,
+    pub entry: PlaylistEntry,
+}
+
+impl Playlist {
+    /// Current entry
+    pub fn current(&self) -> &PlaylistEntry {
+        &self.entries[self.position]
+    }
+
+    /// Move to the next entry; returns `None` after the last one
+    pub fn advance(&mut self) -> Option<&PlaylistEntry> {
+        if self.position + 1 >= self.entries.len() {
+            return None;
+        }
+        self.position += 1;
+        Some(self.current())
+    }
+
+    /// Point entries for the combo file at `old` to `new` after it moved
+    pub fn relocate(&mut self, old: &Path, new: &str) {
+        for entry in &mut self.entries {
+            if Path::new(&entry.path) == old {
+                entry.path = new.to_string();
+            }
+        }
+    }
+
+    /// Progress snapshot
+    pub fn status(&self) -> PlaylistStatus {
+        PlaylistStatus {
+            name: self.name.clone(),
+            position: self.position,
+            total: self.entries.len(),
+            entry: self.current().clone(),
+        }
+    }
+}
+
+/// Parse playlist content
+///
+/// Format: one combo file per line, optionally followed by `,NAME` to pick a
+/// combo from a multi-combo file. Blank lines and `//` or `;` comments are
+/// skipped. Relative paths are resolved against `base_dir`.
+pub fn parse_playlist_content(
+    content: &str,
+    base_dir: &Path,
+) -> Result<Vec<PlaylistEntry>, PlaylistError> {
+    let mut entries = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || COMMENT_MARKERS.iter().any(|m| line.starts_with(m)) {
+            continue;
+        }
+
+        let (path, combo) = match line.split_once(',') {
+            Some((path, combo)) => (path.trim(), Some(combo.trim().to_string())),
+            None => (line, None),
+        };
+        if path.is_empty() || combo.as_deref() == Some("") {
+            return Err(PlaylistError::InvalidEntry {
+                line: i + 1,
+                content: line.to_string(),
+            });
+        }
+
+        entries.push(PlaylistEntry {
+            path: base_dir.join(path).to_string_lossy().to_string(),
+            combo,
+        });
+    }
+
+    if entries.is_empty() {
+        return Err(PlaylistError::Empty);
+    }
+    Ok(entries)
+}
+
+/// Load a playlist file, starting at its first entry
+pub fn load_playlist<P: AsRef<Path>>(path: P) -> Result<Playlist, PlaylistError> {
+    let path = path.as_ref();
+    let content =
+        std::fs::read_to_string(path).map_err(|e| PlaylistError::IoError(e.to_string()))?;
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+
+    Ok(Playlist {
+        name: path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        entries: parse_playlist_content(&content, base_dir)?,
+        position: 0,
+    })
+}
+
+/// Playlist errors
+#[derive(Debug, Clone)]
+pub enum PlaylistError {
+    /// No combo files listed
+    Empty,
+    InvalidEntry {
+        line: usize,
+        content: String,
+    },
+    IoError(String),
+}
+
+impl std::fmt::Display for PlaylistError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlaylistError::Empty => write!(f, "Playlist has no combo files"),
+            PlaylistError::InvalidEntry { line, content } => {
+                write!(f, "Invalid playlist entry at line {}: {}", line, content)
+            }
+            PlaylistError::IoError(msg) => write!(f, "IO error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for PlaylistError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_playlist() {
+        let content = "// レイド\nboss1.txt\n\nboss2.txt, 第2形態\n";
+        let entries = parse_playlist_content(content, Path::new("combos")).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(Path::new(&entries[0].path), Path::new("combos/boss1.txt"));
+        assert_eq!(entries[1].combo.as_deref(), Some("第2形態"));
+        assert!(matches!(
+            parse_playlist_content("// empty", Path::new("")),
+            Err(PlaylistError::Empty)
+        ));
+
+        let mut playlist = Playlist {
+            name: "raid".to_string(),
+            entries,
+            position: 0,
+        };
+        assert!(playlist.advance().is_some());
+        assert!(playlist.advance().is_none());
+        assert_eq!(playlist.status().position, 1);
+    }
+}
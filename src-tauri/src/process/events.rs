@@ -0,0 +1,131 @@
+//! Process start/exit notifications
+//!
+//! On Windows the monitor subscribes to WMI process creation and deletion
+//! events for the game, so the process list is only read when something
+//! happened instead of on a timer. Where WMI isn't available the monitor
+//! falls back to polling.
+
+/// Longest wait for an event, so a stopped monitor notices in time
+#[cfg(target_os = "windows")]
+const EVENT_TIMEOUT_MS: i32 = 1000;
+
+/// Outcome of waiting for a process event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub enum EventWait {
+    /// The process started or exited
+    Event,
+    /// Nothing happened within the timeout
+    TimedOut,
+    /// The subscription failed; the caller should poll instead
+    Failed,
+}
+
+/// Subscription to start/exit events of one process
+#[cfg(target_os = "windows")]
+pub struct ProcessEvents {
+    events: windows::Win32::System::Wmi::IEnumWbemClassObject,
+}
+
+#[cfg(target_os = "windows")]
+impl ProcessEvents {
+    /// Subscribe to starts and exits of `process` (an executable name). Must
+    /// be called on the thread that waits for the events.
+    pub fn subscribe(process: &str) -> Option<Self> {
+        match Self::connect(process) {
+            Ok(events) => Some(events),
+            Err(e) => {
+                eprintln!("WMI process events unavailable, polling instead: {}", e);
+                None
+            }
+        }
+    }
+
+    fn connect(process: &str) -> windows::core::Result<Self> {
+        use windows::core::{BSTR, PCWSTR};
+        use windows::Win32::System::Com::{
+            CoCreateInstance, CoInitializeEx, CoSetProxyBlanket, CLSCTX_INPROC_SERVER,
+            COINIT_MULTITHREADED, EOAC_NONE, RPC_C_AUTHN_LEVEL_CALL, RPC_C_IMP_LEVEL_IMPERSONATE,
+        };
+        use windows::Win32::System::Wmi::{
+            IWbemLocator, WbemLocator, WBEM_FLAG_FORWARD_ONLY, WBEM_FLAG_RETURN_IMMEDIATELY,
+        };
+
+        // Win32_System_Rpc constants, not worth another feature
+        const RPC_C_AUTHN_WINNT: u32 = 10;
+        const RPC_C_AUTHZ_NONE: u32 = 0;
+
+        // Modification events (CPU time, memory, ...) come in all the time
+        let query = format!(
+            "SELECT * FROM __InstanceOperationEvent WITHIN 1 \
+             WHERE (__CLASS = '__InstanceCreationEvent' OR __CLASS = '__InstanceDeletionEvent') \
+             AND TargetInstance ISA 'Win32_Process' AND TargetInstance.Name = '{}'",
+            process
+        );
+
+        unsafe {
+            CoInitializeEx(None, COINIT_MULTITHREADED)?;
+            let locator: IWbemLocator = CoCreateInstance(&WbemLocator, None, CLSCTX_INPROC_SERVER)?;
+            let services = locator.ConnectServer(
+                &BSTR::from("ROOT\\CIMV2"),
+                &BSTR::new(),
+                &BSTR::new(),
+                &BSTR::new(),
+                0,
+                &BSTR::new(),
+                None,
+            )?;
+            CoSetProxyBlanket(
+                &services,
+                RPC_C_AUTHN_WINNT,
+                RPC_C_AUTHZ_NONE,
+                PCWSTR::null(),
+                RPC_C_AUTHN_LEVEL_CALL,
+                RPC_C_IMP_LEVEL_IMPERSONATE,
+                None,
+                EOAC_NONE,
+            )?;
+            let events = services.ExecNotificationQuery(
+                &BSTR::from("WQL"),
+                &BSTR::from(query),
+                WBEM_FLAG_FORWARD_ONLY | WBEM_FLAG_RETURN_IMMEDIATELY,
+                None,
+            )?;
+            Ok(Self { events })
+        }
+    }
+
+    /// Block until the process starts or exits, for at most
+    /// [`EVENT_TIMEOUT_MS`]
+    pub fn wait(&self) -> EventWait {
+        let mut objects = [None];
+        let mut returned = 0;
+        let result = unsafe {
+            self.events
+                .Next(EVENT_TIMEOUT_MS, &mut objects, &mut returned)
+        };
+        if result.is_err() {
+            EventWait::Failed
+        } else if returned > 0 {
+            EventWait::Event
+        } else {
+            // WBEM_S_TIMEDOUT is a success code
+            EventWait::TimedOut
+        }
+    }
+}
+
+/// No process events here; the monitor polls
+#[cfg(not(target_os = "windows"))]
+pub struct ProcessEvents;
+
+#[cfg(not(target_os = "windows"))]
+impl ProcessEvents {
+    pub fn subscribe(_process: &str) -> Option<Self> {
+        None
+    }
+
+    pub fn wait(&self) -> EventWait {
+        EventWait::Failed
+    }
+}
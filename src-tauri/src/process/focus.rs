@@ -0,0 +1,96 @@
+//! Game window focus
+//!
+//! The monitor thread polls the foreground window; changes are sent as
+//! `game-focus-changed` and, unless turned off in the overlay settings, hide
+//! the overlay while another window (a browser after alt-tab, ...) is in front.
+//! Likewise the overlay goes away with the game when it exits, and comes back
+//! as the user left it when the game starts again.
+
+use super::ProcessMonitor;
+use crate::config::OverlaySettings;
+
+/// Whether the game (or this app) is the foreground window, as last polled
+#[derive(Debug, Default)]
+pub struct FocusMonitor {
+    /// `None` until the first poll
+    focused: Option<bool>,
+    /// Whether the game itself (not this app) was in front at the last poll
+    game_in_front: bool,
+}
+
+impl FocusMonitor {
+    /// Check the foreground window; the new state if it changed
+    pub fn poll(&mut self) -> Option<bool> {
+        self.game_in_front = ProcessMonitor::is_game_foreground();
+        self.observe(ProcessMonitor::is_game_active())
+    }
+
+    /// Whether the game itself was in front at the last poll
+    pub fn is_game_in_front(&self) -> bool {
+        self.game_in_front
+    }
+
+    /// Record whether the game is in front; the new state if it changed
+    pub fn observe(&mut self, focused: bool) -> Option<bool> {
+        if self.focused == Some(focused) {
+            return None;
+        }
+        self.focused = Some(focused);
+        Some(focused)
+    }
+
+    /// Whether the game was in front at the last poll
+    pub fn is_focused(&self) -> bool {
+        self.focused.unwrap_or(false)
+    }
+}
+
+/// Whether the overlay should be shown
+///
+/// `wanted` is the user's overlay toggle. With `hide_when_unfocused` off the
+/// overlay stays up while the game runs, whichever window is in front; with
+/// `hide_when_game_closed` on it never outlives the game (even with this app
+/// in front).
+pub fn overlay_shown(
+    wanted: bool,
+    focused: bool,
+    running: bool,
+    settings: &OverlaySettings,
+) -> bool {
+    if settings.hide_when_game_closed && !running {
+        return false;
+    }
+    wanted
+        && if settings.hide_when_unfocused {
+            focused
+        } else {
+            focused || running
+        }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_focus_changes_and_overlay_policy() {
+        let mut focus = FocusMonitor::default();
+        assert_eq!(focus.observe(false), Some(false));
+        assert_eq!(focus.observe(false), None);
+        assert_eq!(focus.observe(true), Some(true));
+        assert!(focus.is_focused());
+
+        // Alt-tabbed away from a running game
+        let mut settings = OverlaySettings::default();
+        assert!(!overlay_shown(true, false, true, &settings));
+        assert!(!overlay_shown(false, true, true, &settings));
+        settings.hide_when_unfocused = false;
+        assert!(overlay_shown(true, false, true, &settings));
+        assert!(!overlay_shown(true, false, false, &settings));
+
+        // Settings in front after the game exited
+        assert!(!overlay_shown(true, true, false, &settings));
+        settings.hide_when_game_closed = false;
+        assert!(overlay_shown(true, true, false, &settings));
+    }
+}
@@ -0,0 +1,79 @@
+//! Game launching
+//!
+//! The launcher is configured as a path (the game or its launcher
+//! executable, a shortcut) or a URI handled by another app (a store's
+//! `...://launch/...` link). Either is opened through the OS, like a double
+//! click, so the game starts the way the player normally starts it.
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// How long after a launch the game's start still counts as launched from
+/// the app (launchers may update the game first)
+pub const LAUNCH_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Whether a game start at `now` follows a launch from the app at `launched`
+///
+/// A launch the player cancelled in the launcher must not bring up the
+/// overlay when they start the game some other way much later.
+pub fn is_recent_launch(launched: Option<Instant>, now: Instant) -> bool {
+    launched.is_some_and(|at| now.saturating_duration_since(at) <= LAUNCH_TIMEOUT)
+}
+
+/// What to open to start the game
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LaunchTarget {
+    Path(PathBuf),
+    Uri(String),
+}
+
+impl LaunchTarget {
+    /// Target configured as `launcher` (`None` if it is empty). Quotes around
+    /// it, as left by Explorer's "Copy as path", are ignored.
+    pub fn parse(launcher: &str) -> Option<Self> {
+        let launcher = launcher.trim().trim_matches('"').trim();
+        if launcher.is_empty() {
+            return None;
+        }
+        // Drive letters (`C:\...`) aren't schemes
+        let is_uri = launcher
+            .split_once(':')
+            .is_some_and(|(scheme, _)| scheme.len() > 1 && !scheme.contains(['\\', '/']));
+        Some(if is_uri {
+            Self::Uri(launcher.to_string())
+        } else {
+            Self::Path(PathBuf::from(launcher))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_launch_targets() {
+        assert_eq!(LaunchTarget::parse("  "), None);
+        assert_eq!(
+            LaunchTarget::parse("\"C:\\Games\\Endfield\\Launcher.exe\""),
+            Some(LaunchTarget::Path(PathBuf::from(
+                "C:\\Games\\Endfield\\Launcher.exe"
+            )))
+        );
+        assert_eq!(
+            LaunchTarget::parse("steam://rungameid/12345"),
+            Some(LaunchTarget::Uri("steam://rungameid/12345".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_launch_goes_stale() {
+        let launched = Instant::now();
+        assert!(!is_recent_launch(None, launched));
+        assert!(is_recent_launch(Some(launched), launched + LAUNCH_TIMEOUT));
+        assert!(!is_recent_launch(
+            Some(launched),
+            launched + LAUNCH_TIMEOUT + Duration::from_secs(1)
+        ));
+    }
+}
@@ -1,8 +1,16 @@
 //! Process monitoring module
 //!
 //! Monitors for the Endfield.exe process to control overlay visibility.
+//! The process list is read when WMI reports a start or exit, or every few
+//! seconds where those events aren't available.
 
-use std::sync::atomic::{AtomicBool, Ordering};
+mod events;
+pub mod focus;
+pub mod launch;
+
+use events::{EventWait, ProcessEvents};
+
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
@@ -11,14 +19,43 @@ use sysinfo::System;
 /// Target process name to monitor
 const TARGET_PROCESS: &str = "Endfield.exe";
 
+/// Default interval between process list reads without process events
+pub const DEFAULT_PROCESS_INTERVAL_MS: u64 = 2000;
+
+/// Default interval between game focus checks
+pub const DEFAULT_FOCUS_INTERVAL_MS: u64 = 500;
+
+/// How much longer intervals get in low-power mode while the game is closed
+const LOW_POWER_FACTOR: u64 = 4;
+
+/// Shortest polling interval, whatever is configured
+const MIN_INTERVAL_MS: u64 = 100;
+
+/// Interval to poll at, stretched in low-power mode while the game isn't
+/// running
+pub fn poll_interval(interval_ms: u64, low_power: bool, running: bool) -> Duration {
+    let interval_ms = interval_ms.max(MIN_INTERVAL_MS);
+    if low_power && !running {
+        Duration::from_millis(interval_ms.saturating_mul(LOW_POWER_FACTOR))
+    } else {
+        Duration::from_millis(interval_ms)
+    }
+}
+
 /// Process monitor state
 pub struct ProcessMonitor {
     /// Whether the target process is currently running
     is_running: Arc<AtomicBool>,
+    /// Process id of the target process (0 while it isn't running)
+    game_pid: Arc<AtomicU32>,
     /// Handle to the monitoring thread
     _thread_handle: Option<thread::JoinHandle<()>>,
     /// Stop flag for the monitoring thread
     stop_flag: Arc<AtomicBool>,
+    /// Interval between process list reads without process events
+    interval_ms: Arc<AtomicU64>,
+    /// Poll less often while the game isn't running
+    low_power: Arc<AtomicBool>,
 }
 
 impl ProcessMonitor {
@@ -29,32 +66,77 @@ impl ProcessMonitor {
 
         Self {
             is_running,
+            game_pid: Arc::new(AtomicU32::new(0)),
             _thread_handle: None,
             stop_flag,
+            interval_ms: Arc::new(AtomicU64::new(DEFAULT_PROCESS_INTERVAL_MS)),
+            low_power: Arc::new(AtomicBool::new(false)),
         }
     }
 
-    /// Start monitoring for the target process
-    pub fn start(&mut self) {
+    /// Set the polling interval used without process events
+    pub fn set_polling(&self, interval_ms: u64, low_power: bool) {
+        self.interval_ms.store(interval_ms, Ordering::Relaxed);
+        self.low_power.store(low_power, Ordering::Relaxed);
+    }
+
+    /// Start monitoring for the target process. `on_change` is called with
+    /// the initial state and then whenever the process starts or exits.
+    pub fn start(&mut self, on_change: impl Fn(bool) + Send + 'static) {
         let is_running = self.is_running.clone();
+        let game_pid = self.game_pid.clone();
         let stop_flag = self.stop_flag.clone();
+        let interval_ms = self.interval_ms.clone();
+        let low_power = self.low_power.clone();
 
         let handle = thread::spawn(move || {
             let mut system = System::new();
+            let mut events = ProcessEvents::subscribe(TARGET_PROCESS);
+            let mut last = None;
 
             while !stop_flag.load(Ordering::Relaxed) {
                 // Refresh process list
                 system.refresh_processes(sysinfo::ProcessesToUpdate::All, true);
 
                 // Check if target process is running
-                let found = system.processes().values().any(|p| {
-                    p.name().to_string_lossy().to_lowercase() == TARGET_PROCESS.to_lowercase()
-                });
-
+                let pid = system
+                    .processes()
+                    .values()
+                    .find(|p| {
+                        p.name().to_string_lossy().to_lowercase() == TARGET_PROCESS.to_lowercase()
+                    })
+                    .map(|p| p.pid().as_u32());
+                let found = pid.is_some();
+
+                game_pid.store(pid.unwrap_or(0), Ordering::Relaxed);
                 is_running.store(found, Ordering::Relaxed);
+                if last != Some(found) {
+                    last = Some(found);
+                    on_change(found);
+                }
 
-                // Sleep before next check (2 seconds)
-                thread::sleep(Duration::from_secs(2));
+                // Wait for the next start/exit (noticing a stop between
+                // waits), or poll
+                let mut waited = false;
+                while let Some(subscription) = &events {
+                    match subscription.wait() {
+                        EventWait::Event => waited = true,
+                        EventWait::TimedOut if !stop_flag.load(Ordering::Relaxed) => continue,
+                        EventWait::TimedOut => waited = true,
+                        EventWait::Failed => {
+                            eprintln!("WMI process events failed, polling instead");
+                            events = None;
+                        }
+                    }
+                    break;
+                }
+                if !waited {
+                    thread::sleep(poll_interval(
+                        interval_ms.load(Ordering::Relaxed),
+                        low_power.load(Ordering::Relaxed),
+                        found,
+                    ));
+                }
             }
         });
 
@@ -66,6 +148,11 @@ impl ProcessMonitor {
         self.is_running.load(Ordering::Relaxed)
     }
 
+    /// Process id of the target process, if it is running
+    pub fn game_pid(&self) -> Option<u32> {
+        Some(self.game_pid.load(Ordering::Relaxed)).filter(|&pid| pid != 0)
+    }
+
     /// Stop the monitoring thread
     pub fn stop(&self) {
         self.stop_flag.store(true, Ordering::Relaxed);
@@ -82,10 +169,10 @@ impl ProcessMonitor {
             .any(|p| p.name().to_string_lossy().to_lowercase() == TARGET_PROCESS.to_lowercase())
     }
 
-    /// Check if the target process is the foreground window
+    /// File name of the process owning the foreground window
     #[cfg(target_os = "windows")]
-    pub fn is_game_active() -> bool {
-        use windows::Win32::Foundation::MAX_PATH;
+    fn foreground_process_name() -> Option<String> {
+        use windows::Win32::Foundation::{CloseHandle, MAX_PATH};
         use windows::Win32::System::Threading::{
             OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
             PROCESS_QUERY_LIMITED_INFORMATION,
@@ -97,14 +184,14 @@ impl ProcessMonitor {
         unsafe {
             let hwnd = GetForegroundWindow();
             if hwnd.0 == 0 {
-                return false;
+                return None;
             }
 
             let mut process_id = 0;
             GetWindowThreadProcessId(hwnd, Some(&mut process_id));
 
             if process_id == 0 {
-                return false;
+                return None;
             }
 
             // PROCESS_QUERY_LIMITED_INFORMATION is sufficient for QueryFullProcessImageName
@@ -122,6 +209,7 @@ impl ProcessMonitor {
                         windows::core::PWSTR(buffer.as_mut_ptr()),
                         &mut size,
                     );
+                    let _ = CloseHandle(handle);
 
                     if result.is_ok() {
                         let full_path = String::from_utf16_lossy(&buffer[..size as usize]);
@@ -130,29 +218,7 @@ impl ProcessMonitor {
                             .file_name()
                             .and_then(|n| n.to_str())
                             .unwrap_or("");
-
-                        // Simple case-insensitive check
-                        let name_lower = name.to_lowercase();
-                        let target_lower = TARGET_PROCESS.to_lowercase();
-                        let self_process = "akef-combonavi.exe";
-                        let self_lower = self_process.to_lowercase();
-
-                        let is_match = name_lower == target_lower || name_lower == self_lower;
-
-                        #[cfg(debug_assertions)]
-                        if is_match {
-                            println!("[DEBUG] Foreground match: {}", name);
-                        } else {
-                            // Print what we found if it's not a match, to help debugging
-                            // Only print occasionally or if it changes to avoid spam?
-                            // For now, let's just print it to see what's going on.
-                            println!(
-                                "[DEBUG] Foreground mismatch: {} (Target: {} or {})",
-                                name, TARGET_PROCESS, self_process
-                            );
-                        }
-
-                        return is_match;
+                        return Some(name.to_string());
                     } else {
                         #[cfg(debug_assertions)]
                         println!(
@@ -160,7 +226,7 @@ impl ProcessMonitor {
                             process_id
                         );
                     }
-                    false
+                    None
                 }
                 Err(e) => {
                     #[cfg(debug_assertions)]
@@ -168,12 +234,57 @@ impl ProcessMonitor {
                         "[DEBUG] Failed to open process (PID: {}): {:?}",
                         process_id, e
                     );
-                    false
+                    None
                 }
             }
         }
     }
 
+    /// Check if the target process (or this app's overlay) is the foreground window
+    #[cfg(target_os = "windows")]
+    pub fn is_game_active() -> bool {
+        let Some(name) = Self::foreground_process_name() else {
+            return false;
+        };
+
+        // Simple case-insensitive check
+        let name_lower = name.to_lowercase();
+        let target_lower = TARGET_PROCESS.to_lowercase();
+        let self_process = "akef-combonavi.exe";
+        let self_lower = self_process.to_lowercase();
+
+        let is_match = name_lower == target_lower || name_lower == self_lower;
+
+        #[cfg(debug_assertions)]
+        if is_match {
+            println!("[DEBUG] Foreground match: {}", name);
+        } else {
+            // Print what we found if it's not a match, to help debugging
+            // Only print occasionally or if it changes to avoid spam?
+            // For now, let's just print it to see what's going on.
+            println!(
+                "[DEBUG] Foreground mismatch: {} (Target: {} or {})",
+                name, TARGET_PROCESS, self_process
+            );
+        }
+
+        is_match
+    }
+
+    /// Check if the game itself is the foreground window
+    #[cfg(target_os = "windows")]
+    pub fn is_game_foreground() -> bool {
+        Self::foreground_process_name()
+            .is_some_and(|name| name.eq_ignore_ascii_case(TARGET_PROCESS))
+    }
+
+    /// The foreground window can't be checked here, so the game always counts
+    /// as in front
+    #[cfg(not(target_os = "windows"))]
+    pub fn is_game_foreground() -> bool {
+        true
+    }
+
     #[cfg(not(target_os = "windows"))]
     pub fn is_game_active() -> bool {
         // Fallback for non-Windows: just check if process exists
@@ -202,5 +313,26 @@ mod tests {
         let monitor = ProcessMonitor::new();
         // Initially, is_running should be false
         assert!(!monitor.is_target_running());
+        assert_eq!(monitor.game_pid(), None);
+    }
+
+    #[test]
+    fn test_low_power_stretches_intervals_while_closed() {
+        assert_eq!(poll_interval(500, false, false), Duration::from_millis(500));
+        assert_eq!(poll_interval(500, true, true), Duration::from_millis(500));
+        assert_eq!(poll_interval(500, true, false), Duration::from_millis(2000));
+        assert_eq!(poll_interval(0, false, true), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_monitor_reports_initial_state() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut monitor = ProcessMonitor::new();
+        monitor.start(move |running| {
+            let _ = tx.send(running);
+        });
+        // The game isn't running on a build machine
+        assert_eq!(rx.recv_timeout(Duration::from_secs(10)), Ok(false));
+        monitor.stop();
     }
 }
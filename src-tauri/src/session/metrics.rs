@@ -0,0 +1,101 @@
+//! Input latency metrics
+//!
+//! Latency is the time from a step being shown to the input that completes
+//! it. The most recent samples are kept so practice progress shows up in the
+//! percentiles instead of being averaged away by the whole session.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Number of latency samples kept
+pub const LATENCY_HISTORY: usize = 200;
+
+/// Latency of one input, sent live as `input-latency`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputLatency {
+    /// Step the input completed
+    pub index: usize,
+    pub latency_ms: u64,
+}
+
+/// Summary of the recent latency samples
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputMetrics {
+    /// Number of samples the summary covers
+    pub count: usize,
+    pub mean_ms: Option<f64>,
+    pub best_ms: Option<u64>,
+    /// Median
+    pub p50_ms: Option<u64>,
+    pub p90_ms: Option<u64>,
+    pub p99_ms: Option<u64>,
+}
+
+/// Ring buffer of the last [`LATENCY_HISTORY`] latencies
+#[derive(Debug, Clone, Default)]
+pub struct LatencyHistory {
+    samples: VecDeque<Duration>,
+}
+
+impl LatencyHistory {
+    /// Add a sample, dropping the oldest once full
+    pub fn push(&mut self, latency: Duration) {
+        if self.samples.len() == LATENCY_HISTORY {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(latency);
+    }
+
+    /// Mean and percentiles of the kept samples
+    pub fn metrics(&self) -> InputMetrics {
+        let mut sorted: Vec<u64> = self
+            .samples
+            .iter()
+            .map(|latency| latency.as_millis() as u64)
+            .collect();
+        sorted.sort_unstable();
+        if sorted.is_empty() {
+            return InputMetrics::default();
+        }
+
+        // Nearest-rank percentile
+        let percentile = |p: usize| sorted[(sorted.len() * p).div_ceil(100) - 1];
+        let total: Duration = self.samples.iter().sum();
+        InputMetrics {
+            count: sorted.len(),
+            mean_ms: Some(total.as_secs_f64() * 1000.0 / sorted.len() as f64),
+            best_ms: Some(sorted[0]),
+            p50_ms: Some(percentile(50)),
+            p90_ms: Some(percentile(90)),
+            p99_ms: Some(percentile(99)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_cover_recent_samples() {
+        let mut history = LatencyHistory::default();
+        assert_eq!(history.metrics(), InputMetrics::default());
+
+        // Slow early samples fall out of the buffer
+        for _ in 0..LATENCY_HISTORY {
+            history.push(Duration::from_millis(2_000));
+        }
+        for ms in 1..=LATENCY_HISTORY as u64 {
+            history.push(Duration::from_millis(ms * 10));
+        }
+
+        let metrics = history.metrics();
+        assert_eq!(metrics.count, LATENCY_HISTORY);
+        assert_eq!(metrics.best_ms, Some(10));
+        assert_eq!(metrics.p50_ms, Some(1_000));
+        assert_eq!(metrics.p90_ms, Some(1_800));
+        assert_eq!(metrics.p99_ms, Some(1_980));
+        assert_eq!(metrics.mean_ms, Some(1_005.0));
+    }
+}
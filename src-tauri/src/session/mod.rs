@@ -0,0 +1,297 @@
+//! Practice session tracking
+//!
+//! Tracks the run timer, input rate (APM) and reaction times, pausing all of
+//! them while the player is idle so breaks don't pollute the statistics.
+
+pub mod metrics;
+pub mod timing;
+
+use metrics::{InputMetrics, LatencyHistory};
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Default inactivity period before the session counts as idle
+pub const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 30;
+
+/// Session statistics snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionStats {
+    /// Active (non-idle) time in milliseconds
+    pub active_ms: u64,
+    /// Number of inputs recorded while active
+    pub input_count: u64,
+    /// Inputs per active minute
+    pub apm: f64,
+    /// Number of step advancements with a measured reaction time
+    pub reaction_count: usize,
+    /// Mean reaction time in milliseconds
+    pub mean_reaction_ms: Option<f64>,
+    /// Whether the session is currently paused due to inactivity
+    pub idle: bool,
+}
+
+/// Timings of an input that advanced the combo
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Advance {
+    /// Time since the previous correct input, if the player went straight
+    /// from that one to this one
+    pub gap: Option<Duration>,
+    /// Time since the step was shown (`None` while idle)
+    pub latency: Option<Duration>,
+}
+
+/// Session tracker with idle detection
+#[derive(Debug, Clone)]
+pub struct SessionTracker {
+    /// Accumulated active time up to the last pause
+    active: Duration,
+    /// Start of the current active period (None while idle or before the first input)
+    resumed_at: Option<Instant>,
+    /// Time of the last relevant input
+    last_input: Option<Instant>,
+    /// Time the current step was shown (reaction time start)
+    step_shown_at: Option<Instant>,
+    /// Time of the previous correct input, while steps follow each other
+    last_advance: Option<Instant>,
+    /// Time the current step became current (unlike `step_shown_at`, not
+    /// moved by coming back from idle)
+    step_changed_at: Option<Instant>,
+    input_count: u64,
+    reaction_count: usize,
+    reaction_total: Duration,
+    /// Recent reaction times for latency metrics
+    latency: LatencyHistory,
+    idle_timeout: Duration,
+    idle: bool,
+}
+
+impl SessionTracker {
+    /// Create a new tracker
+    pub fn new(idle_timeout_secs: u64) -> Self {
+        Self {
+            active: Duration::ZERO,
+            resumed_at: None,
+            last_input: None,
+            step_shown_at: None,
+            last_advance: None,
+            step_changed_at: None,
+            input_count: 0,
+            reaction_count: 0,
+            reaction_total: Duration::ZERO,
+            latency: LatencyHistory::default(),
+            idle_timeout: Duration::from_secs(idle_timeout_secs),
+            idle: true,
+        }
+    }
+
+    /// Change the idle timeout
+    pub fn set_idle_timeout(&mut self, idle_timeout_secs: u64) {
+        self.idle_timeout = Duration::from_secs(idle_timeout_secs);
+    }
+
+    /// Whether the session is currently paused
+    pub fn is_idle(&self) -> bool {
+        self.idle
+    }
+
+    /// Record a relevant input. Returns true if this resumed an idle session.
+    pub fn record_input(&mut self, now: Instant) -> bool {
+        let resumed = self.idle;
+        if resumed {
+            self.idle = false;
+            self.resumed_at = Some(now);
+            // Reaction time restarts when the player comes back
+            if self.step_shown_at.is_some() {
+                self.step_shown_at = Some(now);
+            }
+            self.last_advance = None;
+        }
+        self.last_input = Some(now);
+        self.input_count += 1;
+        resumed
+    }
+
+    /// Record that a new step is displayed
+    ///
+    /// Jumping to a step breaks the chain of inputs timing grades measure.
+    pub fn record_step_shown(&mut self, now: Instant) {
+        self.step_shown_at = Some(now);
+        self.step_changed_at = Some(now);
+        self.last_advance = None;
+    }
+
+    /// Record a correct input that advanced the combo
+    pub fn record_advance(&mut self, now: Instant) -> Advance {
+        let latency = self
+            .step_shown_at
+            .filter(|_| !self.idle)
+            .map(|shown| now.saturating_duration_since(shown));
+        if let Some(latency) = latency {
+            self.reaction_count += 1;
+            self.reaction_total += latency;
+            self.latency.push(latency);
+        }
+        self.step_shown_at = Some(now);
+        self.step_changed_at = Some(now);
+        let gap = self
+            .last_advance
+            .map(|last| now.saturating_duration_since(last));
+        self.last_advance = Some(now);
+        Advance { gap, latency }
+    }
+
+    /// Whether the current step has been current for at least `timeout`
+    pub fn is_stalled(&self, now: Instant, timeout: Duration) -> bool {
+        self.step_changed_at
+            .is_some_and(|changed| now.saturating_duration_since(changed) >= timeout)
+    }
+
+    /// Latency summary of the recent advancements
+    pub fn input_metrics(&self) -> InputMetrics {
+        self.latency.metrics()
+    }
+
+    /// Pause the session if the player went idle. Returns true if it just became idle.
+    ///
+    /// The session is idle when no input arrived within the timeout, or when the
+    /// game is not focused.
+    pub fn check_idle(&mut self, now: Instant, game_focused: bool) -> bool {
+        if self.idle {
+            return false;
+        }
+
+        let inactive = self
+            .last_input
+            .map(|t| now.saturating_duration_since(t) >= self.idle_timeout)
+            .unwrap_or(true);
+        if !inactive && game_focused {
+            return false;
+        }
+
+        // Inactivity only counts up to the last input; unfocusing stops the clock now
+        let paused_at = if inactive {
+            self.last_input.unwrap_or(now)
+        } else {
+            now
+        };
+        if let Some(resumed) = self.resumed_at.take() {
+            self.active += paused_at.saturating_duration_since(resumed);
+        }
+        self.idle = true;
+        true
+    }
+
+    /// Get a statistics snapshot
+    pub fn stats(&self, now: Instant) -> SessionStats {
+        let mut active = self.active;
+        if let Some(resumed) = self.resumed_at {
+            active += now.saturating_duration_since(resumed);
+        }
+
+        let minutes = active.as_secs_f64() / 60.0;
+        let apm = if minutes > 0.0 {
+            self.input_count as f64 / minutes
+        } else {
+            0.0
+        };
+
+        let mean_reaction_ms = if self.reaction_count == 0 {
+            None
+        } else {
+            Some(self.reaction_total.as_secs_f64() * 1000.0 / self.reaction_count as f64)
+        };
+
+        SessionStats {
+            active_ms: active.as_millis() as u64,
+            input_count: self.input_count,
+            apm,
+            reaction_count: self.reaction_count,
+            mean_reaction_ms,
+            idle: self.idle,
+        }
+    }
+
+    /// Clear all statistics
+    pub fn reset(&mut self) {
+        *self = Self {
+            idle_timeout: self.idle_timeout,
+            ..Self::new(0)
+        };
+    }
+}
+
+impl Default for SessionTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_IDLE_TIMEOUT_SECS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_pauses_timer_and_reactions() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut session = SessionTracker::new(10);
+
+        assert!(session.record_input(at(0)));
+        session.record_step_shown(at(0));
+        session.record_advance(at(500));
+        session.record_input(at(1_000));
+
+        // No input for longer than the timeout: paused at the last input
+        assert!(session.check_idle(at(20_000), true));
+        assert!(!session.check_idle(at(21_000), true));
+        assert_eq!(session.stats(at(60_000)).active_ms, 1_000);
+
+        // Resume restarts the reaction timer instead of counting the break
+        assert!(session.record_input(at(60_000)));
+        // The break also ends the chain of inputs timing is measured on
+        assert_eq!(session.record_advance(at(60_200)).gap, None);
+        let stats = session.stats(at(61_000));
+        assert_eq!(stats.active_ms, 2_000);
+        assert_eq!(stats.reaction_count, 2);
+        assert_eq!(stats.mean_reaction_ms, Some(350.0));
+        assert_eq!(
+            session.record_advance(at(61_100)).gap,
+            Some(Duration::from_millis(900))
+        );
+        assert_eq!(session.input_metrics().count, 3);
+    }
+
+    #[test]
+    fn test_stall_ignores_idle_resume() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let timeout = Duration::from_secs(40);
+        let mut session = SessionTracker::new(10);
+        assert!(!session.is_stalled(at(0), timeout));
+
+        session.record_input(at(0));
+        session.record_advance(at(0));
+        session.check_idle(at(20_000), true);
+        session.record_input(at(30_000));
+        assert!(!session.is_stalled(at(39_000), timeout));
+        assert!(session.is_stalled(at(40_000), timeout));
+
+        session.record_step_shown(at(41_000));
+        assert!(!session.is_stalled(at(41_000), timeout));
+    }
+
+    #[test]
+    fn test_unfocus_pauses_immediately() {
+        let start = Instant::now();
+        let mut session = SessionTracker::new(30);
+        session.record_input(start);
+
+        assert!(session.check_idle(start + Duration::from_secs(2), false));
+        assert!(session.is_idle());
+        assert_eq!(
+            session.stats(start + Duration::from_secs(5)).active_ms,
+            2_000
+        );
+    }
+}
@@ -0,0 +1,65 @@
+//! Timing grades
+//!
+//! Steps with a gap annotation (`GAP_MS` column) expect their input a set
+//! time after the previous one. The measured gap is graded against it, like
+//! the judgement of a rhythm game.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Largest deviation from the expected gap graded as perfect
+pub const PERFECT_WINDOW_MS: u64 = 50;
+
+/// Largest deviation from the expected gap graded as good
+pub const GOOD_WINDOW_MS: u64 = 150;
+
+/// How close an input came to its expected timing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimingGrade {
+    Perfect,
+    Good,
+    Early,
+    Late,
+}
+
+/// Grade of one input, sent with `combo-update`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimingResult {
+    pub grade: TimingGrade,
+    /// Gap written in the combo file in milliseconds
+    pub expected_ms: u64,
+    /// Gap measured since the previous input in milliseconds
+    pub actual_ms: u64,
+}
+
+/// Grade an input that came `actual` after the previous one
+pub fn grade(expected_ms: u64, actual: Duration) -> TimingResult {
+    let actual_ms = actual.as_millis() as u64;
+    let grade = match actual_ms.abs_diff(expected_ms) {
+        d if d <= PERFECT_WINDOW_MS => TimingGrade::Perfect,
+        d if d <= GOOD_WINDOW_MS => TimingGrade::Good,
+        _ if actual_ms < expected_ms => TimingGrade::Early,
+        _ => TimingGrade::Late,
+    };
+    TimingResult {
+        grade,
+        expected_ms,
+        actual_ms,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grade() {
+        let ms = Duration::from_millis;
+        assert_eq!(grade(500, ms(530)).grade, TimingGrade::Perfect);
+        assert_eq!(grade(500, ms(380)).grade, TimingGrade::Good);
+        assert_eq!(grade(500, ms(200)).grade, TimingGrade::Early);
+        assert_eq!(grade(500, ms(900)).grade, TimingGrade::Late);
+        assert_eq!(grade(500, ms(900)).actual_ms, 900);
+    }
+}
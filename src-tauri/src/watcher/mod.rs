@@ -0,0 +1,96 @@
+//! Combo file watching
+//!
+//! Reloads the loaded combo when it is edited on disk, so combos can be
+//! tweaked in a text editor while testing.
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Quiet period after the last change before reloading
+///
+/// Editors often write a file in several steps (truncate, write, rename);
+/// waiting for them to finish avoids parsing a half-written file.
+pub const DEBOUNCE_MS: u64 = 150;
+
+/// Whether `event` changes the contents of `file`
+fn touches_file(event: &Event, file: &Path) -> bool {
+    matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+        && event
+            .paths
+            .iter()
+            .any(|p| p.file_name() == file.file_name())
+}
+
+/// Watch `path` and call `on_change` once per burst of writes
+///
+/// The parent directory is watched rather than the file itself because many
+/// editors save by replacing the file. Watching stops when the returned
+/// watcher is dropped.
+pub fn watch_file<F>(path: &Path, on_change: F) -> notify::Result<RecommendedWatcher>
+where
+    F: Fn() + Send + 'static,
+{
+    let file = PathBuf::from(path);
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+        if let Ok(event) = res {
+            if touches_file(&event, &file) {
+                let _ = tx.send(());
+            }
+        }
+    })?;
+    watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+
+    // Ends when the watcher (and with it the sender) is dropped
+    std::thread::spawn(move || {
+        let quiet = Duration::from_millis(DEBOUNCE_MS);
+        while rx.recv().is_ok() {
+            loop {
+                match rx.recv_timeout(quiet) {
+                    Ok(()) => continue,
+                    Err(mpsc::RecvTimeoutError::Timeout) => break,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                }
+            }
+            on_change();
+        }
+    });
+
+    Ok(watcher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, ModifyKind, RemoveKind};
+
+    #[test]
+    fn test_touches_file_filters_other_files() {
+        let file = Path::new("combos/boss.txt");
+        let event = |kind, name: &str| Event::new(kind).add_path(PathBuf::from(name));
+
+        assert!(touches_file(
+            &event(EventKind::Modify(ModifyKind::Any), "combos/boss.txt"),
+            file
+        ));
+        assert!(touches_file(
+            &event(EventKind::Create(CreateKind::File), "combos/boss.txt"),
+            file
+        ));
+        assert!(!touches_file(
+            &event(EventKind::Modify(ModifyKind::Any), "combos/other.txt"),
+            file
+        ));
+        assert!(!touches_file(
+            &event(EventKind::Remove(RemoveKind::File), "combos/boss.txt"),
+            file
+        ));
+    }
+}
@@ -0,0 +1,282 @@
+//! Game window tracking
+//!
+//! Finds the game's main window (Win32 `EnumWindows` / `GetWindowRect`), the
+//! visible top-level window of the game process, so the overlay can be
+//! anchored to one of its corners and follow the game when it is moved or
+//! resized. Positions are in physical pixels, like the overlay's.
+//!
+//! The window's style also tells how the game is displayed: the overlay can't
+//! be drawn over exclusive fullscreen, only over windowed and borderless.
+
+use serde::{Deserialize, Serialize};
+
+/// Position and size of a window on screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Corner of the game window the overlay keeps its distance to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnchorCorner {
+    #[default]
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl AnchorCorner {
+    fn is_right(self) -> bool {
+        matches!(self, Self::TopRight | Self::BottomRight)
+    }
+
+    fn is_bottom(self) -> bool {
+        matches!(self, Self::BottomLeft | Self::BottomRight)
+    }
+
+    /// Overlay position `offset` pixels in from this corner of `game`
+    /// (measured to the overlay's own corner on the same side)
+    pub fn position(self, game: WindowRect, overlay: (u32, u32), offset: (i32, i32)) -> (i32, i32) {
+        let x = if self.is_right() {
+            game.x + game.width as i32 - overlay.0 as i32 - offset.0
+        } else {
+            game.x + offset.0
+        };
+        let y = if self.is_bottom() {
+            game.y + game.height as i32 - overlay.1 as i32 - offset.1
+        } else {
+            game.y + offset.1
+        };
+        (x, y)
+    }
+
+    /// Offset from this corner of `game` of an overlay at `position`, the
+    /// inverse of [`Self::position`]
+    pub fn offset(self, game: WindowRect, overlay: (u32, u32), position: (i32, i32)) -> (i32, i32) {
+        let x = if self.is_right() {
+            game.x + game.width as i32 - overlay.0 as i32 - position.0
+        } else {
+            position.0 - game.x
+        };
+        let y = if self.is_bottom() {
+            game.y + game.height as i32 - overlay.1 as i32 - position.1
+        } else {
+            position.1 - game.y
+        };
+        (x, y)
+    }
+}
+
+impl WindowRect {
+    /// Whether this rect covers all of `other`
+    fn covers(self, other: WindowRect) -> bool {
+        self.x <= other.x
+            && self.y <= other.y
+            && self.x + self.width as i32 >= other.x + other.width as i32
+            && self.y + self.height as i32 >= other.y + other.height as i32
+    }
+}
+
+/// How the game is displayed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayMode {
+    Windowed,
+    /// Borderless window covering the monitor
+    Borderless,
+    /// Exclusive fullscreen, which the overlay can't be drawn over
+    ExclusiveFullscreen,
+}
+
+impl DisplayMode {
+    /// Mode of a game window at `window` on a monitor at `monitor`; `framed`
+    /// if it has a title bar or resizing border, `topmost` if it stays above
+    /// other windows
+    ///
+    /// A frameless window covering the monitor is fullscreen. Exclusive
+    /// fullscreen swap chains make their window topmost, borderless windows
+    /// stay in the normal z-order.
+    pub fn classify(window: WindowRect, monitor: WindowRect, framed: bool, topmost: bool) -> Self {
+        if framed || !window.covers(monitor) {
+            Self::Windowed
+        } else if topmost {
+            Self::ExclusiveFullscreen
+        } else {
+            Self::Borderless
+        }
+    }
+}
+
+/// Rect of a Win32 `RECT`
+#[cfg(target_os = "windows")]
+fn window_rect(rect: windows::Win32::Foundation::RECT) -> WindowRect {
+    WindowRect {
+        x: rect.left,
+        y: rect.top,
+        width: (rect.right - rect.left).max(0) as u32,
+        height: (rect.bottom - rect.top).max(0) as u32,
+    }
+}
+
+/// The main window of process `pid` (the game), unless it has none or it is
+/// minimized
+#[cfg(target_os = "windows")]
+fn game_window(pid: u32) -> Option<windows::Win32::Foundation::HWND> {
+    use windows::Win32::Foundation::{BOOL, HWND, LPARAM};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        EnumWindows, GetWindow, GetWindowThreadProcessId, IsIconic, IsWindowVisible, GW_OWNER,
+    };
+
+    /// Process to look for, and its window once found
+    struct Search {
+        pid: u32,
+        found: Option<HWND>,
+    }
+
+    unsafe extern "system" fn visit(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let search = &mut *(lparam.0 as *mut Search);
+        let mut pid = 0;
+        GetWindowThreadProcessId(hwnd, Some(&mut pid));
+        // Owned windows are dialogs and popups, not the main window
+        if pid == search.pid && IsWindowVisible(hwnd).as_bool() && GetWindow(hwnd, GW_OWNER).0 == 0
+        {
+            search.found = Some(hwnd);
+            return BOOL(0);
+        }
+        BOOL(1)
+    }
+
+    let mut search = Search { pid, found: None };
+    unsafe {
+        // Stopping early is reported as a failure, so the result says nothing
+        let _ = EnumWindows(Some(visit), LPARAM(&mut search as *mut Search as isize));
+        search.found.filter(|&hwnd| !IsIconic(hwnd).as_bool())
+    }
+}
+
+/// Position and size of the window of the game process `pid` (`None` if it
+/// isn't open or is minimized)
+#[cfg(target_os = "windows")]
+pub fn game_window_rect(pid: u32) -> Option<WindowRect> {
+    use windows::Win32::Foundation::RECT;
+    use windows::Win32::UI::WindowsAndMessaging::GetWindowRect;
+
+    let hwnd = game_window(pid)?;
+    let mut rect = RECT::default();
+    unsafe { GetWindowRect(hwnd, &mut rect) }.ok()?;
+    Some(window_rect(rect))
+}
+
+/// How the game process `pid` is displayed (`None` if its window isn't open
+/// or is minimized)
+#[cfg(target_os = "windows")]
+pub fn game_display_mode(pid: u32) -> Option<DisplayMode> {
+    use windows::Win32::Graphics::Gdi::{
+        GetMonitorInfoW, MonitorFromWindow, MONITORINFO, MONITOR_DEFAULTTONEAREST,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetWindowLongW, GWL_EXSTYLE, GWL_STYLE, WS_CAPTION, WS_EX_TOPMOST, WS_THICKFRAME,
+    };
+
+    let hwnd = game_window(pid)?;
+    let window = game_window_rect(pid)?;
+    unsafe {
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        let monitor = MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST);
+        if !GetMonitorInfoW(monitor, &mut info).as_bool() {
+            return None;
+        }
+        let style = GetWindowLongW(hwnd, GWL_STYLE) as u32;
+        let ex_style = GetWindowLongW(hwnd, GWL_EXSTYLE) as u32;
+        Some(DisplayMode::classify(
+            window,
+            window_rect(info.rcMonitor),
+            style & (WS_CAPTION.0 | WS_THICKFRAME.0) != 0,
+            ex_style & WS_EX_TOPMOST.0 != 0,
+        ))
+    }
+}
+
+/// The game window can't be found here
+#[cfg(not(target_os = "windows"))]
+pub fn game_window_rect(_pid: u32) -> Option<WindowRect> {
+    None
+}
+
+/// The game window can't be found here
+#[cfg(not(target_os = "windows"))]
+pub fn game_display_mode(_pid: u32) -> Option<DisplayMode> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anchor_corners_follow_the_game() {
+        let game = WindowRect {
+            x: 100,
+            y: 50,
+            width: 1920,
+            height: 1080,
+        };
+        let overlay = (400, 100);
+        assert_eq!(
+            AnchorCorner::TopLeft.position(game, overlay, (20, 10)),
+            (120, 60)
+        );
+        assert_eq!(
+            AnchorCorner::BottomRight.position(game, overlay, (20, 10)),
+            (1600, 1020)
+        );
+
+        // Dragging the overlay and moving the game keep the same offset
+        for corner in [AnchorCorner::TopRight, AnchorCorner::BottomLeft] {
+            let offset = corner.offset(game, overlay, (700, 300));
+            let moved = WindowRect { x: -500, ..game };
+            let (x, y) = corner.position(moved, overlay, offset);
+            assert_eq!((x, y), (100, 300));
+        }
+    }
+
+    #[test]
+    fn test_display_mode_from_window_style() {
+        let monitor = WindowRect {
+            x: 0,
+            y: 0,
+            width: 1920,
+            height: 1080,
+        };
+        let small = WindowRect {
+            width: 1280,
+            height: 720,
+            ..monitor
+        };
+        assert_eq!(
+            DisplayMode::classify(small, monitor, false, true),
+            DisplayMode::Windowed
+        );
+        // Maximized windows still have their title bar
+        assert_eq!(
+            DisplayMode::classify(monitor, monitor, true, false),
+            DisplayMode::Windowed
+        );
+        assert_eq!(
+            DisplayMode::classify(monitor, monitor, false, false),
+            DisplayMode::Borderless
+        );
+        assert_eq!(
+            DisplayMode::classify(monitor, monitor, false, true),
+            DisplayMode::ExclusiveFullscreen
+        );
+    }
+}
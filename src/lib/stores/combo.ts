@@ -6,6 +6,12 @@ import { writable, derived } from 'svelte/store';
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 
+/** Selectable path of a branch step */
+export interface BranchOption {
+  label: string;
+  target: number | null;
+}
+
 /** Command information from backend */
 export interface CurrentCommandInfo {
   index: number;
@@ -15,7 +21,265 @@ export interface CurrentCommandInfo {
   character: string;
   skill_type: string;
   memo: string;
+  /** Info rows (`!` lines) just before the step */
+  notes: string[];
   is_hold: boolean;
+  direction: Direction | null;
+  gap_ms: number | null;
+  optional: boolean;
+  group: StepGroup | null;
+  tags: string[];
+  /** Icon reference of the step; load the picture with `getStepIcon` */
+  icon: string | null;
+  /** Controller button glyph for the step's key (`null` without a controller) */
+  gamepad_glyph: string | null;
+  combo_tags: string[];
+  branch_options: BranchOption[];
+  loop_remaining: number | null;
+  phase: string | null;
+  dnd: boolean;
+  /** Timing grade of the input that completed the previous step */
+  timing: TimingResult | null;
+  /** Stage reached by the charged hold that completed the previous step */
+  charge: ChargeStageInfo | null;
+  /** Milliseconds between the input that completed the previous step and this update */
+  input_delay_ms: number | null;
+}
+
+/** Timing of an input against the step's gap annotation */
+export interface TimingResult {
+  grade: 'perfect' | 'good' | 'early' | 'late';
+  expected_ms: number;
+  actual_ms: number;
+}
+
+/** Hold (or wait step) progress payload from backend */
+export interface HoldProgressInfo {
+  progress: number;
+  remaining_ms: number;
+  target_ms: number;
+}
+
+/** Time from a step being shown to the input completing it (`input-latency` payload) */
+export interface InputLatency {
+  index: number;
+  latency_ms: number;
+}
+
+/** Latency summary of the recent inputs */
+export interface InputMetrics {
+  count: number;
+  mean_ms: number | null;
+  best_ms: number | null;
+  p50_ms: number | null;
+  p90_ms: number | null;
+  p99_ms: number | null;
+}
+
+/** Global key listener state (`input-listener-status` payload) */
+export interface ListenerStatus {
+  running: boolean;
+  error: string | null;
+  /** Milliseconds until the listener is restarted */
+  retry_in_ms: number | null;
+}
+
+/** Stage of a charged hold (`charge-stage` payload) */
+export interface ChargeStageInfo {
+  /** Stage reached, starting at 1 */
+  stage: number;
+  stages: number;
+}
+
+/** Presses of a multi-tap step (`tap-progress` payload) */
+export interface TapCountInfo {
+  presses: number;
+  /** Presses the step needs */
+  count: number;
+}
+
+/** Wrong key pressed in strict mode (`combo-miss` payload) */
+export interface ComboMiss {
+  /** Step that was current when the key was pressed */
+  index: number;
+  key: string;
+  /** Whether the combo went back to its first step */
+  reset: boolean;
+}
+
+/** Combo row as serialized by the backend */
+export type Direction = 'Forward' | 'Back' | 'Left' | 'Right';
+
+export interface ComboCommand {
+  key:
+    | { Number: number }
+    | 'Chain'
+    | 'HeavyAttack'
+    | 'MouseLeft'
+    | 'MouseRight'
+    | 'MouseMiddle'
+    | 'MouseX1'
+    | 'MouseX2'
+    | 'Dodge'
+    | 'Swap'
+    | { Wheel: 'Up' | 'Down' };
+  input_type:
+    | 'Tap'
+    | 'HoldStart'
+    | 'HoldRelease'
+    | 'LoopEnd'
+    | 'Comment'
+    | 'Info'
+    // duration_ms is null for holds following the hold threshold
+    | { Hold: { duration_ms: number | null } }
+    | { ChargedHold: { stages: number[] } }
+    | { Chord: ComboCommand['key'][] }
+    | { Sequence: { keys: ComboCommand['key'][]; window_ms: number } }
+    | { DoubleTap: { window_ms: number } }
+    | { MultiTap: { count: number; window_ms: number } }
+    | { Wait: { duration_ms: number } }
+    | { Branch: { options: BranchOption[] } }
+    | { LoopStart: { count: number } }
+    | { Include: { path: string } };
+  character: string;
+  skill_type: string;
+  memo: string;
+  direction: Direction | null;
+  gap_ms: number | null;
+  sp_cost: number | null;
+  sp_gain: number | null;
+  optional: boolean;
+  tags?: string[];
+  /** Image path or icon id shown with the step */
+  icon?: string;
+  /** Columns after the icon column, kept as written */
+  unknown_columns?: string[];
+  /** Line the row was read from (lets unchanged rows be saved as written) */
+  source?: SourceLine;
+  /** Cancel window (`GROUP` ... `ENDGROUP`) the step belongs to */
+  group_id: number | null;
+  is_title: boolean;
+}
+
+/** Position of a step within its cancel window */
+export interface StepGroup {
+  id: number;
+  position: number;
+  size: number;
+}
+
+export interface SourceLine {
+  text: string;
+  canonical: string;
+  /** Copy number of a row expanded from a `3xL` line */
+  repeat?: number;
+}
+
+/** SP at one step of the projected rotation */
+export interface SpStep {
+  index: number;
+  sp_before: number;
+  cost: number;
+  gain: number;
+  sp_after: number;
+  starved: boolean;
+}
+
+export interface SpProjection {
+  start: number;
+  steps: SpStep[];
+  lowest: number;
+  end: number;
+}
+
+export interface CharacterStats {
+  character: string;
+  steps: number;
+}
+
+export interface ComboStats {
+  step_count: number;
+  characters: CharacterStats[];
+  tap_steps: number;
+  hold_steps: number;
+  hold_ratio: number;
+  estimated_duration_ms: number;
+  timed_steps: number;
+  pass_steps: number;
+  phase_count: number;
+}
+
+/** Whole combo file */
+/** Provenance details from a combo file's header block */
+export interface ComboMetadata {
+  author?: string;
+  game_version?: string;
+  team?: string[];
+  created?: string;
+  updated?: string;
+  description?: string;
+  tags?: string[];
+}
+
+export type ExportFormat = 'markdown' | 'html';
+
+/** `@alias` short code for an operator name */
+export interface CharacterAlias {
+  alias: string;
+  name: string;
+}
+
+export interface ComboFile {
+  title: string;
+  metadata: ComboMetadata;
+  aliases: CharacterAlias[];
+  commands: ComboCommand[];
+  footer: string[];
+}
+
+/** Line skipped while loading a combo leniently */
+export interface ParseWarning {
+  line: number | null;
+  message: string;
+}
+
+export interface LenientLoadResult {
+  title: string;
+  warnings: ParseWarning[];
+}
+
+/** Line rewritten when upgrading an older combo file */
+export interface MigrationChange {
+  line: number;
+  before: string;
+  after: string;
+}
+
+/** Sent with the `combo-migrated` event */
+export interface MigrationReport {
+  from_version: number;
+  to_version: number;
+  changes: MigrationChange[];
+}
+
+/** Combo file validation finding; also sent with the `combo-unknown-names` event */
+export interface Diagnostic {
+  line: number;
+  kind:
+    | 'parse_error'
+    | 'key_out_of_range'
+    | 'empty_character'
+    | 'hold_too_short'
+    | 'missing_eof_marker'
+    | 'unpaired_hold'
+    | 'unknown_character'
+    | 'unknown_skill_type';
+  /** Stable code (`E001`, `W001`, ...) */
+  code: string;
+  severity: 'error' | 'warning';
+  message: string;
+  /** Likely intended value, e.g. the operator name closest to a typo */
+  suggestion?: string;
 }
 
 /** Config from backend */
@@ -24,6 +288,10 @@ export interface Config {
   key_bindings: {
     open_settings: string;
     toggle_overlay: string;
+    next_step: string;
+    previous_step: string;
+    reset_combo: string;
+    pause_tracking: string;
     normal_attack: string;
     chain_attack: string;
     operator1_skill: string;
@@ -31,6 +299,16 @@ export interface Config {
     operator3_skill: string;
     operator4_skill: string;
     heavy_attack: string;
+    dodge: string;
+  };
+  input: {
+    /** Time a key must be held for hold steps without their own duration */
+    hold_threshold_ms: number;
+    /** Window in which all keys of a chord step must be pressed */
+    chord_window_ms: number;
+    /** Repeated presses of a key within this window are ignored (0 = off) */
+    debounce_ms: number;
+    [key: string]: unknown;
   };
   overlay: {
     opacity: number;
@@ -38,8 +316,78 @@ export interface Config {
     y: number;
     width: number;
     height: number;
+    /** Hide the overlay while another window is in front of the game */
+    hide_when_unfocused: boolean;
+    /** Hide the overlay when the game exits and restore it on relaunch */
+    hide_when_game_closed: boolean;
+    /** Keep the overlay at a corner of the game window */
+    anchor_to_game: boolean;
+    anchor_corner: AnchorCorner;
+    /** Distance in from the anchor corner (physical pixels) */
+    anchor_offset_x: number;
+    anchor_offset_y: number;
+  };
+  polling: {
+    /** Interval between process list reads without process events */
+    process_interval_ms: number;
+    /** Interval between game focus checks */
+    focus_interval_ms: number;
+    /** Poll less often while the game isn't running */
+    low_power: boolean;
   };
   last_combo_file: string | null;
+  /** Path or URI opened to start the game */
+  game_launcher: string | null;
+}
+
+/** How the game is displayed; the overlay can't be drawn over exclusive fullscreen */
+export type DisplayMode = 'windowed' | 'borderless' | 'exclusive_fullscreen';
+
+/** Corner of the game window the overlay is anchored to */
+export type AnchorCorner = 'top_left' | 'top_right' | 'bottom_left' | 'bottom_right';
+
+/** Combo file referenced by a playlist */
+export interface LibraryEntry {
+  path: string;
+  relative_path: string;
+  title: string;
+  metadata: ComboMetadata;
+  combos: string[];
+  characters: string[];
+  skill_types: string[];
+  tags: string[];
+  step_count: number;
+  modified_ms: number;
+  error: string | null;
+}
+
+export interface LibraryIndex {
+  dir: string;
+  entries: LibraryEntry[];
+}
+
+export interface SearchFilters {
+  character?: string;
+  tag?: string;
+  skill_type?: string;
+}
+
+export interface SearchResult {
+  entry: LibraryEntry;
+  score: number;
+}
+
+export interface PlaylistEntry {
+  path: string;
+  combo: string | null;
+}
+
+/** Playlist progress (also sent with the `playlist-advanced` event) */
+export interface PlaylistStatus {
+  name: string;
+  position: number;
+  total: number;
+  entry: PlaylistEntry;
 }
 
 // Current command store
@@ -51,6 +399,12 @@ export const config = writable<Config | null>(null);
 // Game running status
 export const isGameRunning = writable<boolean>(false);
 
+// Whether the game window is in front
+export const isGameFocused = writable<boolean>(false);
+
+// How the game was last seen displayed (null until its window is found)
+export const displayMode = writable<DisplayMode | null>(null);
+
 // Overlay visibility
 export const overlayVisible = writable<boolean>(true);
 
@@ -63,6 +417,33 @@ export const errorMessage = writable<string | null>(null);
 // Hold progress (0.0 to 1.0)
 export const holdProgress = writable<number>(0);
 
+// Remaining hold time in milliseconds
+export const holdRemainingMs = writable<number>(0);
+
+// Last wrong key pressed in strict mode
+export const lastMiss = writable<ComboMiss | null>(null);
+
+// Global key listener state (null until it first fails)
+export const listenerStatus = writable<ListenerStatus | null>(null);
+
+// Whether combo tracking is paused (keys don't advance steps)
+export const trackingPaused = writable<boolean>(false);
+
+// Latency of the last input that completed a step
+export const lastLatency = writable<InputLatency | null>(null);
+
+// Stage the current charged hold has reached (null while not charging)
+export const chargeStage = writable<ChargeStageInfo | null>(null);
+
+// Presses of the current multi-tap step (null until its first press)
+export const tapCount = writable<TapCountInfo | null>(null);
+
+// Playlist being played
+export const playlistStatus = writable<PlaylistStatus | null>(null);
+
+// Lines skipped by the last lenient load
+export const parseWarnings = writable<ParseWarning[]>([]);
+
 // Derived: Progress percentage
 export const progress = derived(currentCommand, ($cmd) => {
   if (!$cmd || $cmd.total === 0) return 0;
@@ -75,6 +456,7 @@ export async function loadComboFile(path: string): Promise<string> {
   errorMessage.set(null);
   try {
     const title = await invoke<string>('load_combo_file', { path });
+    playlistStatus.set(null);
     await refreshCurrentCommand();
     return title;
   } catch (e) {
@@ -85,6 +467,88 @@ export async function loadComboFile(path: string): Promise<string> {
   }
 }
 
+/** Create a combo file with only a title row and load it */
+export async function createComboFile(path: string, title: string): Promise<string> {
+  errorMessage.set(null);
+  try {
+    const created = await invoke<string>('create_combo_file', { path, title });
+    playlistStatus.set(null);
+    await refreshCurrentCommand();
+    return created;
+  } catch (e) {
+    errorMessage.set(String(e));
+    throw e;
+  }
+}
+
+/** Load a combo file, skipping invalid lines instead of failing */
+export async function loadComboFileLenient(path: string): Promise<LenientLoadResult> {
+  isLoading.set(true);
+  errorMessage.set(null);
+  try {
+    const result = await invoke<LenientLoadResult>('load_combo_file_lenient', { path });
+    parseWarnings.set(result.warnings);
+    playlistStatus.set(null);
+    await refreshCurrentCommand();
+    return result;
+  } catch (e) {
+    errorMessage.set(String(e));
+    throw e;
+  } finally {
+    isLoading.set(false);
+  }
+}
+
+/** Load a combo from pasted text without saving a file */
+export async function loadComboFromText(content: string): Promise<string> {
+  isLoading.set(true);
+  errorMessage.set(null);
+  try {
+    const title = await invoke<string>('load_combo_from_text', { content });
+    playlistStatus.set(null);
+    await refreshCurrentCommand();
+    return title;
+  } catch (e) {
+    errorMessage.set(String(e));
+    throw e;
+  } finally {
+    isLoading.set(false);
+  }
+}
+
+/** Load a combo copied to the clipboard (e.g. shared in chat) */
+export async function loadComboFromClipboard(): Promise<string> {
+  const content = await navigator.clipboard.readText();
+  return await loadComboFromText(content);
+}
+
+/** Load a playlist and its first combo */
+export async function loadPlaylist(path: string): Promise<PlaylistStatus> {
+  isLoading.set(true);
+  errorMessage.set(null);
+  try {
+    const status = await invoke<PlaylistStatus>('load_playlist', { path });
+    playlistStatus.set(status);
+    await refreshCurrentCommand();
+    return status;
+  } catch (e) {
+    errorMessage.set(String(e));
+    throw e;
+  } finally {
+    isLoading.set(false);
+  }
+}
+
+/** Load the next combo of the playlist (null after the last one) */
+export async function nextComboInPlaylist(): Promise<PlaylistStatus | null> {
+  const status = await invoke<PlaylistStatus | null>('next_combo_in_playlist');
+  if (status) {
+    playlistStatus.set(status);
+    await refreshCurrentCommand();
+  }
+  return status;
+}
+
 /** Refresh current command from backend */
 export async function refreshCurrentCommand(): Promise<void> {
   try {
@@ -95,6 +559,26 @@ export async function refreshCurrentCommand(): Promise<void> {
   }
 }
 
+/** Insert a row into the playing combo before `index` */
+export async function insertCommand(index: number, command: ComboCommand): Promise<void> {
+  currentCommand.set(await invoke<CurrentCommandInfo | null>('insert_command', { index, command }));
+}
+
+/** Delete a row of the playing combo */
+export async function deleteCommand(index: number): Promise<void> {
+  currentCommand.set(await invoke<CurrentCommandInfo | null>('delete_command', { index }));
+}
+
+/** Move a row of the playing combo */
+export async function moveCommand(from: number, to: number): Promise<void> {
+  currentCommand.set(await invoke<CurrentCommandInfo | null>('move_command', { from, to }));
+}
+
+/** Replace a row of the playing combo */
+export async function updateCommand(index: number, command: ComboCommand): Promise<void> {
+  currentCommand.set(await invoke<CurrentCommandInfo | null>('update_command', { index, command }));
+}
+
 /** Advance to next command */
 export async function advanceCommand(): Promise<void> {
   try {
@@ -125,6 +609,157 @@ export async function resetCombo(): Promise<void> {
   }
 }
 
+/** Jump to the first step of the next phase */
+export async function nextPhase(): Promise<void> {
+  try {
+    const cmd = await invoke<CurrentCommandInfo | null>('next_phase');
+    if (cmd) currentCommand.set(cmd);
+  } catch (e) {
+    console.error('Failed to jump to next phase:', e);
+  }
+}
+
+/** Jump to the first step of the previous phase */
+export async function previousPhase(): Promise<void> {
+  try {
+    const cmd = await invoke<CurrentCommandInfo | null>('previous_phase');
+    if (cmd) currentCommand.set(cmd);
+  } catch (e) {
+    console.error('Failed to jump to previous phase:', e);
+  }
+}
+
+/** Pick an option of the current branch step */
+export async function chooseBranch(option: number): Promise<void> {
+  try {
+    const cmd = await invoke<CurrentCommandInfo>('choose_branch', { option });
+    currentCommand.set(cmd);
+  } catch (e) {
+    console.error('Failed to choose branch:', e);
+  }
+}
+
+/** Write a combo file (format follows the extension) */
+export async function saveComboFile(path: string, combo: ComboFile): Promise<void> {
+  await invoke('save_combo_file', { path, combo });
+}
+
+/** Lint a combo file without loading it */
+export async function validateComboFile(path: string): Promise<Diagnostic[]> {
+  return await invoke<Diagnostic[]>('validate_combo_file', { path });
+}
+
+/** Lint unsaved combo text */
+export async function lintCombo(content: string, format?: 'text' | 'yaml'): Promise<Diagnostic[]> {
+  return await invoke<Diagnostic[]>('lint_combo', { content, format });
+}
+
+/** Picture of a step's icon as a `data:` URL (`null` when the step has none) */
+export async function getStepIcon(index: number): Promise<string | null> {
+  return await invoke<string | null>('get_step_icon', { index });
+}
+
+/** Button glyph per key label (`E`, `1`, `Space`, ...) on the connected controller */
+export async function getGamepadGlyphs(): Promise<[string, string][]> {
+  return await invoke<[string, string][]>('get_gamepad_glyphs');
+}
+
+/** Mean and percentiles of the recent input latencies */
+export async function getInputMetrics(): Promise<InputMetrics> {
+  return await invoke<InputMetrics>('get_input_metrics');
+}
+
+/** Capture the next key press for a key binding; its name arrives as `key-captured` */
+export async function startKeyCapture(): Promise<void> {
+  await invoke('start_key_capture');
+}
+
+/** Stop waiting for a key binding press */
+export async function cancelKeyCapture(): Promise<void> {
+  await invoke('cancel_key_capture');
+}
+
+/** Start recording combo key presses */
+export async function startRecording(): Promise<void> {
+  await invoke('start_recording');
+}
+
+/** Stop recording and save the presses as a draft combo file; returns the step count */
+export async function stopRecording(path: string): Promise<number> {
+  return await invoke<number>('stop_recording', { path });
+}
+
+/** List the combo names in a file (several when it uses `##` sections) */
+export async function listCombosInFile(path: string): Promise<string[]> {
+  return await invoke<string[]>('list_combos_in_file', { path });
+}
+
+/** Switch to another combo in the loaded file */
+export async function selectCombo(name: string): Promise<CurrentCommandInfo | null> {
+  const cmd = await invoke<CurrentCommandInfo | null>('select_combo', { name });
+  currentCommand.set(cmd);
+  return cmd;
+}
+
+/** Get the metadata header of the loaded combo */
+export async function getComboMetadata(): Promise<ComboMetadata | null> {
+  return await invoke<ComboMetadata | null>('get_combo_metadata');
+}
+
+/** Combo library catalog (cached by the backend) */
+export async function listComboLibrary(): Promise<LibraryIndex> {
+  return await invoke<LibraryIndex>('list_combo_library');
+}
+
+/** Rescan the combo directory */
+export async function refreshComboLibrary(): Promise<LibraryIndex> {
+  return await invoke<LibraryIndex>('refresh_combo_library');
+}
+
+/** Fuzzy search the combo library, best matches first */
+export async function searchCombos(
+  query: string,
+  filters?: SearchFilters
+): Promise<SearchResult[]> {
+  return await invoke<SearchResult[]>('search_combos', { query, filters });
+}
+
+/** Running SP over one pass of the loaded rotation */
+export async function getSpProjection(startSp?: number, maxSp?: number): Promise<SpProjection> {
+  return await invoke<SpProjection>('get_sp_projection', { startSp, maxSp });
+}
+
+/** Summary numbers of a combo file for the library browser */
+export async function getComboStats(path: string, name?: string): Promise<ComboStats> {
+  return await invoke<ComboStats>('get_combo_stats', { path, name });
+}
+
+/** Write the loaded combo as a Markdown or HTML cheat sheet */
+export async function exportCombo(path: string, format: ExportFormat): Promise<void> {
+  await invoke('export_combo', { path, format });
+}
+
+export type MergeStrategy = 'concatenate' | 'interleave';
+
+/** Merge the phases of two combo files and open the result in the editor */
+export async function mergeComboFiles(
+  base: string,
+  other: string,
+  strategy?: MergeStrategy
+): Promise<void> {
+  await invoke('merge_combo_files', { base, other, strategy });
+}
+
+/** Pack the loaded combo into a one-line share code */
+export async function exportShareCode(): Promise<string> {
+  return await invoke<string>('export_share_code');
+}
+
+/** Write the share code of the loaded combo as a PNG QR code */
+export async function generateComboQr(path: string): Promise<void> {
+  await invoke('generate_combo_qr', { path });
+}
+
 /** Load config from backend */
 export async function loadConfig(): Promise<void> {
   try {
@@ -146,6 +781,37 @@ export async function saveConfig(newConfig: Config): Promise<void> {
   }
 }
 
+/** Bindings that share one key (`check_binding_conflicts` result) */
+export interface BindingConflict {
+  key: string;
+  /** Binding names, e.g. `toggle_overlay`, `operator1_skill`, `swap` */
+  bindings: string[];
+}
+
+/** Hotkeys in `keyBindings` that collide with other hotkeys or game keys */
+export async function checkBindingConflicts(
+  keyBindings: Config['key_bindings']
+): Promise<BindingConflict[]> {
+  return await invoke<BindingConflict[]>('check_binding_conflicts', { keyBindings });
+}
+
+/** Synthetic input for `inject_input_event` (debug builds only) */
+export type InjectedInput =
+  | { type: 'key_press'; key: string }
+  | { type: 'key_release'; key: string }
+  /** Wheel notches, positive = up */
+  | { type: 'wheel'; delta: number };
+
+/** Feed a synthetic key or mouse event through the input pipeline (debug builds only) */
+export async function injectInputEvent(event: InjectedInput): Promise<void> {
+  await invoke('inject_input_event', { event });
+}
+
+/** Start the game through the configured launcher; the overlay comes up once it runs */
+export async function launchGame(): Promise<void> {
+  await invoke('launch_game');
+}
+
 /** Check if game is running */
 export async function checkGameRunning(): Promise<boolean> {
   try {
@@ -158,6 +824,18 @@ export async function checkGameRunning(): Promise<boolean> {
   }
 }
 
+/** Load how the game is displayed */
+export async function loadDisplayMode(): Promise<DisplayMode | null> {
+  try {
+    const mode = await invoke<DisplayMode | null>('get_display_mode');
+    displayMode.set(mode);
+    return mode;
+  } catch (e) {
+    console.error('Failed to get display mode:', e);
+    return null;
+  }
+}
+
 /** Toggle overlay visibility */
 export async function toggleOverlay(): Promise<boolean> {
   try {
@@ -177,14 +855,63 @@ export async function initializeListeners(): Promise<void> {
     isGameRunning.set(event.payload);
   });
 
+  // Alt-tab in and out of the game
+  await listen<boolean>('game-focus-changed', (event) => {
+    isGameFocused.set(event.payload);
+  });
+
+  // Windowed / borderless / exclusive fullscreen
+  await listen<DisplayMode>('display-mode', (event) => {
+    displayMode.set(event.payload);
+  });
+
   // Listen for combo updates (from key input handler)
   await listen<CurrentCommandInfo>('combo-update', (event) => {
     currentCommand.set(event.payload);
     holdProgress.set(0); // Reset hold progress on new command
+    chargeStage.set(null);
+    tapCount.set(null);
   });
 
   // Listen for hold progress
-  await listen<number>('hold-progress', (event) => {
-    holdProgress.set(event.payload);
+  await listen<HoldProgressInfo>('hold-progress', (event) => {
+    holdProgress.set(event.payload.progress);
+    holdRemainingMs.set(event.payload.remaining_ms);
+  });
+
+  // Wait steps share the hold progress bar
+  await listen<HoldProgressInfo>('wait-progress', (event) => {
+    holdProgress.set(event.payload.progress);
+    holdRemainingMs.set(event.payload.remaining_ms);
+  });
+
+  // Key listener failures and restarts
+  await listen<ListenerStatus>('input-listener-status', (event) => {
+    listenerStatus.set(event.payload);
+  });
+
+  // Combo tracking paused/resumed
+  await listen<boolean>('tracking-paused', (event) => {
+    trackingPaused.set(event.payload);
+  });
+
+  // Latency of each input that completes a step
+  await listen<InputLatency>('input-latency', (event) => {
+    lastLatency.set(event.payload);
+  });
+
+  // Charged holds crossing a stage
+  await listen<ChargeStageInfo>('charge-stage', (event) => {
+    chargeStage.set(event.payload);
+  });
+
+  // Presses of a multi-tap step
+  await listen<TapCountInfo>('tap-progress', (event) => {
+    tapCount.set(event.payload);
+  });
+
+  // Wrong keys in strict mode
+  await listen<ComboMiss>('combo-miss', (event) => {
+    lastMiss.set(event.payload);
   });
 }